@@ -0,0 +1,168 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use base64::{decode, encode, DecodeError};
+use openssl::{
+    error::ErrorStack,
+    hash::{hash, MessageDigest},
+    rand::rand_bytes,
+    symm::{decrypt_aead, encrypt_aead, Cipher},
+};
+use serde::{Deserialize, Serialize};
+
+/// Size of the keys used to seal an envelope (AES-256).
+pub const KEY_SIZE: usize = 32;
+
+/// Label to derive the key that is used to encrypt the state of the service.
+pub const LABEL_STATE: &str = "ref-erx-fd-server-state";
+
+const IV_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+/// Data encrypted with AES-256-GCM.
+///
+/// The data is encrypted with a random data key, that is itself encrypted
+/// with the key-encryption-key passed to `Envelope::seal`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    pub key: Sealed,
+    pub data: Sealed,
+}
+
+/// Base64 encoded parts of a AES-256-GCM cipher text.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sealed {
+    pub iv: String,
+    pub tag: String,
+    pub cipher: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    OpenSslError(ErrorStack),
+    Base64Error(DecodeError),
+    InvalidKey,
+}
+
+impl Envelope {
+    pub fn seal(kek: &[u8], data: &[u8]) -> Result<Self, Error> {
+        let mut dek = [0u8; KEY_SIZE];
+        rand_bytes(&mut dek)?;
+
+        Ok(Self {
+            key: Sealed::seal(kek, &dek)?,
+            data: Sealed::seal(&dek, data)?,
+        })
+    }
+
+    pub fn open(&self, kek: &[u8]) -> Result<Vec<u8>, Error> {
+        let dek = self.key.open(kek)?;
+        let data = self.data.open(&dek)?;
+
+        Ok(data)
+    }
+}
+
+impl Sealed {
+    fn seal(key: &[u8], data: &[u8]) -> Result<Self, Error> {
+        if key.len() != KEY_SIZE {
+            return Err(Error::InvalidKey);
+        }
+
+        let mut iv = [0u8; IV_SIZE];
+        rand_bytes(&mut iv)?;
+
+        let mut tag = [0u8; TAG_SIZE];
+        let cipher = encrypt_aead(Cipher::aes_256_gcm(), key, Some(&iv), &[], data, &mut tag)?;
+
+        Ok(Self {
+            iv: encode(&iv),
+            tag: encode(&tag),
+            cipher: encode(&cipher),
+        })
+    }
+
+    fn open(&self, key: &[u8]) -> Result<Vec<u8>, Error> {
+        if key.len() != KEY_SIZE {
+            return Err(Error::InvalidKey);
+        }
+
+        let iv = decode(&self.iv)?;
+        let tag = decode(&self.tag)?;
+        let cipher = decode(&self.cipher)?;
+
+        let data = decrypt_aead(Cipher::aes_256_gcm(), key, Some(&iv), &[], &cipher, &tag)?;
+
+        Ok(data)
+    }
+}
+
+/// Derive a key-encryption-key from the passed secret (e.g. the private
+/// part of a key pair). The label is used to get different keys for
+/// different purposes from the same secret.
+pub fn derive_key(label: &str, secret: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut data = label.as_bytes().to_vec();
+    data.push(0);
+    data.extend_from_slice(secret);
+
+    let key = hash(MessageDigest::sha256(), &data)?;
+
+    Ok(key.to_vec())
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::OpenSslError(err) => write!(f, "OpenSSL Error: {}", err),
+            Self::Base64Error(err) => write!(f, "Base64 Error: {}", err),
+            Self::InvalidKey => write!(f, "Invalid Key: Expected {} bytes", KEY_SIZE),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl From<ErrorStack> for Error {
+    fn from(err: ErrorStack) -> Self {
+        Self::OpenSslError(err)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Self {
+        Self::Base64Error(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_open() {
+        let kek = derive_key("test", b"secret").unwrap();
+
+        let envelope = Envelope::seal(&kek, b"Hello World").unwrap();
+        assert_eq!(b"Hello World".to_vec(), envelope.open(&kek).unwrap());
+
+        let kek = derive_key("other", b"secret").unwrap();
+        assert!(envelope.open(&kek).is_err());
+    }
+}
@@ -19,5 +19,7 @@
 extern crate lazy_static;
 
 pub mod admission;
+pub mod envelope;
 pub mod jwt;
+pub mod migration;
 pub mod str;
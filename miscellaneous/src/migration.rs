@@ -0,0 +1,281 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::mem::take;
+
+use serde_json::{json, Value};
+
+/// Function that migrates a state from one version to the next one.
+type Migration = fn(&mut Value) -> Result<(), Error>;
+
+/// Migrations that are applied to a state step by step. The migration at
+/// index `n` migrates a state of version `n + 1` to version `n + 2`.
+const MIGRATIONS: &[Migration] = &[v1_to_v2, v2_to_v3];
+
+/// Current version of the state.
+pub const CURRENT_VERSION: usize = MIGRATIONS.len() + 1;
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownVersion(String),
+    UnsupportedVersion(usize),
+    InvalidState(&'static str),
+}
+
+/// Migrate the passed state to the current version.
+///
+/// Returns the version the state had before it was migrated.
+pub fn migrate(value: &mut Value) -> Result<usize, Error> {
+    let version = version(value)?;
+    if version > CURRENT_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    for migration in &MIGRATIONS[version - 1..] {
+        migration(value)?;
+    }
+
+    Ok(version)
+}
+
+/// Get the version of the passed state.
+///
+/// States that were written before the version was added to the file, or
+/// that are marked as 'old', are detected by their content.
+pub fn version(value: &Value) -> Result<usize, Error> {
+    match value.get("version").and_then(Value::as_str) {
+        None | Some("old") | Some("Old") if is_v1(value) => Ok(1),
+        None | Some("old") | Some("Old") => Ok(2),
+        Some(version) => version
+            .trim_start_matches(|c: char| c == 'v' || c == 'V')
+            .parse()
+            .ok()
+            .filter(|version| *version > 0)
+            .ok_or_else(|| Error::UnknownVersion(version.into())),
+    }
+}
+
+/// Returns `true` if the passed state is encrypted.
+pub fn is_encrypted(value: &Value) -> bool {
+    matches!(
+        value.get("version").and_then(Value::as_str),
+        Some("Encrypted") | Some("encrypted")
+    )
+}
+
+fn is_v1(value: &Value) -> bool {
+    items(value, "audit_events").any(|audit_event| {
+        audit_event
+            .pointer("/entity/what")
+            .and_then(Value::as_str)
+            .map(|what| what.starts_with('/'))
+            .unwrap_or(false)
+    })
+}
+
+/// Version 1 stored the text and the entity of audit events as plain strings.
+fn v1_to_v2(value: &mut Value) -> Result<(), Error> {
+    for audit_event in items_mut(value, "audit_events") {
+        if let Some(text) = audit_event.get_mut("text") {
+            let new = match text.as_str() {
+                Some("/Task/$activate Operation") => Some("TaskActivate"),
+                Some("/Task/$accept Operation") => Some("TaskAccept"),
+                Some("/Task/$reject Operation") => Some("TaskReject"),
+                Some("/Task/$close Operation") => Some("TaskClose"),
+                _ => None,
+            };
+
+            if let Some(new) = new {
+                *text = json!(new);
+            }
+        }
+
+        if let Some(what) = audit_event.pointer_mut("/entity/what") {
+            if let Some(s) = what.as_str().filter(|s| s.starts_with('/')) {
+                let mut parts = s[1..].splitn(2, '/');
+
+                *what = match (parts.next(), parts.next()) {
+                    (Some("Task"), Some(id)) => json!({ "Task": id }),
+                    (Some("Task"), None) => json!("Tasks"),
+                    (Some("MedicationDispense"), Some(id)) => json!({ "MedicationDispense": id }),
+                    (Some("MedicationDispense"), None) => json!("MedicationDispenses"),
+                    (_, _) => json!({ "Other": s }),
+                };
+            }
+        }
+    }
+
+    value["version"] = json!("V2");
+
+    Ok(())
+}
+
+/// Version 2 stored the whole history of each task.
+/// Version 3 only stores the latest version.
+fn v2_to_v3(value: &mut Value) -> Result<(), Error> {
+    for task in items_mut(value, "tasks") {
+        let task = match task.as_object_mut() {
+            Some(task) => task,
+            None => return Err(Error::InvalidState("Task must be an object!")),
+        };
+
+        let resource = task
+            .remove("history")
+            .as_mut()
+            .and_then(Value::as_array_mut)
+            .and_then(|history| history.pop())
+            .and_then(|mut version| version.get_mut("resource").map(take))
+            .ok_or(Error::InvalidState(
+                "State must contain at least one task version!",
+            ))?;
+
+        task.insert("task".into(), resource);
+        task.entry("communication_count").or_insert(json!(0));
+    }
+
+    value["version"] = json!("V3");
+
+    Ok(())
+}
+
+fn items<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+fn items_mut<'a>(value: &'a mut Value, key: &str) -> impl Iterator<Item = &'a mut Value> {
+    value
+        .get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::UnknownVersion(version) => write!(f, "Unknown state version: {}", version),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "State version {} is not supported!", version)
+            }
+            Self::InvalidState(err) => write!(f, "Invalid state: {}", err),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1_state() -> Value {
+        json!({
+            "tasks": [{
+                "history": [
+                    { "resource": { "id": "task-1", "status": "Ready" } },
+                    { "resource": { "id": "task-1", "status": "InProgress" } }
+                ]
+            }],
+            "audit_events": [{
+                "id": "audit-event-1",
+                "text": "/Task/$accept Operation",
+                "entity": { "what": "/Task/task-1" }
+            }, {
+                "id": "audit-event-2",
+                "text": "/Task/$close Operation",
+                "entity": { "what": "/MedicationDispense" }
+            }]
+        })
+    }
+
+    #[test]
+    fn detect_version() {
+        assert_eq!(version(&v1_state()).unwrap(), 1);
+
+        let mut state = v1_state();
+        state["version"] = json!("old");
+        assert_eq!(version(&state).unwrap(), 1);
+
+        // Old states without audit events in the format of version 1
+        assert_eq!(version(&json!({ "tasks": [] })).unwrap(), 2);
+        assert_eq!(version(&json!({ "version": "Old" })).unwrap(), 2);
+
+        assert_eq!(version(&json!({ "version": "V2" })).unwrap(), 2);
+        assert_eq!(version(&json!({ "version": "3" })).unwrap(), 3);
+
+        assert!(matches!(
+            version(&json!({ "version": "V0" })),
+            Err(Error::UnknownVersion(_))
+        ));
+        assert!(matches!(
+            version(&json!({ "version": "Encrypted" })),
+            Err(Error::UnknownVersion(_))
+        ));
+
+        assert!(is_encrypted(&json!({ "version": "Encrypted" })));
+        assert!(!is_encrypted(&json!({ "version": "V3" })));
+    }
+
+    #[test]
+    fn migrate_v1_to_current() {
+        let mut state = v1_state();
+
+        assert_eq!(migrate(&mut state).unwrap(), 1);
+        assert_eq!(
+            state,
+            json!({
+                "version": "V3",
+                "tasks": [{
+                    "task": { "id": "task-1", "status": "InProgress" },
+                    "communication_count": 0
+                }],
+                "audit_events": [{
+                    "id": "audit-event-1",
+                    "text": "TaskAccept",
+                    "entity": { "what": { "Task": "task-1" } }
+                }, {
+                    "id": "audit-event-2",
+                    "text": "TaskClose",
+                    "entity": { "what": "MedicationDispenses" }
+                }]
+            })
+        );
+
+        // Migrating the current version again does not change anything
+        let expected = state.clone();
+        assert_eq!(migrate(&mut state).unwrap(), CURRENT_VERSION);
+        assert_eq!(state, expected);
+    }
+
+    #[test]
+    fn migrate_invalid_state() {
+        let mut state = json!({ "version": "V4" });
+        assert!(matches!(
+            migrate(&mut state),
+            Err(Error::UnsupportedVersion(4))
+        ));
+
+        let mut state = json!({ "version": "V2", "tasks": [{ "history": [] }] });
+        assert!(matches!(migrate(&mut state), Err(Error::InvalidState(_))));
+    }
+}
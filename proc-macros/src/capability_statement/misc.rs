@@ -41,6 +41,7 @@ pub struct Interaction {
 pub struct SearchParam {
     pub name: TokenStream2,
     pub type_: TokenStream2,
+    pub documentation: Option<TokenStream2>,
 }
 
 pub struct Resource;
@@ -130,6 +131,7 @@ impl SearchParam {
 
         let mut name = None;
         let mut type_ = None;
+        let mut documentation = None;
 
         let it = group.stream().into_iter();
 
@@ -137,6 +139,7 @@ impl SearchParam {
             match key {
                 "name" => name = Some(value),
                 "type" => type_ = Some(value),
+                "documentation" => documentation = Some(value),
                 s => {
                     return Err(format!(
                         "Attribute 'search_param' has unexpected value: {}",
@@ -155,6 +158,7 @@ impl SearchParam {
             type_: type_
                 .ok_or("Attribute 'search_param' expect value for 'type'")?
                 .into(),
+            documentation: documentation.map(Into::into),
         })
     }
 }
@@ -229,10 +229,14 @@ impl ResourceMacro {
             let search_params = route.search_params.iter().map(|search_param| {
                 let name = &search_param.name;
                 let type_ = &search_param.type_;
+                let documentation = match &search_param.documentation {
+                    Some(documentation) => quote!(Some(#documentation)),
+                    None => quote!(None),
+                };
 
                 quote! {
                     #cfg
-                    update_resource_search_param(res, #name, #type_);
+                    update_resource_search_param(res, #name, #type_, #documentation);
                 }
             });
             let interactions = route.interactions.iter().map(|interaction| {
@@ -335,10 +339,12 @@ fn generate_helper() -> TokenStream {
             res: &mut resources::capability_statement::Resource,
             name: &str,
             type_: resources::capability_statement::SearchParamType,
+            documentation: Option<&str>,
         ) {
             let sp = resources::capability_statement::SearchParam {
                 name: name.into(),
                 type_,
+                documentation: documentation.map(Into::into),
             };
             res.search_param.push(sp);
         }
@@ -61,6 +61,7 @@ pub struct Entity {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Text {
     TaskGetManyPatient,
+    TaskGetManyPharmacy,
     #[serde(alias = "TaskGetPatient")]
     TaskGetOnePatient,
     #[serde(alias = "TaskGetRepresentative")]
@@ -70,6 +71,7 @@ pub enum Text {
     TaskActivate,
     TaskAccept,
     TaskReject,
+    TaskDispense,
     TaskClose,
     TaskAbortDoctor,
     TaskAbortPatient,
@@ -78,6 +80,10 @@ pub enum Text {
     #[serde(alias = "MedicationDispenseGetPatient")]
     MedicationDispenseGetOne,
     MedicationDispenseGetMany,
+    ChargeItemCreate,
+    ChargeItemGetOne,
+    ChargeItemGetMany,
+    ChargeItemDelete,
 
     Other(String),
 
@@ -93,6 +99,9 @@ pub enum What {
     MedicationDispenses,
     MedicationDispense(Id),
 
+    ChargeItems,
+    ChargeItem(Id),
+
     Other(String),
 
     #[serde(other)]
@@ -0,0 +1,31 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use serde::{Deserialize, Serialize};
+
+use super::primitives::Id;
+
+/// Binary content that is stored separately from the resource that
+/// references it (e.g. the attachment of a communication).
+///
+/// The data is base64 encoded.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Binary {
+    pub id: Id,
+    pub content_type: String,
+    pub data: String,
+}
@@ -57,6 +57,7 @@ pub struct Resource {
 pub struct SearchParam {
     pub name: String,
     pub type_: SearchParamType,
+    pub documentation: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -102,8 +103,10 @@ pub enum Type {
     Operation,
     Communication,
     MedicationDispense,
+    ChargeItem,
     AuditEvent,
     Device,
+    Binary,
 }
 
 #[allow(dead_code)]
@@ -182,6 +182,15 @@ impl Communication {
         }
     }
 
+    pub fn content_mut(&mut self) -> &mut Content {
+        match self {
+            Communication::InfoReq(inner) => &mut inner.payload.content,
+            Communication::Reply(inner) => &mut inner.payload.content,
+            Communication::DispenseReq(inner) => &mut inner.payload.content,
+            Communication::Representative(inner) => &mut inner.payload.content,
+        }
+    }
+
     pub fn set_id(&mut self, id: Option<Id>) {
         match self {
             Communication::InfoReq(inner) => inner.id = id,
@@ -208,4 +217,13 @@ impl Communication {
             Communication::Representative(inner) => inner.received = Some(value),
         }
     }
+
+    pub fn clear_received(&mut self) {
+        match self {
+            Communication::InfoReq(inner) => inner.received = None,
+            Communication::Reply(inner) => inner.received = None,
+            Communication::DispenseReq(inner) => inner.received = None,
+            Communication::Representative(inner) => inner.received = None,
+        }
+    }
 }
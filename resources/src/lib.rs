@@ -19,8 +19,10 @@
 extern crate lazy_static;
 
 pub mod audit_event;
+pub mod binary;
 pub mod bundle;
 pub mod capability_statement;
+pub mod charge_item;
 pub mod communication;
 pub mod composition;
 pub mod coverage;
@@ -44,7 +46,9 @@ pub mod task;
 pub mod types;
 
 pub use audit_event::AuditEvent;
+pub use binary::Binary;
 pub use capability_statement::CapabilityStatement;
+pub use charge_item::ChargeItem;
 pub use communication::Communication;
 pub use composition::Composition;
 pub use coverage::Coverage;
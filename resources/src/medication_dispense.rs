@@ -19,13 +19,15 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     misc::{Kvnr, PrescriptionId, TelematikId},
-    primitives::{DateTime, Id},
+    primitives::{DateTime, Id, Instant},
     Medication,
 };
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct MedicationDispense {
     pub id: Option<Id>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<Instant>,
     pub prescription_id: PrescriptionId,
     pub medication: Medication,
     pub subject: Kvnr,
@@ -18,6 +18,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::{
+    medication_request::MultiPrescription,
     misc::{Kvnr, PrescriptionId},
     primitives::{Date, DateTime, Id},
     types::{FlowType, PerformerType},
@@ -26,6 +27,10 @@ use super::{
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Task {
     pub id: Id,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<Id>,
+
     pub extension: Extension,
     pub identifier: Identifier,
     pub status: Status,
@@ -52,6 +57,9 @@ pub struct Extension {
     pub flow_type: FlowType,
     pub accept_date: Option<Date>,
     pub expiry_date: Option<Date>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multi_prescription: Option<MultiPrescription>,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
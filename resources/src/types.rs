@@ -31,6 +31,8 @@ pub enum FlowType {
     Betaeubungsmittel,
     TRezepte,
     DirekteZuweisung,
+    ApothekenpflichtigeArzneimittelPkv,
+    DirekteZuweisungPkv,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -45,6 +47,23 @@ pub enum DocumentType {
     Receipt,
 }
 
+impl FlowType {
+    /// Returns `true` if the prescription is passed from the prescriber to the
+    /// performer directly (flow types 169 and 209).
+    pub fn is_direct_assignment(&self) -> bool {
+        matches!(self, Self::DirekteZuweisung | Self::DirekteZuweisungPkv)
+    }
+
+    /// Returns `true` if the prescription is issued for a patient with a
+    /// private health insurance (flow types 200 and 209).
+    pub fn is_pkv(&self) -> bool {
+        matches!(
+            self,
+            Self::ApothekenpflichtigeArzneimittelPkv | Self::DirekteZuweisungPkv
+        )
+    }
+}
+
 impl From<FlowType> for usize {
     fn from(v: FlowType) -> Self {
         match v {
@@ -56,6 +75,8 @@ impl From<FlowType> for usize {
             FlowType::Betaeubungsmittel => 165,
             FlowType::TRezepte => 166,
             FlowType::DirekteZuweisung => 169,
+            FlowType::ApothekenpflichtigeArzneimittelPkv => 200,
+            FlowType::DirekteZuweisungPkv => 209,
         }
     }
 }
@@ -71,6 +92,8 @@ impl From<FlowType> for u64 {
             FlowType::Betaeubungsmittel => 165,
             FlowType::TRezepte => 166,
             FlowType::DirekteZuweisung => 169,
+            FlowType::ApothekenpflichtigeArzneimittelPkv => 200,
+            FlowType::DirekteZuweisungPkv => 209,
         }
     }
 }
@@ -88,6 +111,8 @@ impl TryFrom<usize> for FlowType {
             165 => Ok(Self::Betaeubungsmittel),
             166 => Ok(Self::TRezepte),
             169 => Ok(Self::DirekteZuweisung),
+            200 => Ok(Self::ApothekenpflichtigeArzneimittelPkv),
+            209 => Ok(Self::DirekteZuweisungPkv),
             value => Err(value),
         }
     }
@@ -106,6 +131,8 @@ impl TryFrom<u64> for FlowType {
             165 => Ok(Self::Betaeubungsmittel),
             166 => Ok(Self::TRezepte),
             169 => Ok(Self::DirekteZuweisung),
+            200 => Ok(Self::ApothekenpflichtigeArzneimittelPkv),
+            209 => Ok(Self::DirekteZuweisungPkv),
             value => Err(value),
         }
     }
@@ -124,6 +151,10 @@ impl Display for FlowType {
             Self::Betaeubungsmittel => write!(f, "Muster 16 (Betäubungsmittel)"),
             Self::TRezepte => write!(f, "Muster 16 (T-Rezepte)"),
             Self::DirekteZuweisung => write!(f, "Muster 16 (Direkte Zuweisung)"),
+            Self::ApothekenpflichtigeArzneimittelPkv => {
+                write!(f, "PKV (Apothekenpflichtige Arzneimittel)")
+            }
+            Self::DirekteZuweisungPkv => write!(f, "PKV (Direkte Zuweisung)"),
         }
     }
 }
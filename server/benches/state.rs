@@ -0,0 +1,287 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+//! Compares the throughput of concurrent task searches of a patient while a
+//! pharmacy accepts and closes tasks of other patients in the background,
+//! once with exclusive access to the state (`State::lock`) and once with
+//! shared access (`State::read`).
+//!
+//! Run with `cargo bench -p ref-erx-fd-server --bench state`.
+
+use std::convert::TryFrom;
+use std::fs::read;
+use std::time::Instant;
+
+use bytes::Bytes;
+use futures::future::{join_all, ready};
+use futures::stream::once;
+use openssl::{
+    asn1::Asn1Time,
+    ec::{EcGroup, EcKey},
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{PKey, Private},
+    x509::{X509NameBuilder, X509},
+};
+use ref_erx_fd_server::{fhir::decode::JsonDecode, service::XAccessCode, state::State};
+use resources::{
+    audit_event::{Agent, ParticipationRoleType},
+    misc::{Kvnr, ParticipantId, PrescriptionId, TelematikId},
+    primitives::Id,
+    task::TaskCreateParameters,
+    types::FlowType,
+    MedicationDispense,
+};
+use serde_json::{from_slice, json, to_vec, Value};
+use tokio::spawn;
+
+const TASKS: usize = 10_000;
+const PATIENTS: usize = 100;
+const READERS: usize = 8;
+const SEARCHES: usize = 100;
+const WRITES: usize = 50;
+
+const E_PRESCRIPTION: &str = "e-prescription";
+const TELEMATIK_ID: &str = "606358757";
+
+/// Activated task of a patient other than the searching one.
+struct SeededTask {
+    id: Id,
+    access_code: String,
+    prescription_id: PrescriptionId,
+    kvnr: Kvnr,
+}
+
+#[tokio::main]
+async fn main() {
+    let exclusive = run(false).await;
+    let shared = run(true).await;
+
+    println!("exclusive: {:>10.1} searches/s", exclusive);
+    println!("shared:    {:>10.1} searches/s", shared);
+    println!("speedup:   {:>10.2}x", shared / exclusive);
+}
+
+/// Run the benchmark and return the number of searches per second.
+async fn run(shared: bool) -> f64 {
+    let (state, tasks) = seed_state().await;
+    let medication_dispense = load_medication_dispense().await;
+
+    let start = Instant::now();
+
+    let writer = {
+        let state = state.clone();
+
+        spawn(async move {
+            for task in tasks.into_iter().take(WRITES) {
+                let secret = {
+                    let mut state = state.lock().await;
+                    let (accepted, _) = state
+                        .task_accept(
+                            task.id.clone(),
+                            XAccessCode(task.access_code),
+                            None,
+                            agent_pharmacy(),
+                        )
+                        .unwrap();
+
+                    accepted.identifier.secret.clone()
+                };
+
+                let mut medication_dispense = medication_dispense.clone();
+                medication_dispense.prescription_id = task.prescription_id;
+                medication_dispense.subject = task.kvnr;
+                medication_dispense.performer = TelematikId::new(TELEMATIK_ID);
+
+                state
+                    .lock()
+                    .await
+                    .task_close(
+                        task.id,
+                        secret,
+                        TelematikId::new(TELEMATIK_ID),
+                        vec![medication_dispense],
+                        None,
+                        agent_pharmacy(),
+                    )
+                    .unwrap();
+            }
+        })
+    };
+
+    let readers = (0..READERS).map(|_| {
+        let state = state.clone();
+
+        spawn(async move {
+            for _ in 0..SEARCHES {
+                let kvnr = Some(kvnr(0));
+                let count = if shared {
+                    let state = state.read().await;
+                    let count = state
+                        .task_iter(kvnr, None, agent_patient(), |_| true)
+                        .count();
+
+                    count
+                } else {
+                    let state = state.lock().await;
+                    let count = state
+                        .task_iter(kvnr, None, agent_patient(), |_| true)
+                        .count();
+
+                    count
+                };
+
+                assert_eq!(count, TASKS / PATIENTS);
+            }
+        })
+    });
+
+    for reader in join_all(readers).await {
+        reader.unwrap();
+    }
+
+    let elapsed = start.elapsed();
+
+    writer.await.unwrap();
+
+    (READERS * SEARCHES) as f64 / elapsed.as_secs_f64()
+}
+
+/// Create a state with `TASKS` activated tasks that are distributed over
+/// `PATIENTS` patients. Returns the state and the tasks of all patients
+/// except the searching one.
+///
+/// Tasks can only be activated with a signed prescription, so the tasks are
+/// created as drafts and activated in the saved state before it is loaded.
+async fn seed_state() -> (State, Vec<SeededTask>) {
+    let drafts = State::new(
+        PKey::generate_ed448().unwrap(),
+        X509::builder().unwrap().build(),
+        10,
+        0,
+        String::new(),
+    );
+
+    let mut data = Vec::new();
+    {
+        let mut drafts = drafts.lock().await;
+        for _ in 0..TASKS {
+            drafts.task_create(create_args()).unwrap();
+        }
+
+        drafts.save(&mut data).unwrap();
+    }
+
+    let mut data: Value = from_slice(&data).unwrap();
+    let mut tasks = Vec::new();
+    for (index, task) in data["tasks"].as_array_mut().unwrap().iter_mut().enumerate() {
+        let task = &mut task["task"];
+        let kvnr = kvnr(index % PATIENTS);
+
+        task["status"] = json!("Ready");
+        task["extension"]["accept_date"] = json!("2099-12-31");
+        task["extension"]["expiry_date"] = json!("2099-12-31");
+        task["for_"] = json!(kvnr.as_string());
+        task["input"]["e_prescription"] = json!(E_PRESCRIPTION);
+
+        if index % PATIENTS != 0 {
+            tasks.push(SeededTask {
+                id: Id::try_from(task["id"].as_str().unwrap().to_owned()).unwrap(),
+                access_code: task["identifier"]["access_code"].as_str().unwrap().into(),
+                prescription_id: task["identifier"]["prescription_id"]
+                    .as_str()
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                kvnr,
+            });
+        }
+    }
+    data["e_prescriptions"] = json!([[E_PRESCRIPTION, { "id": E_PRESCRIPTION, "data": "" }]]);
+
+    let (sig_key, sig_cert) = signing_key();
+    let state = State::new(sig_key, sig_cert, 10, 0, String::new());
+    state
+        .lock()
+        .await
+        .load(&to_vec(&data).unwrap()[..])
+        .unwrap();
+
+    (state, tasks)
+}
+
+/// Create a self-signed key pair to sign the receipts of `$close`.
+fn signing_key() -> (PKey<Private>, X509) {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let sig_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "ErxService").unwrap();
+    let name = name.build();
+
+    let mut sig_cert = X509::builder().unwrap();
+    sig_cert.set_version(2).unwrap();
+    sig_cert.set_subject_name(&name).unwrap();
+    sig_cert.set_issuer_name(&name).unwrap();
+    sig_cert.set_pubkey(&sig_key).unwrap();
+    sig_cert
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    sig_cert
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    sig_cert.sign(&sig_key, MessageDigest::sha256()).unwrap();
+
+    (sig_key, sig_cert.build())
+}
+
+async fn load_medication_dispense() -> MedicationDispense {
+    let data = read("./examples/medication_dispense.json").unwrap();
+    let mut stream = once(ready(Ok::<_, String>(Bytes::from(data))));
+
+    stream.json().await.unwrap()
+}
+
+fn create_args() -> TaskCreateParameters {
+    TaskCreateParameters {
+        flow_type: FlowType::ApothekenpflichtigeArzneimittel,
+    }
+}
+
+/// KVNR of the patient with the passed index, the searching patient has
+/// index 0.
+fn kvnr(index: usize) -> Kvnr {
+    Kvnr::new(format!("X{:09}", 234_567_890 + index)).unwrap()
+}
+
+fn agent_patient() -> Agent {
+    Agent {
+        type_: ParticipationRoleType::HumanUser,
+        who: Some(ParticipantId::Kvnr(kvnr(0))),
+        name: "Max Mustermann".into(),
+        requestor: true,
+    }
+}
+
+fn agent_pharmacy() -> Agent {
+    Agent {
+        type_: ParticipationRoleType::HumanUser,
+        who: Some(ParticipantId::TelematikId(TelematikId::new(TELEMATIK_ID))),
+        name: "Apotheke am Markt".into(),
+        requestor: true,
+    }
+}
@@ -19,9 +19,12 @@ use std::io::Error as IoError;
 
 use log::SetLoggerError;
 use log4rs::config::Errors as Log4RsError;
+use miscellaneous::{envelope::Error as EnvelopeError, migration::Error as MigrationError};
 use openssl::error::ErrorStack as OpenSslError;
+use rusqlite::Error as SqliteError;
 use serde_json::Error as JsonError;
 use thiserror::Error;
+use toml::de::Error as TomlError;
 use vau::Error as VauError;
 
 use crate::pki_store::Error as PkiError;
@@ -51,6 +54,18 @@ pub enum Error {
 
     #[error("PkiError: {0}")]
     PkiError(PkiError),
+
+    #[error("SQLite Error: {0}")]
+    SqliteError(SqliteError),
+
+    #[error("Envelope Error: {0}")]
+    EnvelopeError(EnvelopeError),
+
+    #[error("TOML Error: {0}")]
+    TomlError(TomlError),
+
+    #[error("Migration Error: {0}")]
+    MigrationError(MigrationError),
 }
 
 impl From<String> for Error {
@@ -100,3 +115,27 @@ impl From<PkiError> for Error {
         Self::PkiError(v)
     }
 }
+
+impl From<SqliteError> for Error {
+    fn from(v: SqliteError) -> Self {
+        Self::SqliteError(v)
+    }
+}
+
+impl From<EnvelopeError> for Error {
+    fn from(v: EnvelopeError) -> Self {
+        Self::EnvelopeError(v)
+    }
+}
+
+impl From<TomlError> for Error {
+    fn from(v: TomlError) -> Self {
+        Self::TomlError(v)
+    }
+}
+
+impl From<MigrationError> for Error {
+    fn from(v: MigrationError) -> Self {
+        Self::MigrationError(v)
+    }
+}
@@ -246,6 +246,7 @@ impl Encode for AuditEventContainer<'_> {
         let id = match &audit_event.entity.what {
             What::Task(id) => id.to_string(),
             What::MedicationDispense(id) => id.to_string(),
+            What::ChargeItem(id) => id.to_string(),
             What::Other(s) => s.clone(),
             _ => "[unknown]".into(),
         };
@@ -262,6 +263,9 @@ impl Encode for AuditEventContainer<'_> {
                 (Text::TaskGetManyPatient, Language::En) => {
                     format!("{} retrieved a list of e-prescriptions.", agent)
                 }
+                (Text::TaskGetManyPharmacy, Language::En) => {
+                    format!("{} retrieved a list of accepted e-prescriptions.", agent)
+                }
                 (Text::TaskGetOnePatient, Language::En) => {
                     format!("{} downloaded e-prescription {}.", agent, id)
                 }
@@ -280,6 +284,9 @@ impl Encode for AuditEventContainer<'_> {
                 (Text::TaskReject, Language::En) => {
                     format!("{} rejected e-prescription {}.", agent, id)
                 }
+                (Text::TaskDispense, Language::En) => {
+                    format!("{} dispensed medication for e-prescription {}.", agent, id)
+                }
                 (Text::TaskClose, Language::En) => {
                     format!("{} closed e-prescription {}.", agent, id)
                 }
@@ -302,11 +309,27 @@ impl Encode for AuditEventContainer<'_> {
                     "{} downloaded medication dispense for e-prescription {}.",
                     agent, id
                 ),
+                (Text::ChargeItemCreate, Language::En) => {
+                    format!("{} created the charge item {}.", agent, id)
+                }
+                (Text::ChargeItemGetOne, Language::En) => {
+                    format!("{} downloaded the charge item {}.", agent, id)
+                }
+                (Text::ChargeItemGetMany, Language::En) => {
+                    format!("{} downloaded a list of charge items.", agent)
+                }
+                (Text::ChargeItemDelete, Language::En) => {
+                    format!("{} deleted the charge item {}.", agent, id)
+                }
 
                 /* german */
                 (Text::TaskGetManyPatient, Language::De) => {
                     format!("{} hat eine Liste von E-Rezepten heruntergeladen.", agent)
                 }
+                (Text::TaskGetManyPharmacy, Language::De) => format!(
+                    "{} hat eine Liste von angenommenen E-Rezepten heruntergeladen.",
+                    agent
+                ),
                 (Text::TaskGetOnePatient, Language::De) => {
                     format!("{} hat das E-Rezept {} heruntergeladen.", agent, id)
                 }
@@ -325,6 +348,9 @@ impl Encode for AuditEventContainer<'_> {
                 (Text::TaskReject, Language::De) => {
                     format!("{} hat das E-Rezept {} zurückgegeben.", agent, id)
                 }
+                (Text::TaskDispense, Language::De) => {
+                    format!("{} hat Medikamente zum E-Rezept {} abgegeben.", agent, id)
+                }
                 (Text::TaskClose, Language::De) => {
                     format!("{} hat das E-Rezept {} beliefert.", agent, id)
                 }
@@ -348,6 +374,25 @@ impl Encode for AuditEventContainer<'_> {
                     "{} hat Medikament-Informationen zum E-Rezept {} heruntergeladen.",
                     agent, id
                 ),
+                (Text::ChargeItemCreate, Language::De) => {
+                    format!(
+                        "{} hat die Abrechnungsinformation {} eingestellt.",
+                        agent, id
+                    )
+                }
+                (Text::ChargeItemGetOne, Language::De) => {
+                    format!(
+                        "{} hat die Abrechnungsinformation {} heruntergeladen.",
+                        agent, id
+                    )
+                }
+                (Text::ChargeItemGetMany, Language::De) => format!(
+                    "{} hat eine Liste von Abrechnungsinformationen heruntergeladen.",
+                    agent
+                ),
+                (Text::ChargeItemDelete, Language::De) => {
+                    format!("{} hat die Abrechnungsinformation {} gelöscht.", agent, id)
+                }
             };
 
             stream
@@ -683,6 +728,8 @@ impl ReferenceEx for What {
             return Ok(What::Tasks);
         } else if reference == "/MedicationDispense" {
             return Ok(What::MedicationDispenses);
+        } else if reference == "/ChargeItem" {
+            return Ok(What::ChargeItems);
         } else if let Some(s) = reference
             .strip_prefix("/Task/")
             .or_else(|| reference.strip_prefix("Task/"))
@@ -697,6 +744,13 @@ impl ReferenceEx for What {
             if let Ok(id) = s.try_into() {
                 return Ok(What::MedicationDispense(id));
             }
+        } else if let Some(s) = reference
+            .strip_prefix("/ChargeItem/")
+            .or_else(|| reference.strip_prefix("ChargeItem/"))
+        {
+            if let Ok(id) = s.try_into() {
+                return Ok(What::ChargeItem(id));
+            }
         } else {
             return Ok(What::Other(reference));
         }
@@ -710,6 +764,8 @@ impl ReferenceEx for What {
             Self::Task(id) => format!("/Task/{}", id),
             Self::MedicationDispenses => "/MedicationDispense".to_owned(),
             Self::MedicationDispense(id) => format!("/MedicationDispense/{}", id),
+            Self::ChargeItems => "/ChargeItem".to_owned(),
+            Self::ChargeItem(id) => format!("/ChargeItem/{}", id),
             Self::Other(s) => s.to_owned(),
             Self::Unknown => "<unknown>".into(),
         }
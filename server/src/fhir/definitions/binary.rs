@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use async_trait::async_trait;
+use resources::Binary;
+
+use crate::fhir::{
+    decode::{decode_any, DataStream, Decode, DecodeError, DecodeStream, Fields},
+    encode::{encode_any, DataStorage, Encode, EncodeError, EncodeStream},
+};
+
+/* Decode */
+
+#[async_trait(?Send)]
+impl Decode for Binary {
+    async fn decode<S>(stream: &mut DecodeStream<S>) -> Result<Self, DecodeError<S::Error>>
+    where
+        S: DataStream,
+    {
+        let mut fields = Fields::new(&["id", "contentType", "data"]);
+
+        stream.root("Binary").await?;
+
+        let id = stream.decode(&mut fields, decode_any).await?;
+        let content_type = stream.decode(&mut fields, decode_any).await?;
+        let data = stream.decode(&mut fields, decode_any).await?;
+
+        stream.end().await?;
+
+        Ok(Binary {
+            id,
+            content_type,
+            data,
+        })
+    }
+}
+
+/* Encode */
+
+impl Encode for &Binary {
+    fn encode<S>(self, stream: &mut EncodeStream<S>) -> Result<(), EncodeError<S::Error>>
+    where
+        S: DataStorage,
+    {
+        stream
+            .root("Binary")?
+            .encode("id", &self.id, encode_any)?
+            .encode("contentType", &self.content_type, encode_any)?
+            .encode("data", &self.data, encode_any)?
+            .end()?;
+
+        Ok(())
+    }
+}
+
+pub const PROFILE: &str = "http://hl7.org/fhir/StructureDefinition/Binary";
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::convert::TryInto;
+    use std::fs::read_to_string;
+    use std::str::from_utf8;
+
+    use crate::fhir::{
+        decode::{tests::load_stream, JsonDecode, XmlDecode},
+        encode::{JsonEncode, XmlEncode},
+    };
+
+    use super::super::super::tests::{trim_json_str, trim_xml_str};
+
+    #[tokio::test]
+    async fn test_decode_json() {
+        let mut stream = load_stream("./examples/binary.json");
+
+        let actual: Binary = stream.json().await.unwrap();
+        let expected = test_binary();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_decode_xml() {
+        let mut stream = load_stream("./examples/binary.xml");
+
+        let actual: Binary = stream.xml().await.unwrap();
+        let expected = test_binary();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_json() {
+        let value = test_binary();
+
+        let actual = (&value).json().unwrap();
+        let actual = from_utf8(&actual).unwrap();
+        let expected = read_to_string("./examples/binary.json").unwrap();
+
+        assert_eq!(trim_json_str(&actual), trim_json_str(&expected));
+    }
+
+    #[tokio::test]
+    async fn test_encode_xml() {
+        let value = test_binary();
+
+        let actual = (&value).xml().unwrap();
+        let actual = from_utf8(&actual).unwrap();
+        let expected = read_to_string("./examples/binary.xml").unwrap();
+
+        assert_eq!(trim_xml_str(&actual), trim_xml_str(&expected));
+    }
+
+    pub fn test_binary() -> Binary {
+        Binary {
+            id: "0a4d55a8d778e5022fab701977c5d840bbc486d0"
+                .try_into()
+                .unwrap(),
+            content_type: "text/plain".into(),
+            data: "SGVsbG8gV29ybGQ=".into(),
+        }
+    }
+}
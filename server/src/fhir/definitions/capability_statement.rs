@@ -249,8 +249,10 @@ impl Decode for Type {
             "Operation" => Ok(Self::Operation),
             "Communication" => Ok(Self::Communication),
             "MedicationDispense" => Ok(Self::MedicationDispense),
+            "ChargeItem" => Ok(Self::ChargeItem),
             "AuditEvent" => Ok(Self::AuditEvent),
             "Device" => Ok(Self::Device),
+            "Binary" => Ok(Self::Binary),
             _ => Err(DecodeError::InvalidValue {
                 value,
                 path: stream.path().into(),
@@ -265,16 +267,21 @@ impl Decode for SearchParam {
     where
         S: DataStream,
     {
-        let mut fields = Fields::new(&["name", "type"]);
+        let mut fields = Fields::new(&["name", "type", "documentation"]);
 
         stream.root("SearchParam").await?;
 
         let name = stream.decode(&mut fields, decode_any).await?;
         let type_ = stream.decode(&mut fields, decode_code).await?;
+        let documentation = stream.decode_opt(&mut fields, decode_any).await?;
 
         stream.end().await?;
 
-        Ok(SearchParam { name, type_ })
+        Ok(SearchParam {
+            name,
+            type_,
+            documentation,
+        })
     }
 }
 
@@ -464,6 +471,7 @@ impl Encode for &SearchParam {
             .element()?
             .encode("name", &self.name, encode_any)?
             .encode("type", &self.type_, encode_code)?
+            .encode_opt("documentation", &self.documentation, encode_any)?
             .end()?;
 
         Ok(())
@@ -480,8 +488,10 @@ impl Encode for &Type {
             Type::Operation => "Operation",
             Type::Communication => "Communication",
             Type::MedicationDispense => "MedicationDispense",
+            Type::ChargeItem => "ChargeItem",
             Type::AuditEvent => "AuditEvent",
             Type::Device => "Device",
+            Type::Binary => "Binary",
         };
 
         stream.value(value)?;
@@ -0,0 +1,340 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::iter::once;
+
+use async_trait::async_trait;
+use miscellaneous::str::icase_eq;
+use resources::{
+    charge_item::{ChargeItem, DispenseItem},
+    primitives::Id,
+};
+
+use crate::fhir::{
+    decode::{decode_any, DataStream, Decode, DecodeError, DecodeStream, Fields},
+    encode::{encode_any, DataStorage, Encode, EncodeError, EncodeStream},
+};
+
+use super::{
+    meta::Meta,
+    primitives::{
+        decode_binary, decode_codeable_concept, decode_identifier, decode_reference, encode_binary,
+        encode_codeable_concept, encode_identifier, encode_reference, BinaryEx, CodeEx,
+        CodeableConceptEx, CodingEx,
+    },
+    DecodeBundleResource, EncodeBundleResource,
+};
+
+/* Decode */
+
+impl DecodeBundleResource for ChargeItem {}
+
+#[async_trait(?Send)]
+impl Decode for ChargeItem {
+    async fn decode<S>(stream: &mut DecodeStream<S>) -> Result<Self, DecodeError<S::Error>>
+    where
+        S: DataStream,
+    {
+        let mut fields = Fields::new(&[
+            "id",
+            "meta",
+            "contained",
+            "identifier",
+            "status",
+            "code",
+            "subject",
+            "enterer",
+            "enteredDate",
+            "supportingInformation",
+        ]);
+
+        stream.root("ChargeItem").await?;
+
+        let id = stream.decode_opt(&mut fields, decode_any).await?;
+        let meta = stream.decode::<Meta, _>(&mut fields, decode_any).await?;
+        let dispense_items = stream
+            .resource_vec::<Vec<DispenseItem>, _>(&mut fields, decode_any)
+            .await?;
+        let prescription_id = stream.decode(&mut fields, decode_identifier).await?;
+        let _status = stream.fixed(&mut fields, "billable").await?;
+        let _code = stream
+            .decode::<NotApplicable, _>(&mut fields, decode_codeable_concept)
+            .await?;
+        let subject = {
+            stream.begin_substream(&mut fields).await?;
+            stream.element().await?;
+
+            let mut fields = Fields::new(&["identifier"]);
+            let identifier = stream.decode(&mut fields, decode_identifier).await?;
+
+            stream.end().await?;
+            stream.end_substream().await?;
+
+            identifier
+        };
+        let enterer = {
+            stream.begin_substream(&mut fields).await?;
+            stream.element().await?;
+
+            let mut fields = Fields::new(&["identifier"]);
+            let identifier = stream.decode(&mut fields, decode_identifier).await?;
+
+            stream.end().await?;
+            stream.end_substream().await?;
+
+            identifier
+        };
+        let entered_date = stream.decode_opt(&mut fields, decode_any).await?;
+        let supporting_information = stream.decode_vec(&mut fields, decode_reference).await?;
+
+        stream.end().await?;
+
+        if !meta.profiles.iter().any(|p| icase_eq(p, PROFILE)) {
+            return Err(DecodeError::InvalidProfile {
+                actual: meta.profiles,
+                expected: vec![PROFILE.into()],
+            });
+        }
+
+        let dispense_item =
+            dispense_items
+                .into_iter()
+                .next()
+                .ok_or_else(|| DecodeError::Custom {
+                    message: "Charge item is missing the contained dispense item".into(),
+                    path: stream.path().into(),
+                })?;
+
+        Ok(ChargeItem {
+            id,
+            prescription_id,
+            subject,
+            enterer,
+            entered_date,
+            supporting_information,
+            dispense_item,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Decode for DispenseItem {
+    async fn decode<S>(stream: &mut DecodeStream<S>) -> Result<Self, DecodeError<S::Error>>
+    where
+        S: DataStream,
+    {
+        decode_binary(stream).await
+    }
+}
+
+/* Encode */
+
+impl EncodeBundleResource for &ChargeItem {}
+
+impl Encode for &ChargeItem {
+    fn encode<S>(self, stream: &mut EncodeStream<S>) -> Result<(), EncodeError<S::Error>>
+    where
+        S: DataStorage,
+    {
+        let meta = Meta {
+            profiles: vec![PROFILE.into()],
+            ..Default::default()
+        };
+
+        stream
+            .root("ChargeItem")?
+            .encode_opt("id", &self.id, encode_any)?
+            .encode("meta", meta, encode_any)?
+            .resource_vec("contained", once(&self.dispense_item), encode_any)?
+            .encode_vec("identifier", once(&self.prescription_id), encode_identifier)?
+            .encode("status", "billable", encode_any)?
+            .encode("code", &NotApplicable, encode_codeable_concept)?
+            .field_name("subject")?
+            .element()?
+            .encode("identifier", &self.subject, encode_identifier)?
+            .end()?
+            .field_name("enterer")?
+            .element()?
+            .encode("identifier", &self.enterer, encode_identifier)?
+            .end()?
+            .encode_opt("enteredDate", &self.entered_date, encode_any)?
+            .encode_vec(
+                "supportingInformation",
+                &self.supporting_information,
+                encode_reference,
+            )?
+            .end()?;
+
+        Ok(())
+    }
+}
+
+impl Encode for &DispenseItem {
+    fn encode<S>(self, stream: &mut EncodeStream<S>) -> Result<(), EncodeError<S::Error>>
+    where
+        S: DataStorage,
+    {
+        encode_binary(self, stream)
+    }
+}
+
+/* Misc */
+
+impl BinaryEx for DispenseItem {
+    fn from_parts(id: Option<Id>, data: String) -> Result<Self, String> {
+        let id = id.ok_or_else(|| "DispenseItem is missing the 'id' field".to_owned())?;
+
+        Ok(DispenseItem { id, data })
+    }
+
+    fn id(&self) -> Option<&Id> {
+        Some(&self.id)
+    }
+
+    fn data(&self) -> String {
+        self.data.clone()
+    }
+
+    fn content_type() -> Option<&'static str> {
+        Some("application/pkcs7-mime")
+    }
+}
+
+/// The code of a charge item is not used by the e-prescription service,
+/// so it is always set to the data absent reason `not-applicable`.
+struct NotApplicable;
+
+impl CodeEx for NotApplicable {
+    fn from_parts(value: String) -> Result<Self, String> {
+        match value.as_str() {
+            "not-applicable" => Ok(Self),
+            _ => Err(value),
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        "not-applicable"
+    }
+}
+
+impl CodingEx for NotApplicable {
+    type Code = Self;
+
+    fn from_parts(code: Self::Code) -> Self {
+        code
+    }
+
+    fn code(&self) -> &Self::Code {
+        &self
+    }
+
+    fn system() -> Option<&'static str> {
+        Some("http://terminology.hl7.org/CodeSystem/data-absent-reason")
+    }
+}
+
+impl CodeableConceptEx for NotApplicable {
+    type Coding = Self;
+
+    fn from_parts(coding: Self::Coding, _text: Option<String>) -> Self {
+        coding
+    }
+
+    fn coding(&self) -> &Self::Coding {
+        &self
+    }
+}
+
+pub const PROFILE: &str = "https://gematik.de/fhir/StructureDefinition/ErxChargeItem";
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::convert::TryInto;
+    use std::fs::read_to_string;
+    use std::str::from_utf8;
+
+    use resources::misc::{Kvnr, TelematikId};
+
+    use crate::fhir::{
+        decode::{tests::load_stream, JsonDecode, XmlDecode},
+        encode::{JsonEncode, XmlEncode},
+    };
+
+    use super::super::super::tests::{trim_json_str, trim_xml_str};
+
+    #[tokio::test]
+    async fn test_decode_json() {
+        let mut stream = load_stream("./examples/charge_item.json");
+
+        let actual = stream.json::<ChargeItem>().await.unwrap();
+        let expected = test_charge_item();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_decode_xml() {
+        let mut stream = load_stream("./examples/charge_item.xml");
+
+        let actual = stream.xml::<ChargeItem>().await.unwrap();
+        let expected = test_charge_item();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_json() {
+        let value = test_charge_item();
+
+        let actual = (&value).json().unwrap();
+        let actual = from_utf8(&actual).unwrap();
+        let expected = read_to_string("./examples/charge_item.json").unwrap();
+
+        assert_eq!(trim_json_str(&actual), trim_json_str(&expected));
+    }
+
+    #[tokio::test]
+    async fn test_encode_xml() {
+        let value = test_charge_item();
+
+        let actual = (&value).xml().unwrap();
+        let actual = from_utf8(&actual).unwrap();
+        let expected = read_to_string("./examples/charge_item.xml").unwrap();
+
+        assert_eq!(trim_xml_str(&actual), trim_xml_str(&expected));
+    }
+
+    pub fn test_charge_item() -> ChargeItem {
+        ChargeItem {
+            id: Some("200.123.456.789.123.72".try_into().unwrap()),
+            prescription_id: "200.123.456.789.123.72".parse().unwrap(),
+            subject: Kvnr::new("X234567890").unwrap(),
+            enterer: TelematikId::new("606358757"),
+            entered_date: Some("2021-06-01T07:13:00+00:00".try_into().unwrap()),
+            supporting_information: vec![
+                "Bundle/281a985c-f25b-4aae-91a6-41ad744080b0".into(),
+                "Bundle/dffbfd6a-5712-4798-bdc8-07201eb77ab8".into(),
+            ],
+            dispense_item: DispenseItem {
+                id: "dispense-item".try_into().unwrap(),
+                data: "ZGlzcGVuc2UgaXRlbQ==".into(),
+            },
+        }
+    }
+}
@@ -809,6 +809,13 @@ pub const PROFILE_DISPENSE_REQ: &str =
 pub const PROFILE_REPRESENTATIVE: &str =
     "https://gematik.de/fhir/StructureDefinition/ErxCommunicationRepresentative";
 
+// $mark-read and $mark-unread are not specified by gematik, so they are
+// defined in the namespace of this server.
+pub const OPERATION_MARK_READ: &str =
+    "https://prescriptionserver.telematik/OperationDefinition/CommunicationMarkRead";
+pub const OPERATION_MARK_UNREAD: &str =
+    "https://prescriptionserver.telematik/OperationDefinition/CommunicationMarkUnread";
+
 const URL_INSURANCE_PROVIDER: &str =
     "https://gematik.de/fhir/StructureDefinition/InsuranceProvider";
 const URL_SUBSTITUTION_ALLOWED: &str =
@@ -21,6 +21,7 @@ use std::iter::once;
 use async_trait::async_trait;
 use miscellaneous::str::icase_eq;
 use resources::{
+    bundle::Bundle,
     medication_dispense::{DosageInstruction, MedicationDispense},
     primitives::Id,
     Medication,
@@ -136,6 +137,7 @@ impl Decode for MedicationDispense {
 
         Ok(MedicationDispense {
             id,
+            last_updated: meta.last_updated,
             prescription_id,
             medication,
             subject,
@@ -166,6 +168,62 @@ impl Decode for DosageInstruction {
     }
 }
 
+/// List of medication dispenses that were handed out for one prescription.
+///
+/// The list is decoded from a single `MedicationDispense`, a `Bundle` of
+/// medication dispenses or a `Parameters` resource that contains one
+/// `rxDispensation` parameter for each medication dispense.
+pub struct MedicationDispenseList(pub Vec<MedicationDispense>);
+
+#[async_trait(?Send)]
+impl Decode for MedicationDispenseList {
+    async fn decode<S>(stream: &mut DecodeStream<S>) -> Result<Self, DecodeError<S::Error>>
+    where
+        S: DataStream,
+    {
+        let element = stream.peek_element().await?;
+
+        let items = match element.as_str() {
+            "MedicationDispense" => vec![decode_any(stream).await?],
+            "Bundle" => {
+                let bundle: Bundle<MedicationDispense> = decode_any(stream).await?;
+
+                bundle.entries.into_iter().map(|e| e.resource).collect()
+            }
+            "Parameters" => {
+                let mut items = Vec::new();
+                let mut fields = Fields::new(&["parameter"]);
+
+                stream.root("Parameters").await?;
+
+                while stream.begin_substream_vec(&mut fields).await? {
+                    stream.element().await?;
+
+                    let mut fields = Fields::new(&["name", "resource"]);
+                    let _name = stream.fixed(&mut fields, "rxDispensation").await?;
+                    let item = stream.resource(&mut fields, decode_any).await?;
+                    items.push(item);
+
+                    stream.end().await?;
+                    stream.end_substream().await?;
+                }
+
+                stream.end().await?;
+
+                items
+            }
+            _ => {
+                return Err(DecodeError::UnexpectedElement {
+                    id: element.into(),
+                    path: stream.path().into(),
+                })
+            }
+        };
+
+        Ok(Self(items))
+    }
+}
+
 /* Encode */
 
 impl EncodeBundleResource for &MedicationDispense {}
@@ -176,6 +234,7 @@ impl Encode for &MedicationDispense {
         S: DataStorage,
     {
         let meta = Meta {
+            last_updated: self.last_updated.clone(),
             profiles: vec![PROFILE.into()],
             ..Default::default()
         };
@@ -295,9 +354,34 @@ pub mod tests {
         assert_eq!(trim_xml_str(&actual), trim_xml_str(&expected));
     }
 
+    #[tokio::test]
+    async fn test_decode_list() {
+        let mut stream = load_stream("./examples/medication_dispense.json");
+
+        let actual = stream.json::<MedicationDispenseList>().await.unwrap();
+        assert_eq!(actual.0, vec![test_medication_dispense()]);
+
+        let mut stream = load_stream("./examples/medication_dispense_parameters.json");
+
+        let actual = stream.json::<MedicationDispenseList>().await.unwrap();
+        assert_eq!(
+            actual.0,
+            vec![test_medication_dispense(), test_medication_dispense()]
+        );
+
+        let mut stream = load_stream("./examples/medication_dispense_bundle.json");
+
+        let actual = stream.json::<MedicationDispenseList>().await.unwrap();
+        assert_eq!(
+            actual.0,
+            vec![test_medication_dispense(), test_medication_dispense()]
+        );
+    }
+
     pub fn test_medication_dispense() -> MedicationDispense {
         MedicationDispense {
             id: None,
+            last_updated: None,
             prescription_id: "160.123.456.789.123.58".parse().unwrap(),
             medication: test_medication_pzn(),
             subject: Kvnr::new("X234567890").unwrap(),
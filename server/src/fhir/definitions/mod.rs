@@ -16,8 +16,10 @@
  */
 
 mod audit_event;
+mod binary;
 mod bundle;
 mod capability_statement;
+mod charge_item;
 mod communication;
 mod composition;
 mod coverage;
@@ -43,8 +45,12 @@ mod task_create_parameters;
 mod types;
 
 pub use audit_event::{AuditEventContainer, PROFILE as RESOURCE_PROFILE_AUDIT_EVENT};
+pub use binary::PROFILE as RESOURCE_PROFILE_BINARY;
 pub use bundle::{DecodeBundleResource, EncodeBundleResource};
+pub use charge_item::PROFILE as RESOURCE_PROFILE_CHARGE_ITEM;
 pub use communication::{
+    OPERATION_MARK_READ as OPERATION_COMMUNICATION_MARK_READ,
+    OPERATION_MARK_UNREAD as OPERATION_COMMUNICATION_MARK_UNREAD,
     PROFILE_BASE as RESOURCE_PROFILE_COMMUNICATION,
     PROFILE_DISPENSE_REQ as RESOURCE_PROFILE_COMMUNICATION_DISPENSE_REQ,
     PROFILE_INFO_REQ as RESOURCE_PROFILE_COMMUNICATION_INFO_REQ,
@@ -52,10 +58,13 @@ pub use communication::{
     PROFILE_REPRESENTATIVE as RESOURCE_PROFILE_COMMUNICATION_REPRESENTATIVE,
 };
 pub use device::PROFILE as RESOURCE_PROFILE_DEVICE;
-pub use medication_dispense::PROFILE as RESOURCE_PROFILE_MEDICATION_DISPENSE;
+pub use medication_dispense::{
+    MedicationDispenseList, PROFILE as RESOURCE_PROFILE_MEDICATION_DISPENSE,
+};
 pub use task::{
     TaskContainer, OPERATION_ABORT as OPERATION_TASK_ABORT,
     OPERATION_ACCEPT as OPERATION_TASK_ACCEPT, OPERATION_ACTIVATE as OPERATION_TASK_ACTIVATE,
     OPERATION_CLOSE as OPERATION_TASK_CLOSE, OPERATION_CREATE as OPERATION_TASK_CREATE,
-    OPERATION_REJECT as OPERATION_TASK_REJECT, PROFILE as RESOURCE_PROFILE_TASK,
+    OPERATION_DISPENSE as OPERATION_TASK_DISPENSE, OPERATION_REJECT as OPERATION_TASK_REJECT,
+    PROFILE as RESOURCE_PROFILE_TASK,
 };
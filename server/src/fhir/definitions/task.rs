@@ -87,6 +87,7 @@ impl Decode for Task {
 
         Ok(Task {
             id,
+            version_id: meta.version_id,
             extension,
             identifier,
             status,
@@ -109,6 +110,7 @@ impl Decode for Extension {
         let mut flow_type = None;
         let mut accept_date = None;
         let mut expiry_date = None;
+        let mut multi_prescription = None;
 
         let mut fields = Fields::new(&["extension"]);
         while stream.begin_substream_vec(&mut fields).await? {
@@ -132,6 +134,11 @@ impl Decode for Extension {
 
                     expiry_date = Some(stream.decode(&mut fields, decode_any).await?)
                 }
+                x if icase_eq(x, URL_MULTI_PRESCRIPTION) => {
+                    let mut fields = Fields::new(&["extension"]);
+
+                    multi_prescription = stream.decode(&mut fields, decode_any).await?;
+                }
                 _ => (),
             }
 
@@ -148,6 +155,7 @@ impl Decode for Extension {
             flow_type,
             accept_date,
             expiry_date,
+            multi_prescription,
         })
     }
 }
@@ -340,6 +348,7 @@ pub struct TaskContainer<'a> {
 enum Requestor {
     Patient,
     Supplier,
+    SupplierSearch,
     Doctor,
 }
 
@@ -351,6 +360,15 @@ impl<'a> TaskContainer<'a> {
         }
     }
 
+    /// Encode the task as part of a search result of a supplier, which
+    /// contains neither the access code nor the secret.
+    pub fn for_supplier_search(task: &'a Task) -> Self {
+        Self {
+            task,
+            requestor: Requestor::SupplierSearch,
+        }
+    }
+
     pub fn for_doctor(task: &'a Task) -> Self {
         Self {
             task,
@@ -373,6 +391,7 @@ impl Encode for TaskContainer<'_> {
     {
         let task = self.task;
         let meta = Meta {
+            version_id: task.version_id.clone(),
             profiles: vec![PROFILE.into()],
             ..Default::default()
         };
@@ -380,6 +399,7 @@ impl Encode for TaskContainer<'_> {
         let identifier = IdentifierContainer {
             identifier: &task.identifier,
             requestor: self.requestor,
+            is_direct_assignment: task.extension.flow_type.is_direct_assignment(),
         };
 
         stream
@@ -434,6 +454,14 @@ impl Encode for &Extension {
                 .end()?;
         }
 
+        if self.multi_prescription.is_some() {
+            stream
+                .element()?
+                .attrib("url", URL_MULTI_PRESCRIPTION, encode_any)?
+                .encode("extension", &self.multi_prescription, encode_any)?
+                .end()?;
+        }
+
         stream.end()?;
 
         Ok(())
@@ -443,6 +471,7 @@ impl Encode for &Extension {
 struct IdentifierContainer<'a> {
     identifier: &'a Identifier,
     requestor: Requestor,
+    is_direct_assignment: bool,
 }
 
 impl Encode for IdentifierContainer<'_> {
@@ -453,6 +482,7 @@ impl Encode for IdentifierContainer<'_> {
         let IdentifierContainer {
             identifier,
             requestor,
+            is_direct_assignment,
         } = self;
 
         stream.array()?;
@@ -465,7 +495,10 @@ impl Encode for IdentifierContainer<'_> {
                 .end()?;
         }
 
-        if requestor == Requestor::Doctor || requestor == Requestor::Patient {
+        // The access code of a direct assignment is not passed to the patient.
+        let is_patient = requestor == Requestor::Patient && !is_direct_assignment;
+
+        if requestor == Requestor::Doctor || is_patient {
             if let Some(access_code) = &identifier.access_code {
                 stream
                     .element()?
@@ -597,6 +630,8 @@ pub const OPERATION_ACCEPT: &str =
     "http://gematik.de/fhir/OperationDefinition/AcceptOperationDefinition";
 pub const OPERATION_REJECT: &str =
     "http://gematik.de/fhir/OperationDefinition/RejectOperationDefinition";
+pub const OPERATION_DISPENSE: &str =
+    "http://gematik.de/fhir/OperationDefinition/DispenseOperationDefinition";
 pub const OPERATION_CLOSE: &str =
     "http://gematik.de/fhir/OperationDefinition/CloseOperationDefinition";
 pub const OPERATION_ABORT: &str =
@@ -605,6 +640,8 @@ pub const OPERATION_ABORT: &str =
 const URL_FLOW_TYPE: &str = "https://gematik.de/fhir/StructureDefinition/PrescriptionType";
 const URL_ACCEPT_DATE: &str = "https://gematik.de/fhir/StructureDefinition/AcceptDate";
 const URL_EXPIRY_DATE: &str = "https://gematik.de/fhir/StructureDefinition/ExpiryDate";
+const URL_MULTI_PRESCRIPTION: &str =
+    "https://fhir.kbv.de/StructureDefinition/KBV_EX_ERP_Multiple_Prescription";
 
 const SYSTEM_PRESCRIPTION_ID: &str = "https://gematik.de/fhir/NamingSystem/PrescriptionID";
 const SYSTEM_ACCESS_CODE: &str = "https://gematik.de/fhir/NamingSystem/AccessCode";
@@ -691,13 +728,28 @@ pub mod tests {
         assert_eq!(trim_xml_str(&actual), trim_xml_str(&expected));
     }
 
+    #[tokio::test]
+    async fn test_encode_json_patient_direct_assignment() {
+        let mut value = test_task();
+        value.extension.flow_type = FlowType::DirekteZuweisung;
+
+        let actual = TaskContainer::for_patient(&value).json().unwrap();
+        let actual = from_utf8(&actual).unwrap();
+
+        let access_code = value.identifier.access_code.as_ref().unwrap();
+        assert!(!actual.contains(SYSTEM_ACCESS_CODE));
+        assert!(!actual.contains(access_code.as_str()));
+    }
+
     fn test_task() -> Task {
         Task {
             id: "1234567890".try_into().unwrap(),
+            version_id: None,
             extension: Extension {
                 accept_date: Some("2020-03-02".try_into().unwrap()),
                 expiry_date: Some("2020-05-02".try_into().unwrap()),
                 flow_type: FlowType::ApothekenpflichtigeArzneimittel,
+                multi_prescription: None,
             },
             identifier: Identifier {
                 prescription_id: Some("160.123.456.789.123.58".parse().unwrap()),
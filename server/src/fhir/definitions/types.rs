@@ -30,6 +30,8 @@ impl CodeEx for FlowType {
             "165" => Ok(Self::Betaeubungsmittel),
             "166" => Ok(Self::TRezepte),
             "169" => Ok(Self::DirekteZuweisung),
+            "200" => Ok(Self::ApothekenpflichtigeArzneimittelPkv),
+            "209" => Ok(Self::DirekteZuweisungPkv),
             _ => Err(value),
         }
     }
@@ -44,6 +46,8 @@ impl CodeEx for FlowType {
             Self::Betaeubungsmittel => "165",
             Self::TRezepte => "166",
             Self::DirekteZuweisung => "169",
+            Self::ApothekenpflichtigeArzneimittelPkv => "200",
+            Self::DirekteZuweisungPkv => "209",
         }
     }
 }
@@ -71,6 +75,10 @@ impl CodingEx for FlowType {
             Self::Betaeubungsmittel => Some("Muster 16 (Betäubungsmittel)"),
             Self::TRezepte => Some("Muster 16 (T-Rezepte)"),
             Self::DirekteZuweisung => Some("Muster 16 (Direkte Zuweisung)"),
+            Self::ApothekenpflichtigeArzneimittelPkv => {
+                Some("PKV (Apothekenpflichtige Arzneimittel)")
+            }
+            Self::DirekteZuweisungPkv => Some("PKV (Direkte Zuweisung)"),
         }
     }
 
@@ -15,18 +15,25 @@
  *
  */
 
-use std::fs::{read, File};
+use std::fs::{read, read_to_string, File};
 use std::path::PathBuf;
 
+use chrono::{DateTime, Utc};
 use futures::{future::FutureExt, select};
-use log::warn;
+use log::{info, warn};
+use miscellaneous::envelope::{derive_key, KEY_SIZE, LABEL_STATE};
 use openssl::{ec::EcKey, pkey::PKey, x509::X509};
 use structopt::StructOpt;
-use tokio::{runtime::Builder, task::LocalSet};
+use tokio::{runtime::Builder, task::LocalSet, time::Duration};
 use url::Url;
+use vau::hex_decode;
 
 use ref_erx_fd_server::{
-    error::Error, logging::init_logger, pki_store::PkiStore, service::Service, state::State,
+    error::Error,
+    logging::init_logger,
+    pki_store::PkiStore,
+    service::{AttachmentType, AttachmentTypes, Service},
+    state::{Calendar, Clock, FederalState, Journal, Retention, Sqlite, State},
 };
 
 fn main() -> Result<(), Error> {
@@ -50,12 +57,59 @@ async fn run(opts: Options) -> Result<(), Error> {
     let enc_key = read(&opts.enc_key)?;
     let enc_key = EcKey::private_key_from_pem(&enc_key).map_err(Error::OpenSslError)?;
 
+    let state_key = match (&opts.state_key, opts.encrypt_state) {
+        (Some(path), _) => {
+            let key = read_to_string(path)?;
+            let key = hex_decode(key.trim()).map_err(|()| "Invalid state key!".to_owned())?;
+
+            Some(key)
+        }
+        (None, true) => Some(derive_key(LABEL_STATE, &enc_key.private_key().to_vec())?),
+        (None, false) => None,
+    };
+
+    if matches!(&state_key, Some(key) if key.len() != KEY_SIZE) {
+        return Err(Error::Generic(format!(
+            "Invalid state key: Expected {} bytes!",
+            KEY_SIZE
+        )));
+    }
+
     let enc_cert = read(&opts.enc_cert)?;
     let enc_cert = X509::from_pem(&enc_cert)?;
 
+    let tsl_trust_anchors = read(&opts.tsl_trust_anchor)?;
+    let tsl_trust_anchors = X509::stack_from_pem(&tsl_trust_anchors)?;
+
+    let retention = match &opts.retention {
+        Some(path) => Retention::from_file(path)?,
+        None => Retention::default(),
+    };
+
+    let calendar = match &opts.holidays {
+        Some(path) => Calendar::from_file(path, opts.federal_state)?,
+        None => Calendar::new(opts.federal_state),
+    };
+
+    let clock = match opts.fixed_time {
+        Some(time) => {
+            warn!("Using fixed time {} for all operations!", time);
+
+            Clock::Fixed(time)
+        }
+        None => Clock::System,
+    };
+
     let local = LocalSet::new();
 
-    let pki_store = PkiStore::new(enc_key, enc_cert, opts.tsl, opts.bnetza, opts.token)?;
+    let pki_store = PkiStore::new(
+        enc_key,
+        enc_cert,
+        opts.tsl,
+        opts.bnetza,
+        tsl_trust_anchors,
+        opts.token,
+    )?;
     let state = State::new(
         sig_key,
         sig_cert,
@@ -64,12 +118,52 @@ async fn run(opts: Options) -> Result<(), Error> {
         opts.throttling_header,
     );
 
+    {
+        let mut state = state.lock().await;
+        state.set_state_key(state_key.clone());
+        state.set_retention(retention);
+        state.set_calendar(calendar);
+        state.set_clock(clock);
+        state.set_idempotency_window(Duration::from_secs(opts.idempotency_window));
+        if !opts.attachment_types.is_empty() {
+            state.set_attachment_types(AttachmentTypes::new(opts.attachment_types));
+        }
+
+        match (&opts.storage, &opts.state) {
+            (Some(storage), path) => {
+                let storage = Sqlite::open(storage)?;
+                let is_empty = state.attach_storage(Box::new(storage))? == 0;
+
+                match path {
+                    Some(path) if path.is_file() && is_empty => state.load(File::open(path)?)?,
+                    Some(path) if path.is_file() => {
+                        info!("Storage is not empty, skipped import of state file")
+                    }
+                    _ => (),
+                }
+            }
+            (None, Some(path)) => {
+                if path.is_file() {
+                    state.load(File::open(path)?)?;
+                }
+
+                let mut journal = path.clone().into_os_string();
+                journal.push(".journal");
+
+                let journal = Journal::open(journal, state_key)?;
+                state.attach_storage(Box::new(journal))?;
+            }
+            (None, None) => (),
+        }
+
+        state.progress_timeouts();
+    }
+
     if let Some(path) = &opts.state {
-        if path.is_file() {
-            let file = File::open(path)?;
+        if opts.snapshot_interval > 0 {
+            let interval = Duration::from_secs(opts.snapshot_interval);
 
-            let mut state = state.lock().await;
-            state.load(file)?;
+            state.spawn_snapshot_task(path.clone(), interval);
         }
     }
 
@@ -93,14 +187,9 @@ async fn run(opts: Options) -> Result<(), Error> {
         .await;
 
     if let Some(path) = &opts.state {
-        match File::create(path) {
-            Ok(file) => {
-                let state = state.lock().await;
-                if let Err(err) = state.save(file) {
-                    warn!("Unable to write state to file: {}", err);
-                }
-            }
-            Err(err) => warn!("Unable to open state file: {}", err),
+        let mut state = state.lock().await;
+        if let Err(err) = state.snapshot(path) {
+            warn!("Unable to write state to file: {}", err);
         }
     }
 
@@ -147,9 +236,61 @@ struct Options {
     sig_cert: PathBuf,
 
     /// File to write the state of the service to.
+    /// If no storage is used, all changes since the last snapshot are
+    /// written to a journal next to this file ('<state>.journal').
+    /// If a storage is used, the state is only imported from this file
+    /// if the storage is empty.
     #[structopt(verbatim_doc_comment, long = "state")]
     state: Option<PathBuf>,
 
+    /// File containing the hex encoded 256 bit key that is used to encrypt
+    /// the state file and the journal.
+    #[structopt(verbatim_doc_comment, long = "state-key")]
+    state_key: Option<PathBuf>,
+
+    /// Encrypt the state file and the journal with a key that is derived
+    /// from the encryption key of the service (see '--enc-key').
+    /// Is ignored if '--state-key' is passed.
+    #[structopt(verbatim_doc_comment, long = "encrypt-state")]
+    encrypt_state: bool,
+
+    /// Interval to write a snapshot of the state to the state file
+    /// (in seconds; 0 for disable).
+    #[structopt(
+        verbatim_doc_comment,
+        long = "snapshot-interval",
+        default_value = "300"
+    )]
+    snapshot_interval: u64,
+
+    /// SQLite database to persist the state of the service in.
+    /// Each operation is committed to the database immediately.
+    #[structopt(verbatim_doc_comment, long = "storage")]
+    storage: Option<PathBuf>,
+
+    /// TOML file that defines how long the different resources are kept
+    /// (see 'retention.toml' for an example).
+    /// If not passed, the default retention periods are used.
+    #[structopt(verbatim_doc_comment, long = "retention")]
+    retention: Option<PathBuf>,
+
+    /// Federal state to respect the regional holidays of when the accept
+    /// date of tasks is calculated (e.g. 'DE-BY').
+    #[structopt(verbatim_doc_comment, long = "federal-state")]
+    federal_state: Option<FederalState>,
+
+    /// CSV or ICS file to load the holidays from. If passed, the built-in
+    /// holidays are not used.
+    /// Each line of a CSV file has the format
+    ///     <YYYY-MM-DD>[,<name>[,<federal states separated by spaces>]]
+    #[structopt(verbatim_doc_comment, long = "holidays")]
+    holidays: Option<PathBuf>,
+
+    /// Use a fixed time (RFC 3339) instead of the system time for all
+    /// operations. Only intended for testing!
+    #[structopt(verbatim_doc_comment, long = "fixed-time")]
+    fixed_time: Option<DateTime<Utc>>,
+
     /// URI to get the public key for the access token from.
     /// This parameter accepts normal web URLs and files.
     /// e.g.:
@@ -166,6 +307,11 @@ struct Options {
     #[structopt(verbatim_doc_comment, long = "tsl")]
     tsl: Url,
 
+    /// File containing the PEM encoded certificates that are trusted
+    /// to sign the TSL and the BNetzA-VL.
+    #[structopt(verbatim_doc_comment, long = "tsl-trust-anchor")]
+    tsl_trust_anchor: PathBuf,
+
     /// File to load log configuration from.
     #[structopt(
         verbatim_doc_comment,
@@ -209,4 +355,21 @@ struct Options {
         default_value = "999 Throttling active"
     )]
     throttling_header: String,
+
+    /// Time to keep the responses of requests with an Idempotency-Key
+    /// header (in s; 0 for disable)
+    #[structopt(
+        verbatim_doc_comment,
+        long = "idempotency-window",
+        default_value = "86400"
+    )]
+    idempotency_window: u64,
+
+    /// MIME type that is allowed for the attachments of communications
+    /// together with the maximum size of the attachment (in bytes), e.g.
+    ///     --attachment-type application/pdf=524288
+    /// Can be passed multiple times. If not passed, plain text (10 KiB),
+    /// PDF, JPEG and PNG (512 KiB each) are allowed.
+    #[structopt(verbatim_doc_comment, long = "attachment-type", number_of_values = 1)]
+    attachment_types: Vec<AttachmentType>,
 }
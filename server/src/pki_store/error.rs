@@ -19,12 +19,14 @@ use std::io::Error as IoError;
 
 use base64::DecodeError as Base64Error;
 use chrono::ParseError as ChronoError;
+use libxml::Error as LibXmlError;
 use miscellaneous::jwt::Error as JwtError;
 use openssl::{error::ErrorStack as OpenSslError, ocsp::OcspResponseStatus};
 use quick_xml::DeError as XmlError;
 use reqwest::{Error as ReqwestError, StatusCode};
 use thiserror::Error;
 use url::ParseError;
+use xmlsec::Error as XmlSecError;
 
 #[derive(Debug, Error)]
 #[allow(clippy::enum_variant_names)]
@@ -53,6 +55,12 @@ pub enum Error {
     #[error("JWT Error: {0}")]
     JwtError(JwtError),
 
+    #[error("LibXML Error: {0}")]
+    LibXmlError(LibXmlError),
+
+    #[error("XML Security Error: {0}")]
+    XmlSecError(XmlSecError),
+
     #[error("Invalid Response ({0} - {1})")]
     InvalidResponse(StatusCode, String),
 
@@ -85,6 +93,12 @@ pub enum Error {
 
     #[error("Fetching OCSP Response failed!")]
     FetchingOcspResponseFailed,
+
+    #[error("TSL is not covered by its signature!")]
+    TslNotSigned,
+
+    #[error("TSL signer certificate is not trusted!")]
+    UntrustedTslSigner,
 }
 
 impl From<IoError> for Error {
@@ -134,3 +148,15 @@ impl From<JwtError> for Error {
         Self::JwtError(err)
     }
 }
+
+impl From<LibXmlError> for Error {
+    fn from(err: LibXmlError) -> Self {
+        Self::LibXmlError(err)
+    }
+}
+
+impl From<XmlSecError> for Error {
+    fn from(err: XmlSecError) -> Self {
+        Self::XmlSecError(err)
+    }
+}
@@ -64,6 +64,7 @@ struct Inner {
     ocsp_list: OcspList,
     ocsp_vau: ArcSwapOption<OcspResponse>,
     dummy_store: X509Store,
+    tsl_trust_anchors: X509Store,
 }
 
 impl PkiStore {
@@ -72,6 +73,7 @@ impl PkiStore {
         enc_cert: X509,
         tsl: Url,
         bnetza: Url,
+        tsl_trust_anchors: Vec<X509>,
         puk_token: Url,
     ) -> Result<Self, Error> {
         let (cert_list_sender, cert_list_receiver) = channel(());
@@ -81,6 +83,12 @@ impl PkiStore {
         let ocsp_list = OcspList::new(ocsp_list_sender);
         let dummy_store = X509StoreBuilder::new()?.build();
 
+        let mut builder = X509StoreBuilder::new()?;
+        for cert in tsl_trust_anchors {
+            builder.add_cert(cert)?;
+        }
+        let tsl_trust_anchors = builder.build();
+
         let inner = Inner {
             enc_key,
             enc_cert,
@@ -91,6 +99,7 @@ impl PkiStore {
             ocsp_list,
             ocsp_vau: ArcSwapOption::from(None),
             dummy_store,
+            tsl_trust_anchors,
         };
 
         let store = Self(Arc::new(inner));
@@ -304,6 +313,7 @@ pub mod tests {
             ocsp_list: OcspList::new(ocsp_list_sender),
             ocsp_vau: ArcSwapOption::from(None),
             dummy_store: X509StoreBuilder::new().unwrap().build(),
+            tsl_trust_anchors: X509StoreBuilder::new().unwrap().build(),
         };
 
         PkiStore(Arc::new(inner))
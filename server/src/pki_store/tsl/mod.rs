@@ -17,6 +17,7 @@
 
 mod extract;
 mod update;
+mod verify;
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -31,6 +32,7 @@ use url::Url;
 
 pub use extract::extract;
 pub use update::update;
+pub use verify::verify;
 
 use extract::{ServiceInformation, TrustServiceStatusList};
 
@@ -58,20 +60,34 @@ pub enum TimeCheck {
 impl PkiStore {
     pub(super) fn spawn_tsl_task(&self, url: Url) {
         let store = self.clone();
-
-        spawn(update(url, true, prepare_tsl, move |tsl| {
-            store.0.tsl.store(Some(Arc::new(tsl)));
-            store.cert_list().update();
-            store.ocsp_list().update();
-        }));
+        let anchors = self.clone();
+
+        spawn(update(
+            url,
+            true,
+            prepare_tsl,
+            move |xml| verify(xml, &anchors.0.tsl_trust_anchors),
+            move |tsl| {
+                store.0.tsl.store(Some(Arc::new(tsl)));
+                store.cert_list().update();
+                store.ocsp_list().update();
+            },
+        ));
     }
 
     pub(super) fn spawn_bnetza_task(&self, url: Url) {
         let store = self.clone();
-
-        spawn(update(url, false, prepare_no_op, move |tsl| {
-            store.0.bnetza.store(Some(Arc::new(tsl)));
-        }));
+        let anchors = self.clone();
+
+        spawn(update(
+            url,
+            false,
+            prepare_no_op,
+            move |xml| verify(xml, &anchors.0.tsl_trust_anchors),
+            move |tsl| {
+                store.0.bnetza.store(Some(Arc::new(tsl)));
+            },
+        ));
     }
 }
 
@@ -31,9 +31,10 @@ use super::{
     Tsl,
 };
 
-pub async fn update<P, U>(url: Url, load_hash: bool, prepare: P, update: U)
+pub async fn update<P, V, U>(url: Url, load_hash: bool, prepare: P, verify: V, update: U)
 where
     P: Fn(&mut TrustServiceStatusList) -> Result<(), Error> + Send + Sync,
+    V: Fn(&str) -> Result<(), Error> + Send + Sync,
     U: Fn(Tsl) + Send + Sync,
 {
     let client = match Client::new() {
@@ -82,6 +83,12 @@ where
                 None
             };
 
+            ok!(
+                verify(&xml),
+                "Unable to verify signature of TSL ({}): {}",
+                &url
+            );
+
             let items = ok!(
                 extract(&xml, &prepare),
                 "Unable to extract certificats from TSL ({}): {}",
@@ -0,0 +1,99 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use libxml::Doc;
+use openssl::{
+    stack::Stack,
+    x509::{store::X509StoreRef, X509StoreContext},
+};
+use xmlsec::Node;
+
+use super::super::Error;
+
+pub fn verify(xml: &str, trust_anchors: &X509StoreRef) -> Result<(), Error> {
+    let doc: Doc = xml.parse()?;
+    let node_root = doc.root()?;
+
+    /* verify the enveloped signature
+     * (the signature must cover the whole document) */
+    let verified_nodes = node_root.verify()?;
+    if !verified_nodes.contains(node_root, None) {
+        return Err(Error::TslNotSigned);
+    }
+
+    /* check that the signer is trusted */
+    let signer_cert = node_root.signer_cert()?;
+    let chain = Stack::new()?;
+    let mut context = X509StoreContext::new()?;
+    let is_trusted = context.init(trust_anchors, &signer_cert, &chain, |c| c.verify_cert())?;
+    if !is_trusted {
+        return Err(Error::UntrustedTslSigner);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{read, read_to_string};
+
+    use openssl::x509::{
+        store::{X509Store, X509StoreBuilder},
+        X509,
+    };
+
+    fn store(path: &str) -> X509Store {
+        let cert = X509::from_pem(&read(path).unwrap()).unwrap();
+
+        let mut builder = X509StoreBuilder::new().unwrap();
+        builder.add_cert(cert).unwrap();
+        builder.build()
+    }
+
+    fn signed_tsl() -> String {
+        read_to_string("./examples/tsl_signed.xml").unwrap()
+    }
+
+    #[test]
+    fn verify_trusted_tsl() {
+        let anchors = store("./examples/tsl_trust_anchor.pem");
+
+        verify(&signed_tsl(), &anchors).unwrap();
+    }
+
+    #[test]
+    fn verify_tampered_tsl() {
+        let anchors = store("./examples/tsl_trust_anchor.pem");
+        let xml = signed_tsl().replace(
+            "<TSLSequenceNumber>1</TSLSequenceNumber>",
+            "<TSLSequenceNumber>2</TSLSequenceNumber>",
+        );
+
+        assert!(verify(&xml, &anchors).is_err());
+    }
+
+    #[test]
+    fn verify_untrusted_tsl() {
+        let anchors = store("./examples/tsl_untrusted_anchor.pem");
+
+        let res = verify(&signed_tsl(), &anchors);
+
+        assert!(matches!(res, Err(Error::UntrustedTslSigner)));
+    }
+}
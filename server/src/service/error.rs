@@ -43,9 +43,12 @@ use super::{
     misc::{AccessTokenError, DataType},
     routes::{
         audit_event::Error as AuditEventError,
+        binary::Error as BinaryError,
         capabilty_statement::Error as CapabiltyStatementError,
+        charge_item::Error as ChargeItemError,
         communication::Error as CommunicationError,
-        medication_dispense::Error as MedicationDispenseError, task::Error as TaskError,
+        medication_dispense::Error as MedicationDispenseError,
+        task::{Error as TaskError, KbvBundleError},
     },
 };
 
@@ -95,6 +98,8 @@ impl ResponseError for TypedRequestError {
             },
             E::DecodeXml(err) => res.status(StatusCode::BAD_REQUEST).expression_opt(err.path()),
             E::DecodeJson(err) => res.status(StatusCode::BAD_REQUEST).expression_opt(err.path()),
+            E::Payload(PayloadError::Overflow) => res.status(StatusCode::PAYLOAD_TOO_LARGE),
+            E::Payload(_) => res.status(StatusCode::BAD_REQUEST),
             E::EncodeXml(_) => res.status(StatusCode::INTERNAL_SERVER_ERROR).severity(Severity::Fatal),
             E::EncodeJson(_) => res.status(StatusCode::INTERNAL_SERVER_ERROR).severity(Severity::Fatal),
             E::CapabiltyStatementError(err) => match err {
@@ -105,8 +110,16 @@ impl ResponseError for TypedRequestError {
                 AuditEventError::NotFound(_) => res.status(StatusCode::NOT_FOUND).code(IssueType::ProcessingNotFound),
                 AuditEventError::Forbidden(_) => res.status(StatusCode::FORBIDDEN).code(IssueType::SecurityForbidden),
             },
+            E::BinaryError(err) => match err {
+                BinaryError::NotFound(_) => res.status(StatusCode::NOT_FOUND).code(IssueType::ProcessingNotFound),
+            },
             E::CommunicationError(err) => match err {
                 CommunicationError::ContentSizeExceeded => res.status(StatusCode::BAD_REQUEST).code(IssueType::ProcessingTooLong),
+                CommunicationError::AttachmentContentTypeMissing => res.status(StatusCode::BAD_REQUEST).code(IssueType::InvalidRequired).expression("/Communication/payload/contentAttachment/contentType".into()),
+                CommunicationError::AttachmentContentTypeNotAllowed(_) => res.status(StatusCode::BAD_REQUEST).code(IssueType::ProcessingNotSupported),
+                CommunicationError::AttachmentSizeMismatch => res.status(StatusCode::BAD_REQUEST).code(IssueType::InvalidValue),
+                CommunicationError::AttachmentHashMismatch => res.status(StatusCode::BAD_REQUEST).code(IssueType::InvalidValue),
+                CommunicationError::InvalidAttachmentUrl(_) => res.status(StatusCode::BAD_REQUEST).code(IssueType::InvalidValue),
                 CommunicationError::MissingFieldBasedOn => res.status(StatusCode::BAD_REQUEST).code(IssueType::InvalidRequired).expression("/Communication/basedOn".into()),
                 CommunicationError::SenderEqualRecipient => res.status(StatusCode::BAD_REQUEST),
                 CommunicationError::InvalidSender => res.status(StatusCode::BAD_REQUEST),
@@ -122,14 +135,27 @@ impl ResponseError for TypedRequestError {
                 MedicationDispenseError::NotFound(_) => res.status(StatusCode::NOT_FOUND).code(IssueType::ProcessingNotFound),
                 MedicationDispenseError::Forbidden(_) => res.status(StatusCode::FORBIDDEN).code(IssueType::SecurityForbidden),
             },
+            E::ChargeItemError(err) => match err {
+                ChargeItemError::NotFound(_) => res.status(StatusCode::NOT_FOUND).code(IssueType::ProcessingNotFound),
+                ChargeItemError::Forbidden(_) => res.status(StatusCode::FORBIDDEN).code(IssueType::SecurityForbidden),
+                ChargeItemError::UnknownTask(_) => res.status(StatusCode::BAD_REQUEST),
+                ChargeItemError::TaskForbidden(_) => res.status(StatusCode::FORBIDDEN).code(IssueType::SecurityForbidden),
+                ChargeItemError::InvalidFlowType(_) => res.status(StatusCode::BAD_REQUEST).code(IssueType::ProcessingNotSupported),
+                ChargeItemError::PrescriptionMismatch => res.status(StatusCode::BAD_REQUEST),
+                ChargeItemError::SubjectMissing => res.status(StatusCode::BAD_REQUEST),
+                ChargeItemError::Conflict(_) => res.status(StatusCode::CONFLICT).code(IssueType::ProcessingConflict),
+            },
             E::TaskError(err) => match err {
                 TaskError::SignedError(_) => res.status(StatusCode::INTERNAL_SERVER_ERROR).severity(Severity::Fatal),
                 TaskError::NotFound(_) => res.status(StatusCode::NOT_FOUND).code(IssueType::ProcessingNotFound),
+                TaskError::VersionNotFound(_, _) => res.status(StatusCode::NOT_FOUND).code(IssueType::ProcessingNotFound),
                 TaskError::Forbidden(_) => res.status(StatusCode::FORBIDDEN).code(IssueType::SecurityForbidden),
                 TaskError::Conflict(_) => res.status(StatusCode::CONFLICT).code(IssueType::ProcessingConflict),
                 TaskError::Gone(_) => res.status(StatusCode::GONE).code(IssueType::ProcessingConflict),
+                TaskError::PreconditionFailed(_) => res.status(StatusCode::PRECONDITION_FAILED).code(IssueType::ProcessingConflict),
                 TaskError::EPrescriptionMissing => res.status(StatusCode::BAD_REQUEST),
                 TaskError::EPrescriptionMismatch => res.status(StatusCode::BAD_REQUEST),
+                TaskError::MedicationDispenseMissing => res.status(StatusCode::BAD_REQUEST).code(IssueType::InvalidRequired),
                 TaskError::EPrescriptionNotFound(_) => res.status(StatusCode::INTERNAL_SERVER_ERROR).severity(Severity::Fatal),
                 TaskError::EPrescriptionAlreadyRegistered(_) => res.status(StatusCode::BAD_REQUEST),
                 TaskError::PatientReceiptNotFound(_) => res.status(StatusCode::INTERNAL_SERVER_ERROR).severity(Severity::Fatal),
@@ -144,6 +170,17 @@ impl ResponseError for TypedRequestError {
                 TaskError::InvalidUrl(_) => res.status(StatusCode::BAD_REQUEST),
                 TaskError::GeneratePrescriptionId => res.status(StatusCode::SERVICE_UNAVAILABLE).severity(Severity::Error),
                 TaskError::AuditEventAgentInvalid => res.status(StatusCode::BAD_REQUEST),
+                TaskError::UnsupportedFlowType(_) => res.status(StatusCode::BAD_REQUEST).code(IssueType::ProcessingNotSupported),
+                TaskError::RedeemPeriodNotStarted(_) => res.status(StatusCode::FORBIDDEN).code(IssueType::ProcessingBusinessRule),
+                TaskError::InvalidKbvBundle(err) => {
+                    let res = res.status(StatusCode::BAD_REQUEST).expression(err.expression());
+
+                    match err {
+                        KbvBundleError::MissingEntry(_) => res.code(IssueType::InvalidRequired),
+                        KbvBundleError::InvalidReference(_, _) => res.code(IssueType::InvalidInvariant),
+                        _ => res.code(IssueType::InvalidValue),
+                    }
+                }
             },
             E::CmsContainerError { warning, .. } => {
                 let res = res.status(StatusCode::BAD_REQUEST);
@@ -161,6 +198,7 @@ impl ResponseError for TypedRequestError {
             E::ContentTypeNotSupported => res.status(StatusCode::BAD_REQUEST),
             E::AcceptUnsupported => res.status(StatusCode::BAD_REQUEST),
             E::MissingAccessCode => res.status(StatusCode::UNAUTHORIZED),
+            E::IdempotencyKeyMismatch(_) => res.status(StatusCode::UNPROCESSABLE_ENTITY).code(IssueType::ProcessingConflict),
         };
 
         if res.details.is_none() {
@@ -187,6 +225,9 @@ pub enum RequestError {
     #[error("Error while decoding JSON: {0}")]
     DecodeJson(DecodeError<JsonDecodeError<PayloadError>>),
 
+    #[error("Error while reading payload: {0}")]
+    Payload(PayloadError),
+
     #[error("Error while encoding XML: {0}")]
     EncodeXml(EncodeError<XmlEncodeError>),
 
@@ -199,12 +240,18 @@ pub enum RequestError {
     #[error("Audit Event Resource Error: {0}")]
     AuditEventError(AuditEventError),
 
+    #[error("Binary Resource Error: {0}")]
+    BinaryError(BinaryError),
+
     #[error("Communication Resource Error: {0}")]
     CommunicationError(CommunicationError),
 
     #[error("Medication Dispense Resource Error: {0}")]
     MedicationDispenseError(MedicationDispenseError),
 
+    #[error("Charge Item Resource Error: {0}")]
+    ChargeItemError(ChargeItemError),
+
     #[error("Task Resource Error: {0}")]
     TaskError(TaskError),
 
@@ -234,6 +281,9 @@ pub enum RequestError {
 
     #[error("Missing Access Code!")]
     MissingAccessCode,
+
+    #[error("Idempotency key ({0}) was already used for a different request!")]
+    IdempotencyKeyMismatch(String),
 }
 
 impl RequestError {
@@ -410,6 +460,12 @@ impl IntoReqErr for DecodeError<JsonDecodeError<PayloadError>> {
     }
 }
 
+impl IntoReqErr for PayloadError {
+    fn into_req_err(self) -> RequestError {
+        RequestError::Payload(self)
+    }
+}
+
 impl IntoReqErr for EncodeError<XmlEncodeError> {
     fn into_req_err(self) -> RequestError {
         RequestError::EncodeXml(self)
@@ -434,6 +490,12 @@ impl IntoReqErr for AuditEventError {
     }
 }
 
+impl IntoReqErr for BinaryError {
+    fn into_req_err(self) -> RequestError {
+        RequestError::BinaryError(self)
+    }
+}
+
 impl IntoReqErr for CommunicationError {
     fn into_req_err(self) -> RequestError {
         RequestError::CommunicationError(self)
@@ -446,6 +508,12 @@ impl IntoReqErr for MedicationDispenseError {
     }
 }
 
+impl IntoReqErr for ChargeItemError {
+    fn into_req_err(self) -> RequestError {
+        RequestError::ChargeItemError(self)
+    }
+}
+
 impl IntoReqErr for TaskError {
     fn into_req_err(self) -> RequestError {
         RequestError::TaskError(self)
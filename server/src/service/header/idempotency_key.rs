@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::ops::Deref;
+
+use actix_web::{dev::Payload, http::header::HeaderName, FromRequest, HttpRequest};
+use futures::future::{err, ok, Ready};
+
+use crate::service::{RequestError, TypedRequestError};
+
+lazy_static! {
+    pub static ref IDEMPOTENCY_KEY: HeaderName =
+        HeaderName::from_lowercase(b"idempotency-key").unwrap();
+}
+
+/// `Idempotency-Key` header of a request that may be retried by the client.
+///
+/// The key is chosen by the client and has to consist of 1 to 255 visible
+/// ASCII characters.
+pub struct IdempotencyKey(pub String);
+
+impl FromRequest for IdempotencyKey {
+    type Error = TypedRequestError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let key = match req.headers().get(&*IDEMPOTENCY_KEY) {
+            Some(key) => key,
+            None => {
+                return err(
+                    RequestError::HeaderMissing(IDEMPOTENCY_KEY.to_string()).with_type_from(req)
+                )
+            }
+        };
+
+        match key.to_str() {
+            Ok(key) if is_valid_key(key) => ok(IdempotencyKey(key.to_owned())),
+            _ => err(RequestError::HeaderInvalid(IDEMPOTENCY_KEY.to_string()).with_type_from(req)),
+        }
+    }
+}
+
+impl Deref for IdempotencyKey {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= 255 && key.bytes().all(|b| b.is_ascii_graphic())
+}
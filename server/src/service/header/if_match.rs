@@ -0,0 +1,62 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use actix_web::{
+    dev::Payload,
+    http::header::{EntityTag, Header, IfMatch as IfMatchHeader, IF_MATCH},
+    FromRequest, HttpRequest,
+};
+use futures::future::{err, ok, Ready};
+
+use crate::service::{RequestError, TypedRequestError};
+
+/// `If-Match` header of a request that should only be executed if the
+/// resource was not changed in the meantime.
+pub struct IfMatch(pub IfMatchHeader);
+
+impl IfMatch {
+    /// Returns `true` if the passed entity tag matches one of the tags of
+    /// the header.
+    ///
+    /// The weak comparison is used on purpose, although RFC 7232 requires the
+    /// strong comparison for `If-Match`: FHIR uses weak entity tags that are
+    /// derived from the version ID (`W/"<versionId>"`) for conditional
+    /// updates, and these would never match with the strong comparison.
+    pub fn matches(&self, etag: &EntityTag) -> bool {
+        match &self.0 {
+            IfMatchHeader::Any => true,
+            IfMatchHeader::Items(items) => items.iter().any(|item| item.weak_eq(etag)),
+        }
+    }
+}
+
+impl FromRequest for IfMatch {
+    type Error = TypedRequestError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        if !req.headers().contains_key(IF_MATCH) {
+            return err(RequestError::HeaderMissing(IF_MATCH.to_string()).with_type_from(req));
+        }
+
+        match IfMatchHeader::parse(req) {
+            Ok(if_match) => ok(IfMatch(if_match)),
+            Err(_) => err(RequestError::HeaderInvalid(IF_MATCH.to_string()).with_type_from(req)),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use actix_web::{
+    dev::Payload,
+    http::header::{EntityTag, Header, IfNoneMatch as IfNoneMatchHeader, IF_NONE_MATCH},
+    FromRequest, HttpRequest,
+};
+use futures::future::{err, ok, Ready};
+
+use crate::service::{RequestError, TypedRequestError};
+
+/// `If-None-Match` header of a request that only needs a response if the
+/// resource was changed since the client received it.
+pub struct IfNoneMatch(pub IfNoneMatchHeader);
+
+impl IfNoneMatch {
+    /// Returns `true` if the passed entity tag matches one of the tags of
+    /// the header.
+    pub fn matches(&self, etag: &EntityTag) -> bool {
+        match &self.0 {
+            IfNoneMatchHeader::Any => true,
+            IfNoneMatchHeader::Items(items) => items.iter().any(|item| item.weak_eq(etag)),
+        }
+    }
+}
+
+impl FromRequest for IfNoneMatch {
+    type Error = TypedRequestError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        if !req.headers().contains_key(IF_NONE_MATCH) {
+            return err(RequestError::HeaderMissing(IF_NONE_MATCH.to_string()).with_type_from(req));
+        }
+
+        match IfNoneMatchHeader::parse(req) {
+            Ok(if_none_match) => ok(IfNoneMatch(if_none_match)),
+            Err(_) => {
+                err(RequestError::HeaderInvalid(IF_NONE_MATCH.to_string()).with_type_from(req))
+            }
+        }
+    }
+}
@@ -19,10 +19,16 @@ mod accept;
 mod accept_language;
 mod authorization;
 mod content_type;
+mod idempotency_key;
+mod if_match;
+mod if_none_match;
 mod x_access_code;
 
 pub use accept::{Accept, ACCEPT};
 pub use accept_language::{AcceptLanguage, ACCEPT_LANGUAGE};
 pub use authorization::Authorization;
 pub use content_type::{ContentType, CONTENT_TYPE};
+pub use idempotency_key::{IdempotencyKey, IDEMPOTENCY_KEY};
+pub use if_match::IfMatch;
+pub use if_none_match::IfNoneMatch;
 pub use x_access_code::{XAccessCode, X_ACCESS_CODE};
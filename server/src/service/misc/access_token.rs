@@ -307,4 +307,21 @@ bTruoO+7I/dqnloCIAtzL2Vk1W3dHT+3Z5Qiaa3vWnAuaBELd6wj9oY9W5aA
         let access_token = r##"eyJhbGciOiJCUDI1NlIxIn0.eyJzdWIiOiJzdWJqZWN0Iiwib3JnYW5pemF0aW9uTmFtZSI6ImdlbWF0aWsgR21iSCBOT1QtVkFMSUQiLCJwcm9mZXNzaW9uT0lEIjoiMS4yLjI3Ni4wLjc2LjQuNDkiLCJpZE51bW1lciI6IlgxMTQ0Mjg1MzAiLCJpc3MiOiJzZW5kZXIiLCJyZXNwb25zZV90eXBlIjoiY29kZSIsImNvZGVfY2hhbGxlbmdlX21ldGhvZCI6IlMyNTYiLCJnaXZlbl9uYW1lIjoiSnVuYSIsImNsaWVudF9pZCI6bnVsbCwiYXVkIjoiZXJwLnplbnRyYWwuZXJwLnRpLWRpZW5zdGUuZGUiLCJhY3IiOiJlaWRhcy1sb2EtaGlnaCIsInNjb3BlIjoib3BlbmlkIGUtcmV6ZXB0Iiwic3RhdGUiOiJhZjBpZmpzbGRraiIsInJlZGlyZWN0X3VyaSI6bnVsbCwiZXhwIjoxNjAzMTk3NjUyLCJmYW1pbHlfbmFtZSI6IkZ1Y2hzIiwiY29kZV9jaGFsbGVuZ2UiOm51bGwsImlhdCI6MTYwMzE5NzM1MiwiYXV0aF90aW1lIjoxNjAzMTk3MzUyfQ.XqPmrlF-6elvj6sAU0mH2GmBoggef-RYpTdJ3Ae9KiB3n7yvc3W27wH9hcTm4gSbdddNZ1_oZfP_Rc-U2Jb9Sg"##;
         AccessToken::verify(access_token, pub_key, now.into()).unwrap();
     }
+
+    pub fn access_token(profession: Profession, id_number: &str) -> AccessToken {
+        AccessToken {
+            iss: Default::default(),
+            sub: Default::default(),
+            aud: Default::default(),
+            nonce: None,
+            exp: Utc::now(),
+            iat: Utc::now(),
+            nbf: None,
+            profession,
+            id_number: id_number.into(),
+            given_name: None,
+            family_name: None,
+            organization_name: None,
+        }
+    }
 }
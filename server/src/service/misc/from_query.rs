@@ -46,6 +46,14 @@ impl QueryValue<'_> {
 
 pub trait FromQuery: Default {
     fn parse_key_value_pair(&mut self, key: &str, value: QueryValue) -> Result<(), String>;
+
+    /// Returns `true` if the passed key is a supported query parameter.
+    ///
+    /// Unsupported parameters are ignored, unless the client requested
+    /// strict handling (`Prefer: handling=strict`).
+    fn is_supported(_key: &str) -> bool {
+        true
+    }
 }
 
 impl<T> FromRequest for Query<T>
@@ -58,9 +66,16 @@ where
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
         let q = req.query_string();
+        let is_strict = is_strict_handling(req);
         let mut ret = T::default();
 
         for (key, value) in parse(q.as_bytes()) {
+            if is_strict && !T::is_supported(&key) {
+                let err = format!("Unsupported query parameter: {}", key);
+
+                return ready(Err(RequestError::QueryInvalid(err).with_type_from(req)));
+            }
+
             let value = if value.is_empty() {
                 None
             } else {
@@ -77,3 +92,13 @@ where
         ready(Ok(Query(ret)))
     }
 }
+
+/// Returns `true` if the client requested strict handling of the search
+/// parameters (`Prefer: handling=strict`).
+fn is_strict_handling(req: &HttpRequest) -> bool {
+    req.headers()
+        .get_all("Prefer")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(|c: char| c == ',' || c == ';'))
+        .any(|pref| pref.trim().eq_ignore_ascii_case("handling=strict"))
+}
@@ -0,0 +1,367 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use actix_web::{
+    dev::{Body, ResponseBody},
+    http::{HeaderName, HeaderValue, StatusCode},
+    HttpRequest, HttpResponse,
+};
+use bytes::Bytes;
+use log::warn;
+use openssl::sha::Sha256;
+use resources::misc::TelematikId;
+
+use crate::{
+    service::{header::IdempotencyKey, RequestError},
+    state::Inner,
+};
+
+use super::{AccessToken, AccessTokenError};
+
+/// Time the responses of requests with an `Idempotency-Key` are kept by
+/// default.
+const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum number of responses that are kept for a single client. If the
+/// limit is reached, the response that expires first is dropped.
+const MAX_RESPONSES_PER_CLIENT: usize = 1000;
+
+/// Responses of requests that were sent with an `Idempotency-Key` header.
+///
+/// The responses are only kept in memory for the configured window and are
+/// limited per client. If the same client retries a request with the same
+/// key, the stored response is returned instead of executing the request
+/// again.
+pub struct Idempotency {
+    window: Duration,
+    responses: HashMap<TelematikId, HashMap<String, StoredResponse>>,
+}
+
+/// Key of a request that was sent with an `Idempotency-Key` header.
+///
+/// Keys are chosen by the clients, so they are only unique in combination
+/// with the telematik ID of the client. The hash of the request is used to
+/// detect retries that do not match the original request.
+pub struct RequestKey {
+    telematik_id: TelematikId,
+    key: String,
+    hash: [u8; 32],
+}
+
+struct StoredResponse {
+    hash: [u8; 32],
+    status: StatusCode,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    body: Bytes,
+    expires_at: Instant,
+}
+
+impl Idempotency {
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    /// Drop all responses whose window has expired.
+    fn prune(&mut self, now: Instant) {
+        self.responses.retain(|_, responses| {
+            responses.retain(|_, response| response.expires_at > now);
+
+            !responses.is_empty()
+        });
+    }
+}
+
+impl Default for Idempotency {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_IDEMPOTENCY_WINDOW,
+            responses: HashMap::new(),
+        }
+    }
+}
+
+impl RequestKey {
+    pub fn new(
+        access_token: &AccessToken,
+        key: IdempotencyKey,
+        request: &HttpRequest,
+        payload: &[u8],
+    ) -> Result<Self, AccessTokenError> {
+        let telematik_id = access_token.telematik_id()?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(request.method().as_str().as_bytes());
+        hasher.update(b" ");
+        hasher.update(request.uri().to_string().as_bytes());
+        hasher.update(b"\n");
+        hasher.update(payload);
+
+        Ok(Self {
+            telematik_id,
+            key: key.0,
+            hash: hasher.finish(),
+        })
+    }
+}
+
+impl Inner {
+    /// Get the stored response of a previous request with the same key.
+    ///
+    /// Returns an error if the key was already used for a different request.
+    pub fn idempotency_replay(
+        &mut self,
+        key: Option<&RequestKey>,
+    ) -> Result<Option<HttpResponse>, RequestError> {
+        let key = match key {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+
+        self.idempotency.prune(Instant::now());
+
+        let response = match self
+            .idempotency
+            .responses
+            .get(&key.telematik_id)
+            .and_then(|responses| responses.get(&key.key))
+        {
+            Some(response) => response,
+            None => return Ok(None),
+        };
+
+        if response.hash != key.hash {
+            return Err(RequestError::IdempotencyKeyMismatch(key.key.clone()));
+        }
+
+        let mut res = HttpResponse::build(response.status);
+        for (name, value) in &response.headers {
+            res.header(name.clone(), value.clone());
+        }
+
+        Ok(Some(res.body(response.body.clone())))
+    }
+
+    /// Store the response of a request, so it can be replayed if the client
+    /// retries the request with the same key.
+    pub fn idempotency_store(
+        &mut self,
+        key: Option<RequestKey>,
+        res: HttpResponse,
+    ) -> HttpResponse {
+        let key = match key {
+            Some(key) => key,
+            None => return res,
+        };
+
+        let window = self.idempotency.window;
+        if window == Duration::from_secs(0) {
+            return res;
+        }
+
+        let body = match res.body() {
+            ResponseBody::Body(Body::Bytes(body)) => body.clone(),
+            _ => {
+                warn!(
+                    "Unable to store response for idempotency key {}: body is not buffered",
+                    key.key
+                );
+
+                return res;
+            }
+        };
+
+        let now = Instant::now();
+        self.idempotency.prune(now);
+
+        let responses = self
+            .idempotency
+            .responses
+            .entry(key.telematik_id)
+            .or_default();
+        if !responses.contains_key(&key.key) && responses.len() >= MAX_RESPONSES_PER_CLIENT {
+            let oldest = responses
+                .iter()
+                .min_by_key(|(_, response)| response.expires_at)
+                .map(|(oldest, _)| oldest.clone());
+            if let Some(oldest) = oldest {
+                responses.remove(&oldest);
+            }
+        }
+
+        let response = StoredResponse {
+            hash: key.hash,
+            status: res.status(),
+            headers: res
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            body,
+            expires_at: now + window,
+        };
+
+        responses.insert(key.key, response);
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::test::TestRequest;
+
+    use crate::{
+        service::misc::{access_token::tests::access_token, Profession},
+        state::tests::state,
+    };
+
+    #[tokio::test]
+    async fn replay_stored_response() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let key = request_key("606358757", "key", b"payload");
+        assert!(state.idempotency_replay(Some(&key)).unwrap().is_none());
+
+        let res = HttpResponse::Created()
+            .header("X-Test", "value")
+            .body("response");
+        state.idempotency_store(Some(key), res);
+
+        let key = request_key("606358757", "key", b"payload");
+        let res = state.idempotency_replay(Some(&key)).unwrap().unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert_eq!(
+            Some(&HeaderValue::from_static("value")),
+            res.headers().get("X-Test")
+        );
+        match res.body() {
+            ResponseBody::Body(Body::Bytes(body)) => assert_eq!(&b"response"[..], &body[..]),
+            _ => panic!("Unexpected body of the replayed response"),
+        }
+
+        /* the same key of a different client is a different request */
+        let key = request_key("3-SMC-B-Testkarte-883110000120312", "key", b"payload");
+        assert!(state.idempotency_replay(Some(&key)).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn reject_key_of_different_request() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let key = request_key("606358757", "key", b"payload");
+        state.idempotency_store(Some(key), HttpResponse::Created().body("response"));
+
+        let key = request_key("606358757", "key", b"other payload");
+        match state.idempotency_replay(Some(&key)) {
+            Err(RequestError::IdempotencyKeyMismatch(key)) => assert_eq!("key", key),
+            _ => panic!("Key of a different request was accepted"),
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_expired_responses() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let key = request_key("606358757", "key", b"payload");
+        state.idempotency_store(Some(key), HttpResponse::Created().body("response"));
+
+        expire_responses(&mut state);
+
+        let key = request_key("606358757", "key", b"payload");
+        assert!(state.idempotency_replay(Some(&key)).unwrap().is_none());
+        assert!(state.idempotency.responses.is_empty());
+
+        /* responses are not stored at all if the window is disabled */
+        state.idempotency.set_window(Duration::from_secs(0));
+
+        let key = request_key("606358757", "key", b"payload");
+        state.idempotency_store(Some(key), HttpResponse::Created().body("response"));
+        assert!(state.idempotency.responses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn drop_expired_responses_on_store() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let key = request_key("606358757", "key", b"payload");
+        state.idempotency_store(Some(key), HttpResponse::Created().body("response"));
+        expire_responses(&mut state);
+
+        let key = request_key("606358758", "key", b"payload");
+        state.idempotency_store(Some(key), HttpResponse::Created().body("response"));
+        assert_eq!(1, state.idempotency.responses.len());
+        assert!(state
+            .idempotency
+            .responses
+            .contains_key(&TelematikId::new("606358758")));
+    }
+
+    #[tokio::test]
+    async fn limit_responses_per_client() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let key = request_key("606358757", "key-0", b"payload");
+        state.idempotency_store(Some(key), HttpResponse::Created().body("response"));
+        let responses = state
+            .idempotency
+            .responses
+            .get_mut(&TelematikId::new("606358757"))
+            .unwrap();
+        responses.get_mut("key-0").unwrap().expires_at = Instant::now() + Duration::from_secs(60);
+
+        for i in 1..=MAX_RESPONSES_PER_CLIENT {
+            let key = request_key("606358757", &format!("key-{}", i), b"payload");
+            state.idempotency_store(Some(key), HttpResponse::Created().body("response"));
+        }
+
+        let key = request_key("606358758", "key-0", b"payload");
+        state.idempotency_store(Some(key), HttpResponse::Created().body("response"));
+
+        /* the response that expires first is dropped */
+        let responses = &state.idempotency.responses[&TelematikId::new("606358757")];
+        assert_eq!(MAX_RESPONSES_PER_CLIENT, responses.len());
+        assert!(!responses.contains_key("key-0"));
+
+        let key = request_key("606358758", "key-0", b"payload");
+        assert!(state.idempotency_replay(Some(&key)).unwrap().is_some());
+    }
+
+    fn expire_responses(state: &mut Inner) {
+        for responses in state.idempotency.responses.values_mut() {
+            for response in responses.values_mut() {
+                response.expires_at = Instant::now();
+            }
+        }
+    }
+
+    fn request_key(telematik_id: &str, key: &str, payload: &[u8]) -> RequestKey {
+        let access_token = access_token(Profession::OeffentlicheApotheke, telematik_id);
+        let request = TestRequest::post().uri("/Task/$create").to_http_request();
+
+        RequestKey::new(&access_token, IdempotencyKey(key.into()), &request, payload).unwrap()
+    }
+}
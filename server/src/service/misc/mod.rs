@@ -18,6 +18,7 @@
 pub mod access_token;
 pub mod data_type;
 pub mod from_query;
+pub mod idempotency;
 pub mod logging;
 pub mod search;
 pub mod sort;
@@ -25,18 +26,36 @@ pub mod sort;
 pub use access_token::{AccessToken, Error as AccessTokenError, Profession};
 pub use data_type::DataType;
 pub use from_query::{FromQuery, Query, QueryValue};
+pub use idempotency::{Idempotency, RequestKey};
 pub use search::Search;
 pub use sort::Sort;
 
 use std::convert::TryInto;
 
-use actix_web::{dev::HttpResponseBuilder, http::StatusCode, web::Payload, HttpResponse};
+use actix_web::{
+    dev::HttpResponseBuilder,
+    error::PayloadError,
+    http::{
+        header::{EntityTag, ETAG, LAST_MODIFIED},
+        HeaderValue, StatusCode,
+    },
+    web::Payload,
+    HttpResponse,
+};
+use bytes::{Bytes, BytesMut};
+use futures::{
+    future::ready,
+    stream::{once, Stream, StreamExt},
+};
+use openssl::sha::sha256;
 use regex::{Captures, Regex};
 use resources::device::{Device, DeviceName, Status, Type};
 
 use crate::fhir::{decode::Decode, encode::Encode};
 
-use super::{IntoReqErrResult, RequestError, TypedRequestError, TypedRequestResult};
+use super::{
+    header::IfNoneMatch, IntoReqErrResult, RequestError, TypedRequestError, TypedRequestResult,
+};
 
 lazy_static! {
     pub static ref DEVICE: Device = Device {
@@ -51,6 +70,9 @@ lazy_static! {
     };
 }
 
+/// Maximum size of a payload that is read into memory as a whole.
+const MAX_PAYLOAD_SIZE: usize = 4 * 1024 * 1024; // 4 MB
+
 pub fn format_version() -> String {
     static VERSIONS: [Option<&str>; 2] = [
         option_env!("GIT_VERSION_TAG"),
@@ -87,9 +109,42 @@ pub fn format_version() -> String {
     ret
 }
 
-pub async fn read_payload<T>(data_type: DataType, mut payload: Payload) -> Result<T, RequestError>
+pub async fn read_payload<T>(data_type: DataType, payload: Payload) -> Result<T, RequestError>
+where
+    T: Decode,
+{
+    decode_stream(data_type, payload).await
+}
+
+/// Read the whole payload of the request into memory.
+///
+/// Payloads larger than `MAX_PAYLOAD_SIZE` are rejected.
+pub async fn read_payload_bytes(mut payload: Payload) -> Result<Bytes, RequestError> {
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > MAX_PAYLOAD_SIZE {
+            return Err(PayloadError::Overflow.into());
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes.freeze())
+}
+
+/// Decode a payload that was already read into memory.
+pub async fn decode_payload<T>(data_type: DataType, payload: Bytes) -> Result<T, RequestError>
 where
     T: Decode,
+{
+    decode_stream(data_type, once(ready(Ok::<_, PayloadError>(payload)))).await
+}
+
+async fn decode_stream<T, S>(data_type: DataType, mut payload: S) -> Result<T, RequestError>
+where
+    T: Decode,
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
 {
     match data_type {
         #[cfg(feature = "support-xml")]
@@ -144,7 +199,7 @@ where
 
             f(&mut res);
 
-            Ok(res.body(xml))
+            Ok(with_etag(res, xml))
         }
 
         #[cfg(feature = "support-json")]
@@ -158,13 +213,60 @@ where
 
             f(&mut res);
 
-            Ok(res.body(json))
+            Ok(with_etag(res, json))
         }
 
         DataType::Any | DataType::Unknown => panic!("Data type of response was not specified"),
     }
 }
 
+/// Returns `304 Not Modified` instead of the passed response, if the entity
+/// tag of the response matches the `If-None-Match` header of the request.
+pub fn check_not_modified(res: HttpResponse, if_none_match: Option<&IfNoneMatch>) -> HttpResponse {
+    let if_none_match = match if_none_match {
+        Some(if_none_match) if res.status() == StatusCode::OK => if_none_match,
+        _ => return res,
+    };
+
+    let etag = res
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .and_then(|etag| etag.parse::<EntityTag>().ok());
+    match etag {
+        Some(etag) if if_none_match.matches(&etag) => (),
+        _ => return res,
+    }
+
+    let mut not_modified = HttpResponse::NotModified();
+    for name in &[ETAG, LAST_MODIFIED] {
+        if let Some(value) = res.headers().get(name) {
+            not_modified.header(name.clone(), value.clone());
+        }
+    }
+
+    not_modified.finish()
+}
+
+/// Create the response with the passed body and add a weak entity tag that
+/// is derived from the body, if no entity tag was set before.
+fn with_etag(mut res: HttpResponseBuilder, body: Bytes) -> HttpResponse {
+    let hash = sha256(&body);
+    let etag = hash[..16]
+        .iter()
+        .map(|x| format!("{:02x}", x))
+        .collect::<String>();
+    let etag = EntityTag::weak(etag).to_string();
+
+    let mut res = res.body(body);
+    if !res.headers().contains_key(ETAG) {
+        res.headers_mut()
+            .insert(ETAG, HeaderValue::from_str(&etag).unwrap());
+    }
+
+    res
+}
+
 pub fn make_page_uri(uri: &str, query: &str, page_id: usize) -> String {
     lazy_static! {
         static ref RX: Regex = Regex::new("(&?)(pageId|pageid|page-id)=[^&]").unwrap();
@@ -190,6 +292,8 @@ pub fn make_page_uri(uri: &str, query: &str, page_id: usize) -> String {
 pub mod tests {
     use super::*;
 
+    use actix_web::test::TestRequest;
+
     #[test]
     fn make_page_uri_test() {
         assert_eq!("/Task?pageId=6", make_page_uri("/Task", "", 6));
@@ -210,4 +314,21 @@ pub mod tests {
             make_page_uri("/Task", "bar=2&baz=3&page-id=1", 10)
         );
     }
+
+    #[tokio::test]
+    async fn read_payload_bytes_respects_max_size() {
+        let (_, payload) = TestRequest::default()
+            .set_payload(vec![0u8; MAX_PAYLOAD_SIZE])
+            .to_http_parts();
+        let bytes = read_payload_bytes(Payload(payload)).await.unwrap();
+        assert_eq!(MAX_PAYLOAD_SIZE, bytes.len());
+
+        let (_, payload) = TestRequest::default()
+            .set_payload(vec![0u8; MAX_PAYLOAD_SIZE + 1])
+            .to_http_parts();
+        match read_payload_bytes(Payload(payload)).await {
+            Err(RequestError::Payload(PayloadError::Overflow)) => (),
+            _ => panic!("Payload exceeding the maximum size was accepted"),
+        }
+    }
 }
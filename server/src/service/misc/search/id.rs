@@ -0,0 +1,42 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::convert::TryFrom;
+
+use resources::primitives::Id;
+
+use super::{Comperator, Parameter};
+
+impl Parameter for Id {
+    type Storage = Id;
+
+    fn parse(s: &str) -> Result<Self::Storage, String> {
+        Id::try_from(s).map_err(|_| format!("Invalid ID: {}", s))
+    }
+
+    fn compare(&self, comperator: Comperator, param: &Self::Storage) -> bool {
+        match comperator {
+            Comperator::Equal => self == param,
+            Comperator::NotEqual => self != param,
+            _ => false,
+        }
+    }
+
+    fn has_comperator() -> bool {
+        false
+    }
+}
@@ -17,7 +17,12 @@
 
 mod audit_event;
 mod date_time;
+mod id;
+mod kvnr;
 mod option;
+mod performer_type;
+mod prescription_id;
+mod pzn_code;
 mod string;
 mod task_status;
 mod telematik_id;
@@ -35,6 +40,12 @@ pub trait Parameter: Sized {
     fn parse(s: &str) -> Result<Self::Storage, String>;
 
     fn compare(&self, comperator: Comperator, param: &Self::Storage) -> bool;
+
+    /// Returns `false` if the values of the parameter must not be prefixed
+    /// with a comperator (like `eq` or `ge`), which is the case for tokens.
+    fn has_comperator() -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -80,6 +91,7 @@ impl<T: Parameter> FromStr for Search<T> {
             .split(',')
             .map(|s: &str| -> Result<(Comperator, T::Storage), Self::Err> {
                 let (comperator, s) = match s {
+                    s if !T::has_comperator() => (Comperator::Equal, s),
                     s if s.starts_with("eq") => (Comperator::Equal, &s[2..]),
                     s if s.starts_with("ne") => (Comperator::NotEqual, &s[2..]),
                     s if s.starts_with("gt") => (Comperator::GreaterThan, &s[2..]),
@@ -101,3 +113,64 @@ impl<T: Parameter> FromStr for Search<T> {
         Ok(Self { args })
     }
 }
+
+/// Get the value of a token parameter (`[system|]value`).
+///
+/// The system is optional, but if it is passed it has to match the expected
+/// system of the parameter.
+fn parse_token<'a>(s: &'a str, expected_system: &str) -> Result<&'a str, String> {
+    let mut parts = s.splitn(2, '|');
+    let first = parts.next().unwrap_or_default();
+
+    match parts.next() {
+        None => Ok(first),
+        Some(value) if first.is_empty() || first == expected_system => Ok(value),
+        Some(_) => Err(format!("Invalid system: {}", first)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryFrom;
+
+    use resources::{misc::PrescriptionId, primitives::Id};
+
+    #[test]
+    fn parse_token_search_parameter() {
+        let prescription_id: PrescriptionId = "160.123.456.789.123.58".parse().unwrap();
+
+        for s in &[
+            "160.123.456.789.123.58",
+            "|160.123.456.789.123.58",
+            "https://gematik.de/fhir/NamingSystem/PrescriptionID|160.123.456.789.123.58",
+        ] {
+            let search: Search<PrescriptionId> = s.parse().unwrap();
+
+            assert!(search.matches(&prescription_id), "{}", s);
+        }
+
+        assert!("http://example.com|160.123.456.789.123.58"
+            .parse::<Search<PrescriptionId>>()
+            .is_err());
+
+        // Tokens must not be interpreted as comperator prefixes
+        let id = Id::try_from("eb1a0c9e-b2c0-4a2f-8c48-a7f1e9f0b1b3").unwrap();
+        let search: Search<Id> = id.to_string().parse().unwrap();
+        assert!(search.matches(&id));
+    }
+
+    #[test]
+    fn parse_missing_modifier() {
+        let missing = Search::<Option<String>>::missing("true").unwrap();
+        assert!(missing.matches(&None));
+        assert!(!missing.matches(&Some("value".into())));
+
+        let present = Search::<Option<String>>::missing("false").unwrap();
+        assert!(!present.matches(&None));
+        assert!(present.matches(&Some("value".into())));
+
+        assert!(Search::<Option<String>>::missing("null").is_err());
+    }
+}
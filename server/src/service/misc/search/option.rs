@@ -15,7 +15,25 @@
  *
  */
 
-use super::{Comperator, Parameter};
+use super::{Comperator, Parameter, Search};
+
+impl<T: Parameter> Search<Option<T>> {
+    /// Create a search for the `:missing` modifier of a parameter.
+    ///
+    /// `true` matches values that are not set, `false` matches values that
+    /// are set.
+    pub fn missing(s: &str) -> Result<Self, String> {
+        let comperator = match s {
+            "true" => Comperator::Equal,
+            "false" => Comperator::NotEqual,
+            s => return Err(format!("Invalid value for missing modifier: {}", s)),
+        };
+
+        Ok(Self {
+            args: vec![(comperator, None)],
+        })
+    }
+}
 
 impl<T> Parameter for Option<T>
 where
@@ -73,4 +91,8 @@ where
             },
         }
     }
+
+    fn has_comperator() -> bool {
+        T::has_comperator()
+    }
 }
@@ -0,0 +1,46 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use resources::misc::PrescriptionId;
+
+use super::{parse_token, Comperator, Parameter};
+
+impl Parameter for PrescriptionId {
+    type Storage = PrescriptionId;
+
+    fn parse(s: &str) -> Result<Self::Storage, String> {
+        let value = parse_token(s, SYSTEM_PRESCRIPTION_ID)?;
+
+        value
+            .parse()
+            .map_err(|_| format!("Invalid prescription ID: {}", value))
+    }
+
+    fn compare(&self, comperator: Comperator, param: &Self::Storage) -> bool {
+        match comperator {
+            Comperator::Equal => self == param,
+            Comperator::NotEqual => self != param,
+            _ => false,
+        }
+    }
+
+    fn has_comperator() -> bool {
+        false
+    }
+}
+
+const SYSTEM_PRESCRIPTION_ID: &str = "https://gematik.de/fhir/NamingSystem/PrescriptionID";
@@ -0,0 +1,44 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use resources::medication::PznCode;
+
+use super::{parse_token, Comperator, Parameter};
+
+impl Parameter for PznCode {
+    type Storage = String;
+
+    fn parse(s: &str) -> Result<Self::Storage, String> {
+        let value = parse_token(s, SYSTEM_PZN)?;
+
+        Ok(value.to_owned())
+    }
+
+    fn compare(&self, comperator: Comperator, param: &Self::Storage) -> bool {
+        match comperator {
+            Comperator::Equal => &self.code == param,
+            Comperator::NotEqual => &self.code != param,
+            _ => false,
+        }
+    }
+
+    fn has_comperator() -> bool {
+        false
+    }
+}
+
+const SYSTEM_PZN: &str = "http://fhir.de/CodeSystem/ifa/pzn";
@@ -34,11 +34,14 @@ pub use error::{
     AsAuditEventOutcome, IntoReqErr, IntoReqErrResult, RequestError, TypedRequestError,
     TypedRequestResult,
 };
+pub use header::XAccessCode;
 use middleware::{AccessLog, HeaderCheck, Vau};
+pub use misc::Idempotency;
 use routes::configure_routes;
 pub use routes::{
     audit_event::{AuditEventBuilder, AuditEvents},
-    communication::Communications,
+    charge_item::ChargeItems,
+    communication::{AttachmentType, AttachmentTypes, Communications},
     medication_dispense::MedicationDispenses,
     task::{TaskMeta, Tasks},
 };
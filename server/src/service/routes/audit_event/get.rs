@@ -111,8 +111,8 @@ pub async fn get_all(
 
     let kvnr = access_token.kvnr().into_req_err().err_with_type(accept)?;
 
-    let state = state.lock().await;
-    let mut events: Vec<&AuditEvent> = state
+    let state = state.read().await;
+    let mut events: Vec<AuditEvent> = state
         .audit_event_iter(&kvnr, |av| check_query(&query, av))
         .collect();
 
@@ -162,7 +162,7 @@ pub async fn get_all(
     bundle.total = Some(result_count);
 
     let lang = accept_language.into();
-    for audit_event in events.into_iter().skip(skip).take(take) {
+    for audit_event in events.iter().skip(skip).take(take) {
         let cntr = AuditEventContainer { audit_event, lang };
         let mut entry = Entry::new(cntr);
         entry.url = Some(format!("/AuditEvent/{}", &audit_event.id));
@@ -225,13 +225,13 @@ pub async fn get_one(
     let id = id.into_inner();
     let kvnr = access_token.kvnr().into_req_err().err_with_type(accept)?;
 
-    let state = state.lock().await;
+    let state = state.read().await;
     let audit_event = state
         .audit_event_get(id, &kvnr)
         .into_req_err()
         .err_with_type(accept)?;
     let cntr = AuditEventContainer {
-        audit_event,
+        audit_event: &audit_event,
         lang: accept_language.into(),
     };
 
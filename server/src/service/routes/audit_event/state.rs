@@ -15,12 +15,10 @@
  *
  */
 
-use std::cell::RefCell;
 use std::collections::{hash_map::Entry, HashMap, HashSet};
 use std::fmt::Display;
-use std::rc::Rc;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use chrono::Utc;
 use resources::{
     audit_event::{
         Action, Agent, AuditEvent, Entity, Outcome, ParticipationRoleType, Source, SubType, Text,
@@ -32,20 +30,32 @@ use resources::{
 
 use crate::{
     service::misc::DEVICE,
-    state::{Inner, Timeouts},
+    state::{Clock, Inner, Table, Timeouts},
 };
 
 use super::Error;
 
+/// Audit events of the service.
+///
+/// Audit events are also recorded by requests that only read the state, so
+/// they are guarded by their own lock and can be modified through a shared
+/// reference to the state.
 #[derive(Default)]
 pub struct AuditEvents {
-    by_id: HashMap<Id, AuditEvent>,
+    inner: RwLock<AuditEventsInner>,
+}
+
+#[derive(Default)]
+struct AuditEventsInner {
+    by_id: Table<Id, AuditEvent>,
     by_kvnr: HashMap<Kvnr, HashSet<Id>>,
     by_task: HashMap<Id, HashSet<Id>>,
 }
 
 impl AuditEvents {
-    pub fn insert(&mut self, audit_event: AuditEvent) {
+    pub fn insert(&self, audit_event: AuditEvent) {
+        let mut inner = self.write();
+
         let id = audit_event.id.clone();
         let kvnr = audit_event.entity.name.clone();
         let task_id = match &audit_event.entity.what {
@@ -54,7 +64,7 @@ impl AuditEvents {
             _ => None,
         };
 
-        match self.by_id.entry(id.clone()) {
+        match inner.by_id.entry(id.clone()) {
             Entry::Occupied(_) => {
                 panic!("Audit event with this ID ({}) already exists!", id);
             }
@@ -63,26 +73,62 @@ impl AuditEvents {
             }
         }
 
-        self.by_kvnr.entry(kvnr).or_default().insert(id.clone());
+        inner.by_kvnr.entry(kvnr).or_default().insert(id.clone());
 
         if let Some(task_id) = task_id {
-            self.by_task.entry(task_id).or_default().insert(id);
+            inner.by_task.entry(task_id).or_default().insert(id);
         }
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &AuditEvent> {
-        self.by_id.values()
+    pub fn to_vec(&self) -> Vec<AuditEvent> {
+        self.read().by_id.values().cloned().collect()
+    }
+
+    pub fn get_by_id(&self, id: &Id) -> Option<AuditEvent> {
+        self.read().by_id.get(id).cloned()
+    }
+
+    pub fn remove_by_id(&self, id: &Id) {
+        let mut inner = self.write();
+        let AuditEventsInner {
+            ref mut by_id,
+            ref mut by_kvnr,
+            ref mut by_task,
+        } = &mut *inner;
+
+        let audit_event = match by_id.remove(id) {
+            Some(audit_event) => audit_event,
+            None => return,
+        };
+
+        if let Some(ids) = by_kvnr.get_mut(&audit_event.entity.name) {
+            ids.remove(id);
+        }
+
+        if let What::Task(task_id) = &audit_event.entity.what {
+            if let Some(ids) = by_task.get_mut(task_id) {
+                ids.remove(id);
+            }
+        }
+    }
+
+    pub fn take_changes(&self) -> HashSet<Id> {
+        self.write().by_id.take_changes()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, AuditEventsInner> {
+        self.inner.read().expect("Audit events lock is poisoned!")
     }
 
-    pub fn get_by_id(&self, id: &Id) -> Option<&AuditEvent> {
-        self.by_id.get(id)
+    fn write(&self) -> RwLockWriteGuard<'_, AuditEventsInner> {
+        self.inner.write().expect("Audit events lock is poisoned!")
     }
 }
 
 impl Inner {
-    pub fn audit_event_get(&self, id: Id, kvnr: &Kvnr) -> Result<&AuditEvent, Error> {
-        let event = match self.audit_events.by_id.get(&id) {
-            Some(events) => events,
+    pub fn audit_event_get(&self, id: Id, kvnr: &Kvnr) -> Result<AuditEvent, Error> {
+        let event = match self.audit_events.get_by_id(&id) {
+            Some(event) => event,
             None => return Err(Error::NotFound(id)),
         };
 
@@ -93,75 +139,42 @@ impl Inner {
         Ok(event)
     }
 
-    pub fn audit_event_iter<F>(&self, kvnr: &Kvnr, mut f: F) -> impl Iterator<Item = &AuditEvent>
+    pub fn audit_event_iter<F>(&self, kvnr: &Kvnr, mut f: F) -> impl Iterator<Item = AuditEvent>
     where
         F: FnMut(&AuditEvent) -> bool,
     {
-        let Self {
-            ref audit_events, ..
-        } = self;
+        let inner = self.audit_events.read();
 
-        lazy_static! {
-            static ref EMPTY: HashSet<Id> = HashSet::new();
-        }
-
-        let events = match audit_events.by_kvnr.get(&kvnr) {
-            Some(events) => events,
-            None => &EMPTY,
+        let events = match inner.by_kvnr.get(&kvnr) {
+            Some(events) => events
+                .iter()
+                .map(|id| inner.by_id.get(&id).unwrap())
+                .filter(|event| f(event))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
         };
 
-        events.iter().filter_map(move |id| {
-            let v = audit_events.by_id.get(&id).unwrap();
-            if f(v) {
-                Some(v)
-            } else {
-                None
-            }
-        })
-    }
-
-    pub fn audit_event_iter_by_task(
-        &self,
-        task_id: &Id,
-    ) -> Option<impl Iterator<Item = &AuditEvent>> {
-        let AuditEvents {
-            ref by_id,
-            ref by_task,
-            ..
-        } = &self.audit_events;
-
-        Some(
-            by_task
-                .get(task_id)?
-                .iter()
-                .map(move |id| by_id.get(id).unwrap()),
-        )
+        events.into_iter()
     }
 
-    pub fn audit_event_delete_by_id(&mut self, id: &Id) {
-        let Self {
-            ref mut audit_events,
-            ..
-        } = self;
+    pub fn audit_event_iter_by_task(&self, task_id: &Id) -> impl Iterator<Item = AuditEvent> {
+        let inner = self.audit_events.read();
 
-        let audit_event = audit_events.by_id.get(id).unwrap();
-        let kvnr = &audit_event.entity.name;
-
-        if let Some(ids) = audit_events.by_kvnr.get_mut(&kvnr) {
-            ids.remove(id);
-        }
-
-        let ids = match &audit_event.entity.what {
-            What::Task(task_id) => audit_events.by_task.get_mut(&task_id),
-            What::MedicationDispense(_) => None,
-            _ => None,
+        let events = match inner.by_task.get(task_id) {
+            Some(events) => events
+                .iter()
+                .map(|id| inner.by_id.get(id).unwrap())
+                .cloned()
+                .collect(),
+            None => Vec::new(),
         };
 
-        if let Some(ids) = ids {
-            ids.remove(id);
-        }
+        events.into_iter()
+    }
 
-        audit_events.by_id.remove(id);
+    pub fn audit_event_delete_by_id(&mut self, id: &Id) {
+        self.audit_events.remove_by_id(id);
     }
 
     pub fn agent() -> &'static Agent {
@@ -169,8 +182,9 @@ impl Inner {
     }
 
     pub fn logged<F, T, E>(
-        audit_events: &mut AuditEvents,
-        timeouts: Rc<RefCell<&mut Timeouts>>,
+        audit_events: &AuditEvents,
+        timeouts: &Timeouts,
+        clock: &Clock,
         f: F,
     ) -> Result<T, E>
     where
@@ -182,8 +196,7 @@ impl Inner {
 
         let err = ret.as_ref().err().map(|err| format!("{}", err));
 
-        let mut timeouts = timeouts.borrow_mut();
-        builder.build(audit_events, &mut timeouts, err);
+        builder.build(audit_events, timeouts, clock, err);
 
         ret
     }
@@ -222,8 +235,9 @@ impl Builder {
 
     pub fn build(
         self,
-        audit_events: &mut AuditEvents,
-        timeouts: &mut Timeouts,
+        audit_events: &AuditEvents,
+        timeouts: &Timeouts,
+        clock: &Clock,
         error: Option<String>,
     ) -> Option<()> {
         let sub_type = self.sub_type?;
@@ -247,7 +261,7 @@ impl Builder {
             text,
             sub_type,
             action,
-            recorded: Utc::now().into(),
+            recorded: clock.now().into(),
             outcome,
             outcome_description,
             agent,
@@ -0,0 +1,66 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use actix_web::{
+    web::{Data, Path},
+    HttpResponse,
+};
+use resources::primitives::Id;
+
+use crate::{
+    service::{
+        header::{Accept, Authorization},
+        misc::{create_response, DataType, Profession},
+        IntoReqErrResult, TypedRequestError, TypedRequestResult,
+    },
+    state::State,
+};
+
+#[allow(clippy::match_like_matches_macro)]
+pub async fn get_one(
+    state: Data<State>,
+    id: Path<Id>,
+    accept: Accept,
+    access_token: Authorization,
+) -> Result<HttpResponse, TypedRequestError> {
+    let accept = DataType::from_accept(&accept)
+        .and_then(DataType::ignore_any)
+        .unwrap_or_default()
+        .check_supported()
+        .err_with_type_default()?;
+
+    access_token
+        .check_profession(|p| match p {
+            Profession::Versicherter => true,
+            Profession::KrankenhausApotheke => true,
+            Profession::OeffentlicheApotheke => true,
+            _ => false,
+        })
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    let id = id.into_inner();
+    let participant_id = access_token.id().into_req_err().err_with_type(accept)?;
+
+    let state = state.read().await;
+    let binary = state
+        .binary_get(id, &participant_id)
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    create_response(binary, accept)
+}
@@ -0,0 +1,78 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use resources::{misc::ParticipantId, primitives::Id, Binary};
+
+use crate::state::Inner;
+
+use super::Error;
+
+impl Inner {
+    /// Get a binary that is referenced by a communication.
+    ///
+    /// The binary can only be read by the sender and the recipient of the
+    /// communications that reference it. The ID of a binary is the hash of
+    /// its data, so binaries that are not accessible are reported as not
+    /// found, to not reveal if the data is stored for someone else.
+    pub fn binary_get(&self, id: Id, participant_id: &ParticipantId) -> Result<&Binary, Error> {
+        let binary = match self.binaries.get_by_id(&id) {
+            Some(binary) => binary,
+            None => return Err(Error::NotFound(id)),
+        };
+
+        if !self
+            .communications
+            .is_binary_accessible(&id, participant_id)
+        {
+            return Err(Error::NotFound(id));
+        }
+
+        Ok(binary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryFrom;
+
+    use resources::misc::TelematikId;
+
+    use crate::state::tests::state;
+
+    #[tokio::test]
+    async fn inaccessible_binary_is_not_found() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let id = Id::try_from("a94a8fe5ccb19ba61c4c0873d391e987982fbbd3").unwrap();
+        state.binaries.insert(Binary {
+            id: id.clone(),
+            content_type: "text/plain".into(),
+            data: "dGVzdA==".into(),
+        });
+
+        let participant_id = ParticipantId::TelematikId(TelematikId::new("606358757"));
+        let res = state.binary_get(id, &participant_id);
+        assert!(matches!(res, Err(Error::NotFound(_))));
+
+        let id = Id::generate().unwrap();
+        let res = state.binary_get(id, &participant_id);
+        assert!(matches!(res, Err(Error::NotFound(_))));
+    }
+}
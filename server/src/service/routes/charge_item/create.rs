@@ -0,0 +1,77 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use actix_web::{
+    http::StatusCode,
+    web::{Data, Payload, Query},
+    HttpResponse,
+};
+use resources::{primitives::Id, ChargeItem};
+use serde::Deserialize;
+
+use crate::service::{
+    header::{Accept, Authorization, ContentType},
+    misc::{create_response_with, read_payload, DataType, Profession},
+    IntoReqErrResult, State, TypedRequestError, TypedRequestResult,
+};
+
+#[derive(Deserialize)]
+pub struct QueryArgs {
+    task: Id,
+    secret: Option<String>,
+}
+
+pub async fn create(
+    state: Data<State>,
+    accept: Accept,
+    access_token: Authorization,
+    content_type: ContentType,
+    query: Query<QueryArgs>,
+    payload: Payload,
+) -> Result<HttpResponse, TypedRequestError> {
+    let data_type = DataType::from_mime(&content_type);
+    let accept = DataType::from_accept(&accept)
+        .unwrap_or_default()
+        .replace_any(data_type)
+        .check_supported()
+        .err_with_type_default()?;
+
+    access_token
+        .check_profession(|p| {
+            p == Profession::OeffentlicheApotheke || p == Profession::KrankenhausApotheke
+        })
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    let QueryArgs { task, secret } = query.into_inner();
+    let enterer = access_token
+        .telematik_id()
+        .into_req_err()
+        .err_with_type(accept)?;
+    let charge_item = read_payload::<ChargeItem>(data_type, payload)
+        .await
+        .err_with_type(accept)?;
+    let agent = (&*access_token).into();
+
+    let mut state = state.lock().await;
+    let charge_item = state
+        .charge_item_create(task, secret, enterer, charge_item, agent)
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    create_response_with(charge_item, accept, StatusCode::CREATED, |_| ())
+}
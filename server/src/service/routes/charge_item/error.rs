@@ -0,0 +1,46 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use resources::{misc::PrescriptionId, primitives::Id, types::FlowType};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Not Found: /ChargeItem/{0}!")]
+    NotFound(Id),
+
+    #[error("Forbidden: /ChargeItem/{0}!")]
+    Forbidden(Id),
+
+    #[error("Unknown Task: {0}!")]
+    UnknownTask(Id),
+
+    #[error("Forbidden: /Task/{0}!")]
+    TaskForbidden(Id),
+
+    #[error("Charge items are only supported for private insurance prescriptions: {0}!")]
+    InvalidFlowType(FlowType),
+
+    #[error("Prescription ID of the charge item does not match the task!")]
+    PrescriptionMismatch,
+
+    #[error("Task is missing the subject!")]
+    SubjectMissing,
+
+    #[error("Charge item for prescription {0} already exists!")]
+    Conflict(PrescriptionId),
+}
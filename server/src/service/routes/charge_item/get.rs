@@ -0,0 +1,110 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use actix_web::{
+    web::{Data, Path},
+    HttpResponse,
+};
+use resources::{
+    bundle::{Bundle, Entry, Type},
+    primitives::Id,
+    ChargeItem,
+};
+
+use crate::{
+    service::{
+        header::{Accept, Authorization},
+        misc::{create_response, DataType, Profession},
+        IntoReqErrResult, TypedRequestError, TypedRequestResult,
+    },
+    state::State,
+};
+
+pub async fn get_all(
+    state: Data<State>,
+    accept: Accept,
+    access_token: Authorization,
+) -> Result<HttpResponse, TypedRequestError> {
+    let accept = DataType::from_accept(&accept)
+        .and_then(DataType::ignore_any)
+        .unwrap_or_default()
+        .check_supported()
+        .err_with_type_default()?;
+
+    access_token
+        .check_profession(|p| p == Profession::Versicherter)
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    let kvnr = access_token.kvnr().into_req_err().err_with_type(accept)?;
+    let agent = (&*access_token).into();
+    let state = state.read().await;
+
+    let mut results = state.charge_item_iter(&kvnr, agent).collect::<Vec<_>>();
+    results.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut bundle = Bundle::new(Type::Searchset);
+    bundle.total = Some(results.len());
+
+    for result in results {
+        add_to_bundle(&mut bundle, result);
+    }
+
+    create_response(&bundle, accept)
+}
+
+pub async fn get_one(
+    state: Data<State>,
+    id: Path<Id>,
+    accept: Accept,
+    access_token: Authorization,
+) -> Result<HttpResponse, TypedRequestError> {
+    let accept = DataType::from_accept(&accept)
+        .and_then(DataType::ignore_any)
+        .unwrap_or_default()
+        .check_supported()
+        .err_with_type_default()?;
+
+    access_token
+        .check_profession(|p| p == Profession::Versicherter)
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    let id = id.into_inner();
+    let kvnr = access_token.kvnr().into_req_err().err_with_type(accept)?;
+    let agent = (&*access_token).into();
+    let state = state.read().await;
+    let charge_item = state
+        .charge_item_get(id, &kvnr, agent)
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    create_response(charge_item, accept)
+}
+
+fn add_to_bundle<'b, 's>(bundle: &'b mut Bundle<&'s ChargeItem>, charge_item: &'s ChargeItem)
+where
+    's: 'b,
+{
+    let mut entry = Entry::new(charge_item);
+    entry.url = charge_item
+        .id
+        .as_ref()
+        .map(|id| format!("/ChargeItem/{}", id));
+
+    bundle.entries.push(entry);
+}
@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+mod create;
+mod delete;
+mod error;
+mod get;
+mod state;
+
+pub use error::Error;
+pub use state::ChargeItems;
+
+use actix_web::web::{delete, get, post, resource, ServiceConfig};
+use proc_macros::capability_statement_resource;
+use resources::capability_statement::{Interaction, Type};
+
+use create::create;
+use delete::delete_one;
+use get::{get_all, get_one};
+
+use crate::fhir::definitions::RESOURCE_PROFILE_CHARGE_ITEM;
+
+#[derive(Default)]
+pub struct ChargeItemRoutes;
+
+#[capability_statement_resource(
+    type = Type::ChargeItem,
+    profile = RESOURCE_PROFILE_CHARGE_ITEM)]
+impl ChargeItemRoutes {
+    #[interaction(Interaction::Create)]
+    #[interaction(Interaction::Read)]
+    #[interaction(Interaction::Delete)]
+    fn configure_all(&self, cfg: &mut ServiceConfig) {
+        cfg.service(
+            resource("/ChargeItem")
+                .route(get().to(get_all))
+                .route(post().to(create)),
+        );
+        cfg.service(
+            resource("/ChargeItem/{id}")
+                .route(get().to(get_one))
+                .route(delete().to(delete_one)),
+        );
+    }
+}
@@ -0,0 +1,344 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::convert::TryFrom;
+
+use resources::{
+    audit_event::{Action, Agent, SubType, Text, What},
+    misc::{Kvnr, PrescriptionId, TelematikId},
+    primitives::Id,
+    task::Status,
+    ChargeItem,
+};
+
+use crate::state::{Inner, Table};
+
+use super::Error;
+
+#[derive(Default)]
+pub struct ChargeItems {
+    by_id: Table<Id, ChargeItem>,
+    by_kvnr: HashMap<Kvnr, HashSet<Id>>,
+}
+
+impl ChargeItems {
+    pub fn insert(&mut self, charge_item: ChargeItem) {
+        let id = charge_item.id.as_ref().unwrap().clone();
+        let kvnr = charge_item.subject.clone();
+
+        match self.by_id.entry(id.clone()) {
+            Entry::Occupied(_) => {
+                panic!("Charge item with this ID ({}) already exists!", id);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(charge_item);
+            }
+        }
+
+        self.by_kvnr.entry(kvnr).or_default().insert(id);
+    }
+
+    pub fn get_by_id(&self, id: &Id) -> Option<&ChargeItem> {
+        self.by_id.get(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ChargeItem> {
+        self.by_id.values()
+    }
+
+    pub fn take_changes(&mut self) -> HashSet<Id> {
+        self.by_id.take_changes()
+    }
+
+    pub fn remove_by_id(&mut self, id: &Id) {
+        if let Some(charge_item) = self.by_id.remove(id) {
+            if let Some(ids) = self.by_kvnr.get_mut(&charge_item.subject) {
+                ids.remove(id);
+            }
+        }
+    }
+
+    /// Remove the charge item of the task with the passed prescription ID.
+    pub fn remove_by_prescription_id(&mut self, prescription_id: &PrescriptionId) {
+        let id = Id::try_from(prescription_id.to_string()).unwrap();
+
+        self.remove_by_id(&id);
+    }
+}
+
+impl Inner {
+    pub fn charge_item_create(
+        &mut self,
+        task_id: Id,
+        secret: Option<String>,
+        enterer: TelematikId,
+        mut charge_item: ChargeItem,
+        agent: Agent,
+    ) -> Result<&ChargeItem, Error> {
+        let Self {
+            ref tasks,
+            ref medication_dispenses,
+            ref mut charge_items,
+            ref audit_events,
+            ref timeouts,
+            ref clock,
+            ..
+        } = self;
+
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
+            let task = match tasks.get_by_id(&task_id) {
+                Some(task_meta) => &task_meta.task,
+                None => return Err(Error::UnknownTask(task_id)),
+            };
+
+            let prescription_id = match &task.identifier.prescription_id {
+                Some(prescription_id) if prescription_id == &charge_item.prescription_id => {
+                    prescription_id
+                }
+                _ => return Err(Error::PrescriptionMismatch),
+            };
+            let id = Id::try_from(prescription_id.to_string()).unwrap();
+
+            event_builder.agent(agent);
+            event_builder.action(Action::Create);
+            event_builder.sub_type(SubType::Create);
+            event_builder.what(What::ChargeItem(id.clone()));
+            event_builder.patient_opt(task.for_.clone());
+            event_builder.description(prescription_id.clone());
+            event_builder.text(Text::ChargeItemCreate);
+
+            let flow_type = task.extension.flow_type;
+            if !flow_type.is_pkv() {
+                return Err(Error::InvalidFlowType(flow_type));
+            }
+
+            if task.status != Status::Completed || task.identifier.secret != secret {
+                return Err(Error::TaskForbidden(task_id));
+            }
+
+            /* only the pharmacy that dispensed the medication may enter the charge item */
+
+            let mut dispensed = medication_dispenses
+                .iter_by_prescription_id(prescription_id)
+                .peekable();
+            let is_performer =
+                dispensed.peek().is_some() && dispensed.all(|md| md.performer == enterer);
+            if !is_performer {
+                return Err(Error::TaskForbidden(task_id));
+            }
+
+            if charge_items.by_id.contains_key(&id) {
+                return Err(Error::Conflict(prescription_id.clone()));
+            }
+
+            let subject = task.for_.clone().ok_or(Error::SubjectMissing)?;
+
+            let mut supporting_information = Vec::new();
+            if let Some(e_prescription) = &task.input.e_prescription {
+                supporting_information.push(format!("Bundle/{}", e_prescription));
+            }
+            if let Some(receipt) = &task.output.receipt {
+                supporting_information.push(format!("Bundle/{}", receipt));
+            }
+
+            charge_item.id = Some(id.clone());
+            charge_item.subject = subject;
+            charge_item.enterer = enterer;
+            charge_item.entered_date = Some(clock.now().into());
+            charge_item.supporting_information = supporting_information;
+
+            // The signature of the dispense data is deliberately not verified:
+            // pharmacies sign it with their institution card (SMC-B), which is
+            // not covered by the trust list that is used to verify prescriptions.
+            charge_items.insert(charge_item);
+
+            Ok(charge_items.by_id.get(&id).unwrap())
+        })
+    }
+
+    pub fn charge_item_get(&self, id: Id, kvnr: &Kvnr, agent: Agent) -> Result<&ChargeItem, Error> {
+        let Self {
+            ref charge_items,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
+            ..
+        } = self;
+
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
+            let charge_item = match charge_items.by_id.get(&id) {
+                Some(charge_item) => charge_item,
+                None => return Err(Error::NotFound(id)),
+            };
+
+            event_builder.agent(agent);
+            event_builder.action(Action::Read);
+            event_builder.sub_type(SubType::Read);
+            event_builder.what(What::ChargeItem(id.clone()));
+            event_builder.patient(kvnr.clone());
+            event_builder.description(charge_item.prescription_id.clone());
+            event_builder.text(Text::ChargeItemGetOne);
+
+            if &charge_item.subject != kvnr {
+                return Err(Error::Forbidden(id));
+            }
+
+            Ok(charge_item)
+        })
+    }
+
+    pub fn charge_item_iter<'a>(
+        &'a self,
+        kvnr: &'a Kvnr,
+        agent: Agent,
+    ) -> impl Iterator<Item = &'a ChargeItem> {
+        let Self {
+            ref charge_items,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
+            ..
+        } = self;
+
+        lazy_static! {
+            static ref EMPTY: HashSet<Id> = HashSet::new();
+        }
+
+        let mut event_builder = Self::audit_event_builder();
+        event_builder.agent(agent);
+        event_builder.action(Action::Read);
+        event_builder.sub_type(SubType::Read);
+        event_builder.what(What::ChargeItems);
+        event_builder.patient(kvnr.clone());
+        event_builder.text(Text::ChargeItemGetMany);
+        event_builder.build(audit_events, timeouts, clock, None);
+
+        let items = match charge_items.by_kvnr.get(&kvnr) {
+            Some(items) => items,
+            None => &EMPTY,
+        };
+
+        items
+            .iter()
+            .map(move |id| charge_items.by_id.get(&id).unwrap())
+    }
+
+    pub fn charge_item_delete(&mut self, id: Id, kvnr: &Kvnr, agent: Agent) -> Result<(), Error> {
+        let Self {
+            ref mut charge_items,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
+            ..
+        } = self;
+
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
+            let charge_item = match charge_items.by_id.get(&id) {
+                Some(charge_item) => charge_item,
+                None => return Err(Error::NotFound(id)),
+            };
+
+            event_builder.agent(agent);
+            event_builder.action(Action::Delete);
+            event_builder.sub_type(SubType::Delete);
+            event_builder.what(What::ChargeItem(id.clone()));
+            event_builder.patient(kvnr.clone());
+            event_builder.description(charge_item.prescription_id.clone());
+            event_builder.text(Text::ChargeItemDelete);
+
+            if &charge_item.subject != kvnr {
+                return Err(Error::Forbidden(id));
+            }
+
+            charge_items.remove_by_id(&id);
+
+            Ok(())
+        })
+    }
+
+    pub fn charge_item_delete_by_id(&mut self, id: &Id) {
+        self.charge_items.remove_by_id(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use resources::{charge_item::DispenseItem, types::FlowType};
+
+    use crate::service::{
+        misc::{access_token::tests::access_token, Profession},
+        routes::task::tests::create_ready_task,
+    };
+    use crate::state::tests::state;
+
+    #[tokio::test]
+    async fn charge_items_are_removed_with_the_task() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let patient = access_token(Profession::Versicherter, "X123456789");
+
+        // aborted task
+        let (id, _) = create_ready_task(
+            &mut state,
+            FlowType::ApothekenpflichtigeArzneimittelPkv,
+            &kvnr,
+        );
+        let charge_item_id = insert_charge_item(&mut state, &id);
+
+        state
+            .task_abort(id, &patient, None, None, None, (&patient).into())
+            .unwrap();
+        assert!(state.charge_items.get_by_id(&charge_item_id).is_none());
+
+        // deleted task
+        let (id, _) = create_ready_task(
+            &mut state,
+            FlowType::ApothekenpflichtigeArzneimittelPkv,
+            &kvnr,
+        );
+        let charge_item_id = insert_charge_item(&mut state, &id);
+
+        state.task_delete_by_id(&id);
+        assert!(state.charge_items.get_by_id(&charge_item_id).is_none());
+    }
+
+    fn insert_charge_item(state: &mut Inner, task_id: &Id) -> Id {
+        let task = &state.tasks.get_by_id(task_id).unwrap().task;
+        let prescription_id = task.identifier.prescription_id.clone().unwrap();
+        let id = Id::try_from(prescription_id.to_string()).unwrap();
+
+        state.charge_items.insert(ChargeItem {
+            id: Some(id.clone()),
+            prescription_id,
+            subject: task.for_.clone().unwrap(),
+            enterer: TelematikId::new("606358757"),
+            entered_date: None,
+            supporting_information: Vec::new(),
+            dispense_item: DispenseItem {
+                id: Id::generate().unwrap(),
+                data: Default::default(),
+            },
+        });
+
+        id
+    }
+}
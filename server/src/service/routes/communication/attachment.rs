@@ -0,0 +1,226 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use base64::encode as base64_encode;
+use openssl::sha::sha1;
+use resources::{communication::Attachment, primitives::Id, Binary};
+use vau::hex_encode;
+
+use super::Error;
+
+/// MIME type that is allowed for the attachments of communications together
+/// with the maximum size of the attachment data (in bytes).
+///
+/// Parsed from `<mime type>=<max size>`, e.g. `application/pdf=524288`.
+#[derive(Clone, Debug)]
+pub struct AttachmentType {
+    pub mime_type: String,
+    pub max_size: usize,
+}
+
+/// Allow-list of the MIME types of communication attachments.
+#[derive(Clone, Debug)]
+pub struct AttachmentTypes(Vec<AttachmentType>);
+
+impl AttachmentTypes {
+    pub fn new(types: Vec<AttachmentType>) -> Self {
+        Self(types)
+    }
+
+    /// Get the maximum size of attachments with the passed content type, or
+    /// `None` if the content type is not allowed. Parameters of the content
+    /// type (like `charset`) are ignored.
+    pub fn max_size(&self, content_type: &str) -> Option<usize> {
+        let mime_type = content_type.split(';').next().unwrap_or_default().trim();
+
+        self.0
+            .iter()
+            .find(|t| t.mime_type.eq_ignore_ascii_case(mime_type))
+            .map(|t| t.max_size)
+    }
+}
+
+impl Default for AttachmentTypes {
+    fn default() -> Self {
+        Self(vec![
+            AttachmentType::new("text/plain", 10 * 1024),
+            AttachmentType::new("application/pdf", 512 * 1024),
+            AttachmentType::new("image/jpeg", 512 * 1024),
+            AttachmentType::new("image/png", 512 * 1024),
+        ])
+    }
+}
+
+impl AttachmentType {
+    fn new(mime_type: &str, max_size: usize) -> Self {
+        Self {
+            mime_type: mime_type.into(),
+            max_size,
+        }
+    }
+}
+
+impl FromStr for AttachmentType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '=');
+        let mime_type = parts.next().unwrap_or_default().trim();
+        let max_size = parts
+            .next()
+            .ok_or_else(|| format!("Missing maximum size: {}", s))?
+            .trim();
+
+        if mime_type.is_empty() {
+            return Err(format!("Missing MIME type: {}", s));
+        }
+
+        let max_size = max_size
+            .parse()
+            .map_err(|_| format!("Invalid maximum size: {}", max_size))?;
+
+        Ok(Self::new(mime_type, max_size))
+    }
+}
+
+/// Verify the data of the passed attachment and move it to a binary.
+///
+/// The size and the SHA-1 hash of the attachment are checked against the
+/// data (or set, if they are missing) and the attachment references the
+/// returned binary instead of containing the data itself. Attachments
+/// without data are returned unchanged.
+pub fn take_attachment_data(
+    attachment: &mut Attachment,
+    attachment_types: &AttachmentTypes,
+) -> Result<Option<Binary>, Error> {
+    let data = match attachment.data.take() {
+        Some(data) => data,
+        None if attachment.url.as_deref().and_then(binary_id).is_some() => {
+            return Err(Error::InvalidAttachmentUrl(attachment.url.clone().unwrap()))
+        }
+        None => return Ok(None),
+    };
+
+    let content_type = attachment
+        .content_type
+        .clone()
+        .ok_or(Error::AttachmentContentTypeMissing)?;
+    let max_size = attachment_types
+        .max_size(&content_type)
+        .ok_or_else(|| Error::AttachmentContentTypeNotAllowed(content_type.clone()))?;
+    if data.len() > max_size {
+        return Err(Error::ContentSizeExceeded);
+    }
+
+    match attachment.size {
+        Some(size) if size != data.len() => return Err(Error::AttachmentSizeMismatch),
+        _ => (),
+    }
+
+    let hash = sha1(&data).to_vec();
+    match &attachment.hash {
+        Some(expected) if expected != &hash => return Err(Error::AttachmentHashMismatch),
+        _ => (),
+    }
+
+    let id = Id::try_from(hex_encode(&hash).to_lowercase()).unwrap();
+
+    attachment.url = Some(format!("Binary/{}", id));
+    attachment.size = Some(data.len());
+    attachment.hash = Some(hash);
+
+    Ok(Some(Binary {
+        id,
+        content_type,
+        data: base64_encode(&data),
+    }))
+}
+
+/// Get the ID of the binary the passed attachment URL refers to.
+pub fn binary_id(url: &str) -> Option<Id> {
+    let id = url.strip_prefix("Binary/")?;
+
+    Id::try_from(id).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(data: &[u8]) -> Attachment {
+        Attachment {
+            content_type: Some("text/plain; charset=utf-8".into()),
+            language: None,
+            data: Some(data.to_vec()),
+            url: None,
+            size: None,
+            hash: None,
+            title: None,
+            creation: None,
+        }
+    }
+
+    #[test]
+    fn take_attachment_data_moves_data_to_binary() {
+        let mut attachment = attachment(b"Hello World");
+
+        let binary = take_attachment_data(&mut attachment, &Default::default())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            binary.id.to_string(),
+            "0a4d55a8d778e5022fab701977c5d840bbc486d0"
+        );
+        assert_eq!(binary.data, "SGVsbG8gV29ybGQ=");
+        assert_eq!(attachment.data, None);
+        assert_eq!(
+            attachment.url.as_deref(),
+            Some("Binary/0a4d55a8d778e5022fab701977c5d840bbc486d0")
+        );
+        assert_eq!(attachment.size, Some(11));
+    }
+
+    #[test]
+    fn take_attachment_data_verifies_attachment() {
+        let mut invalid_hash = attachment(b"Hello World");
+        invalid_hash.hash = Some(vec![0; 20]);
+
+        let mut invalid_size = attachment(b"Hello World");
+        invalid_size.size = Some(12);
+
+        let mut invalid_type = attachment(b"Hello World");
+        invalid_type.content_type = Some("application/x-msdownload".into());
+
+        let types = AttachmentTypes::default();
+        assert!(matches!(
+            take_attachment_data(&mut invalid_hash, &types),
+            Err(Error::AttachmentHashMismatch)
+        ));
+        assert!(matches!(
+            take_attachment_data(&mut invalid_size, &types),
+            Err(Error::AttachmentSizeMismatch)
+        ));
+        assert!(matches!(
+            take_attachment_data(&mut invalid_type, &types),
+            Err(Error::AttachmentContentTypeNotAllowed(_))
+        ));
+    }
+}
@@ -23,6 +23,21 @@ pub enum Error {
     #[error("Content Size Exceeded!")]
     ContentSizeExceeded,
 
+    #[error("Attachment Content Type Missing!")]
+    AttachmentContentTypeMissing,
+
+    #[error("Attachment Content Type Not Allowed: {0}!")]
+    AttachmentContentTypeNotAllowed(String),
+
+    #[error("Attachment Size Mismatch!")]
+    AttachmentSizeMismatch,
+
+    #[error("Attachment Hash Mismatch!")]
+    AttachmentHashMismatch,
+
+    #[error("Invalid Attachment Url: {0}!")]
+    InvalidAttachmentUrl(String),
+
     #[error("Missing Field: 'basedOn'!")]
     MissingFieldBasedOn,
 
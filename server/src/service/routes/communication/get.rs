@@ -30,21 +30,24 @@ use resources::{
 
 use crate::{
     service::{
-        header::{Accept, Authorization},
-        misc::{create_response, DataType, FromQuery, Profession, Query, QueryValue, Search, Sort},
+        header::{Accept, Authorization, IfNoneMatch},
+        misc::{
+            check_not_modified, create_response, DataType, FromQuery, Profession, Query,
+            QueryValue, Search, Sort,
+        },
         IntoReqErrResult, TypedRequestError, TypedRequestResult,
     },
     state::State,
 };
 
-use super::CommunicationRefMut;
-
 #[derive(Default, Debug)]
 pub struct QueryArgs {
     sent: Vec<Search<DateTime<Utc>>>,
     received: Vec<Search<Option<DateTime<Utc>>>>,
+    received_missing: bool,
     sender: Vec<Search<String>>,
     recipient: Vec<Search<String>>,
+    since: Option<DateTime<Utc>>,
     sort: Option<Sort<SortArgs>>,
 }
 
@@ -53,8 +56,20 @@ impl FromQuery for QueryArgs {
         match key {
             "sent" => self.sent.push(value.ok()?.parse()?),
             "received" => self.received.push(value.ok()?.parse()?),
+            "received:missing" => {
+                self.received.push(Search::missing(value.ok()?)?);
+                self.received_missing = true;
+            }
             "sender" => self.sender.push(value.ok()?.parse()?),
             "recipient" => self.recipient.push(value.ok()?.parse()?),
+            "_since" => {
+                self.since = Some(
+                    value
+                        .ok()?
+                        .parse()
+                        .map_err(|_| "Invalid search parameter: _since!")?,
+                )
+            }
             "_sort" => self.sort = Some(value.ok()?.parse()?),
             _ => (),
         }
@@ -90,6 +105,7 @@ pub async fn get_all(
     state: Data<State>,
     accept: Accept,
     access_token: Authorization,
+    if_none_match: Option<IfNoneMatch>,
     query: Query<QueryArgs>,
 ) -> Result<HttpResponse, TypedRequestError> {
     let accept = DataType::from_accept(&accept)
@@ -113,16 +129,22 @@ pub async fn get_all(
 
     // Find all communications
     let mut state = state.lock().await;
+    let now = state.now();
+
+    // Clients that search for unread communications mark them as read
+    // explicitly by Communication/$mark-read.
+    let received = if query.received_missing {
+        None
+    } else {
+        Some(now.into())
+    };
     let mut communications = state
-        .communication_iter_mut(participant_id, |c| check_query(&query, c))
+        .communication_iter(&participant_id, received, |c| check_query(&query, c))
         .collect::<Vec<_>>();
 
     // Sort the result
     if let Some(sort) = &query.sort {
         communications.sort_by(|a, b| {
-            let a = &*a;
-            let b = &*b;
-
             sort.cmp(|arg| match arg {
                 SortArgs::Sent => {
                     let a: Option<DateTime<Utc>> = a.sent().clone().map(Into::into);
@@ -153,23 +175,14 @@ pub async fn get_all(
     }
 
     // Generate the response
+    // The timestamp of the bundle may be passed as `_since` to the next call
     let mut bundle = Bundle::new(Type::Searchset);
+    bundle.timestamp = Some(now.into());
     for c in communications {
-        let c = match c {
-            CommunicationRefMut::Sender(c) => &*c,
-            CommunicationRefMut::Recipient(c) => {
-                if c.received().is_none() {
-                    c.set_received(Utc::now().into());
-                }
-
-                &*c
-            }
-        };
-
         bundle.entries.push(Entry::new(c));
     }
 
-    create_response(&bundle, accept)
+    create_response(&bundle, accept).map(|res| check_not_modified(res, if_none_match.as_ref()))
 }
 
 #[allow(clippy::match_like_matches_macro)]
@@ -178,6 +191,7 @@ pub async fn get_one(
     id: Path<Id>,
     accept: Accept,
     access_token: Authorization,
+    if_none_match: Option<IfNoneMatch>,
 ) -> Result<HttpResponse, TypedRequestError> {
     let accept = DataType::from_accept(&accept)
         .and_then(DataType::ignore_any)
@@ -199,26 +213,24 @@ pub async fn get_one(
     let participant_id = access_token.id().into_req_err().err_with_type(accept)?;
 
     let mut state = state.lock().await;
+    let now = state.now();
     let communication = state
-        .communication_get_mut(id, &participant_id)
+        .communication_get(id, &participant_id, Some(now.into()))
         .into_req_err()
         .err_with_type(accept)?;
 
-    let communication = match communication {
-        CommunicationRefMut::Sender(c) => &*c,
-        CommunicationRefMut::Recipient(c) => {
-            if c.received().is_none() {
-                c.set_received(Utc::now().into());
-            }
-
-            &*c
-        }
-    };
-
     create_response(communication, accept)
+        .map(|res| check_not_modified(res, if_none_match.as_ref()))
 }
 
 fn check_query(query: &QueryArgs, communication: &Communication) -> bool {
+    if let Some(since) = &query.since {
+        match communication.sent() {
+            Some(sent) if &DateTime::<Utc>::from(sent.clone()) >= since => (),
+            _ => return false,
+        }
+    }
+
     for qsent in &query.sent {
         if let Some(csent) = communication.sent() {
             let csent = csent.clone().into();
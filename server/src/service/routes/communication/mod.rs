@@ -15,24 +15,29 @@
  *
  */
 
+mod attachment;
 mod create;
 mod delete;
 mod error;
 mod get;
+mod mark;
 mod state;
 
+pub use attachment::{AttachmentType, AttachmentTypes};
 pub use error::Error;
-pub use state::{CommunicationRefMut, Communications};
+pub use state::Communications;
 
 use create::create;
 use delete::delete_one;
 use get::{get_all, get_one};
+use mark::{mark_read, mark_unread};
 
 use actix_web::web::{delete, get, post, resource, ServiceConfig};
 use proc_macros::capability_statement_resource;
 use resources::capability_statement::{Interaction, SearchParamType, Type};
 
 use crate::fhir::definitions::{
+    OPERATION_COMMUNICATION_MARK_READ, OPERATION_COMMUNICATION_MARK_UNREAD,
     RESOURCE_PROFILE_COMMUNICATION, RESOURCE_PROFILE_COMMUNICATION_DISPENSE_REQ,
     RESOURCE_PROFILE_COMMUNICATION_INFO_REQ, RESOURCE_PROFILE_COMMUNICATION_REPLY,
     RESOURCE_PROFILE_COMMUNICATION_REPRESENTATIVE,
@@ -55,9 +60,18 @@ impl CommunicationRoutes {
     #[interaction(Interaction::Read)]
     #[interaction(Interaction::Delete)]
     #[search_param(name="sent", type=SearchParamType::Date)]
-    #[search_param(name="received", type=SearchParamType::Date)]
+    #[search_param(
+        name="received",
+        type=SearchParamType::Date,
+        documentation="received:missing=true returns unread communications without marking them")]
     #[search_param(name="sender", type=SearchParamType::String)]
     #[search_param(name="recipient", type=SearchParamType::String)]
+    #[search_param(
+        name="_since",
+        type=SearchParamType::Date,
+        documentation="Only communications sent since the timestamp of the previous search bundle")]
+    #[operation(name="mark-read", definition = OPERATION_COMMUNICATION_MARK_READ)]
+    #[operation(name="mark-unread", definition = OPERATION_COMMUNICATION_MARK_UNREAD)]
     fn configure_all(&self, cfg: &mut ServiceConfig) {
         cfg.service(
             resource("/Communication")
@@ -69,5 +83,7 @@ impl CommunicationRoutes {
                 .route(get().to(get_one))
                 .route(delete().to(delete_one)),
         );
+        cfg.service(resource("/Communication/{id}/$mark-read").route(post().to(mark_read)));
+        cfg.service(resource("/Communication/{id}/$mark-unread").route(post().to(mark_unread)));
     }
 }
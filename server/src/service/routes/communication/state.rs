@@ -15,14 +15,12 @@
  *
  */
 
-use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
 use std::convert::TryInto;
-use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
-use chrono::Utc;
 use resources::{
-    communication::{Attachment, Content, Inner as CommunicationInner},
+    communication::{Content, Inner as CommunicationInner},
     misc::ParticipantId,
     primitives::{DateTime, Id},
     task::Status,
@@ -30,19 +28,38 @@ use resources::{
 };
 use url::Url;
 
-use crate::{service::header::XAccessCode, state::Inner};
+use crate::{
+    service::header::XAccessCode,
+    state::{Binaries, Inner, Table},
+};
 
-use super::Error;
+use super::{
+    attachment::{binary_id, take_attachment_data},
+    Error,
+};
 
 #[derive(Default)]
 pub struct Communications {
-    by_id: HashMap<Id, Communication>,
+    by_id: Table<Id, Communication>,
+    by_task: HashMap<Id, HashSet<Id>>,
+    by_binary: HashMap<Id, HashSet<Id>>,
 }
 
 impl Communications {
     pub fn insert(&mut self, communication: Communication) {
         let id = communication.id().as_ref().unwrap().clone();
 
+        if let Some(task_id) = task_id(&communication) {
+            self.by_task.entry(task_id).or_default().insert(id.clone());
+        }
+
+        if let Some(binary_id) = attachment_binary_id(&communication) {
+            self.by_binary
+                .entry(binary_id)
+                .or_default()
+                .insert(id.clone());
+        }
+
         match self.by_id.entry(id.clone()) {
             Entry::Occupied(_) => {
                 panic!("Communication with this ID ({}) already exists!", id);
@@ -61,39 +78,68 @@ impl Communications {
         self.by_id.values()
     }
 
-    pub fn remove_by_task_id(&mut self, id: &Id) {
-        self.by_id.retain(|_, c| {
-            let based_on = c.based_on();
-            let (based_on, _) = Inner::parse_task_url(&based_on).unwrap();
-
-            id != &based_on
-        });
+    pub fn take_changes(&mut self) -> HashSet<Id> {
+        self.by_id.take_changes()
     }
-}
 
-pub enum CommunicationRefMut<'a> {
-    Sender(&'a mut Communication),
-    Recipient(&'a mut Communication),
-}
+    pub fn remove_by_id(&mut self, id: &Id) -> Option<Communication> {
+        let communication = self.by_id.remove(id)?;
+
+        if let Some(task_id) = task_id(&communication) {
+            if let Some(ids) = self.by_task.get_mut(&task_id) {
+                ids.remove(id);
+
+                if ids.is_empty() {
+                    self.by_task.remove(&task_id);
+                }
+            }
+        }
+
+        if let Some(binary_id) = attachment_binary_id(&communication) {
+            if let Some(ids) = self.by_binary.get_mut(&binary_id) {
+                ids.remove(id);
+
+                if ids.is_empty() {
+                    self.by_binary.remove(&binary_id);
+                }
+            }
+        }
 
-impl<'a> Deref for CommunicationRefMut<'a> {
-    type Target = Communication;
+        Some(communication)
+    }
 
-    fn deref(&self) -> &Self::Target {
-        match self {
-            Self::Sender(c) => &*c,
-            Self::Recipient(c) => &*c,
+    /// Remove all communications of the passed task together with the
+    /// binaries that are no longer referenced.
+    pub fn remove_by_task_id(&mut self, id: &Id, binaries: &mut Binaries) {
+        if let Some(ids) = self.by_task.remove(id) {
+            for id in ids {
+                if let Some(communication) = self.remove_by_id(&id) {
+                    self.release_binary(&communication, binaries);
+                }
+            }
         }
     }
-}
 
-impl<'a> DerefMut for CommunicationRefMut<'a> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        match self {
-            Self::Sender(c) => c,
-            Self::Recipient(c) => c,
+    /// Remove the binary of the passed communication (that was already
+    /// removed) if no other communication references it.
+    pub fn release_binary(&self, communication: &Communication, binaries: &mut Binaries) {
+        if let Some(binary_id) = attachment_binary_id(communication) {
+            if !self.by_binary.contains_key(&binary_id) {
+                binaries.remove_by_id(&binary_id);
+            }
         }
     }
+
+    /// Returns `true` if the passed participant is the sender or the
+    /// recipient of any communication that references the passed binary.
+    pub fn is_binary_accessible(&self, binary_id: &Id, participant_id: &ParticipantId) -> bool {
+        self.by_binary
+            .get(binary_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.by_id.get(id))
+            .any(|c| communication_matches(c, participant_id) != Match::Unauthorized)
+    }
 }
 
 impl Inner {
@@ -105,20 +151,21 @@ impl Inner {
     ) -> Result<&mut Communication, Error> {
         let Self {
             ref mut communications,
+            ref mut binaries,
+            ref attachment_types,
+            ref timeouts,
             ..
         } = self;
 
-        match communication.content() {
+        let binary = match communication.content_mut() {
             Content::String(s) if s.as_bytes().len() > MAX_CONTENT_SIZE => {
                 return Err(Error::ContentSizeExceeded)
             }
-            Content::Attachment(Attachment {
-                data: Some(data), ..
-            }) if data.len() > MAX_CONTENT_SIZE => return Err(Error::ContentSizeExceeded),
-            _ => (),
-        }
+            Content::String(_) => None,
+            Content::Attachment(attachment) => take_attachment_data(attachment, attachment_types)?,
+        };
 
-        communication.set_sent(Utc::now().into());
+        communication.set_sent(self.clock.now().into());
         match (&mut communication, &participant_id) {
             (Communication::InfoReq(c), ParticipantId::Kvnr(id)) => c.sender = Some(id.clone()),
             (Communication::Reply(c), ParticipantId::TelematikId(id)) => {
@@ -161,6 +208,11 @@ impl Inner {
             if !Self::task_matches(&task, &kvnr, &access_code, &None) {
                 return Err(Error::UnauthorizedTaskAccess);
             }
+
+            // Direct assignments can not be redeemed by the patient.
+            if task.extension.flow_type.is_direct_assignment() {
+                return Err(Error::UnauthorizedTaskAccess);
+            }
         }
 
         let is_representative = match (&communication, task.status) {
@@ -177,54 +229,124 @@ impl Inner {
         let id = Id::generate().unwrap();
         communication.set_id(Some(id.clone()));
 
-        let communication = match communications.by_id.entry(id) {
-            Entry::Occupied(e) => panic!("Communication does already exists: {}", e.key()),
-            Entry::Vacant(e) => e.insert(communication),
-        };
+        if let Some(binary) = binary {
+            binaries.insert(binary);
+        }
+
+        timeouts.insert(&communication);
+        communications.insert(communication);
 
         if is_representative {
-            let task_meta = self.tasks.get_mut_by_id(&task_id).unwrap();
+            let mut task_meta = self.tasks.get_mut_by_id(&task_id).unwrap();
             task_meta.communication_count += 1;
         }
 
-        Ok(communication)
+        Ok(communications.by_id.get_mut(&id).unwrap())
     }
 
-    pub fn communication_get_mut(
+    /// Get the communication with the passed ID.
+    ///
+    /// If `received` is set and the participant is the recipient of the
+    /// communication, it is marked as received (if it was not already).
+    pub fn communication_get(
         &mut self,
         id: Id,
         participant_id: &ParticipantId,
-    ) -> Result<CommunicationRefMut<'_>, Error> {
-        let c = match self.communications.by_id.get_mut(&id) {
+        received: Option<DateTime>,
+    ) -> Result<&Communication, Error> {
+        let c = match self.communications.by_id.get(&id) {
             Some(c) => c,
             None => return Err(Error::NotFound(id)),
         };
 
-        match communication_matches(c, participant_id) {
-            Match::Sender => Ok(CommunicationRefMut::Sender(c)),
-            Match::Recipient => Ok(CommunicationRefMut::Recipient(c)),
-            Match::Unauthorized => Err(Error::Unauthorized(id)),
+        let is_unread = match communication_matches(c, participant_id) {
+            Match::Sender => false,
+            Match::Recipient => c.received().is_none(),
+            Match::Unauthorized => return Err(Error::Unauthorized(id)),
+        };
+
+        if let (true, Some(received)) = (is_unread, received) {
+            self.communications
+                .by_id
+                .get_mut(&id)
+                .unwrap()
+                .set_received(received);
         }
+
+        Ok(self.communications.by_id.get(&id).unwrap())
     }
 
-    pub fn communication_iter_mut<F>(
+    /// Mark the communication as read (set the received timestamp) or as
+    /// unread (clear it). Only the recipient may change this state.
+    pub fn communication_mark(
         &mut self,
-        participant_id: ParticipantId,
+        id: Id,
+        participant_id: &ParticipantId,
+        read: bool,
+    ) -> Result<&Communication, Error> {
+        let now = self.clock.now();
+        let c = match self.communications.by_id.get_mut(&id) {
+            Some(c) => c,
+            None => return Err(Error::NotFound(id)),
+        };
+
+        if communication_matches(c, participant_id) != Match::Recipient {
+            return Err(Error::Unauthorized(id));
+        }
+
+        if !read {
+            c.clear_received();
+        } else if c.received().is_none() {
+            c.set_received(now.into());
+        }
+
+        Ok(c)
+    }
+
+    /// Get all communications the participant is the sender or the recipient
+    /// of and `f` returns `true` for.
+    ///
+    /// If `received` is set, the returned communications the participant is
+    /// the recipient of are marked as received (if they were not already).
+    /// Only these are written to the storage again.
+    pub fn communication_iter<'a, F>(
+        &'a mut self,
+        participant_id: &ParticipantId,
+        received: Option<DateTime>,
         mut f: F,
-    ) -> impl Iterator<Item = CommunicationRefMut<'_>>
+    ) -> impl Iterator<Item = &'a Communication>
     where
         F: FnMut(&Communication) -> bool,
     {
-        self.communications
-            .by_id
-            .iter_mut()
-            .filter_map(
-                move |(_, c)| match communication_matches(c, &participant_id) {
-                    Match::Sender if f(c) => Some(CommunicationRefMut::Sender(c)),
-                    Match::Recipient if f(c) => Some(CommunicationRefMut::Recipient(c)),
-                    _ => None,
-                },
-            )
+        let mut ids = Vec::new();
+        let mut unread = Vec::new();
+
+        for (id, c) in self.communications.by_id.iter() {
+            match communication_matches(c, participant_id) {
+                Match::Sender if f(c) => ids.push(id.clone()),
+                Match::Recipient if f(c) => {
+                    if c.received().is_none() {
+                        unread.push(id.clone());
+                    }
+
+                    ids.push(id.clone());
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(received) = received {
+            for id in unread {
+                if let Some(c) = self.communications.by_id.get_mut(&id) {
+                    c.set_received(received.clone());
+                }
+            }
+        }
+
+        let communications = &self.communications;
+
+        ids.into_iter()
+            .map(move |id| communications.by_id.get(&id).unwrap())
     }
 
     pub fn communication_delete(
@@ -241,7 +363,8 @@ impl Inner {
             return Err(Error::Unauthorized(id));
         }
 
-        let c = self.communications.by_id.remove(&id).unwrap();
+        let c = self.communications.remove_by_id(&id).unwrap();
+        self.communications.release_binary(&c, &mut self.binaries);
 
         let received = match c {
             Communication::DispenseReq(c) => c.received,
@@ -261,7 +384,7 @@ impl Inner {
             ..
         } = self;
 
-        communications.by_id.remove(id);
+        communications.remove_by_id(id);
     }
 
     fn parse_task_url(uri: &str) -> Result<(Id, Option<XAccessCode>), Error> {
@@ -369,4 +492,18 @@ fn communication_matches(communication: &Communication, participant_id: &Partici
     }
 }
 
+fn attachment_binary_id(communication: &Communication) -> Option<Id> {
+    match communication.content() {
+        Content::Attachment(attachment) => attachment.url.as_deref().and_then(binary_id),
+        Content::String(_) => None,
+    }
+}
+
+fn task_id(communication: &Communication) -> Option<Id> {
+    let based_on = communication.based_on();
+    let (task_id, _) = Inner::parse_task_url(&based_on).ok()?;
+
+    Some(task_id)
+}
+
 const MAX_CONTENT_SIZE: usize = 10 * 1024;
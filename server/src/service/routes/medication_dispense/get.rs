@@ -24,7 +24,8 @@ use actix_web::{
 use chrono::{DateTime, Utc};
 use resources::{
     bundle::{Bundle, Entry, Relation, Type},
-    misc::TelematikId,
+    medication::{Data as MedicationData, PznCode},
+    misc::{Kvnr, PrescriptionId, TelematikId},
     primitives::Id,
     MedicationDispense,
 };
@@ -43,6 +44,10 @@ use crate::{
 
 #[derive(Default)]
 pub struct QueryArgs {
+    identifier: Vec<Search<PrescriptionId>>,
+    subject: Vec<Search<Kvnr>>,
+    medication_code: Vec<Search<PznCode>>,
+    last_updated: Vec<Search<DateTime<Utc>>>,
     when_handed_over: Vec<Search<DateTime<Utc>>>,
     when_prepared: Vec<Search<DateTime<Utc>>>,
     performer: Vec<Search<TelematikId>>,
@@ -54,6 +59,10 @@ pub struct QueryArgs {
 impl FromQuery for QueryArgs {
     fn parse_key_value_pair(&mut self, key: &str, value: QueryValue) -> Result<(), String> {
         match key {
+            "identifier" => self.identifier.push(value.ok()?.parse()?),
+            "subject" => self.subject.push(value.ok()?.parse()?),
+            "medication.code" => self.medication_code.push(value.ok()?.parse()?),
+            "_lastUpdated" => self.last_updated.push(value.ok()?.parse()?),
             "whenHandedOver" | "whenhandedover" | "when-handed-over" => {
                 self.when_handed_over.push(value.ok()?.parse()?)
             }
@@ -71,6 +80,27 @@ impl FromQuery for QueryArgs {
 
         Ok(())
     }
+
+    fn is_supported(key: &str) -> bool {
+        matches!(
+            key,
+            "identifier"
+                | "subject"
+                | "medication.code"
+                | "_lastUpdated"
+                | "whenHandedOver"
+                | "whenhandedover"
+                | "when-handed-over"
+                | "whenPrepared"
+                | "whenprepared"
+                | "when-prepared"
+                | "performer"
+                | "_sort"
+                | "_count"
+                | "pageId"
+                | "page-id"
+        )
+    }
 }
 
 pub enum SortArgs {
@@ -106,18 +136,36 @@ pub async fn get_all(
         .err_with_type_default()?;
 
     access_token
-        .check_profession(|p| p == Profession::Versicherter)
+        .check_profession(|p| {
+            p == Profession::Versicherter
+                || p == Profession::OeffentlicheApotheke
+                || p == Profession::KrankenhausApotheke
+        })
         .into_req_err()
         .err_with_type(accept)?;
 
-    let kvnr = access_token.kvnr().into_req_err().err_with_type(accept)?;
-    let mut state = state.lock().await;
+    let state = state.read().await;
 
     // Collect results
     let agent = (&*access_token).into();
-    let mut results = state
-        .medication_dispense_iter(&kvnr, agent, |md| check_query(&query, md))
-        .collect::<Vec<_>>();
+    let mut results = if access_token.is_pharmacy() {
+        let telematik_id = access_token
+            .telematik_id()
+            .into_req_err()
+            .err_with_type(accept)?;
+
+        state
+            .medication_dispense_iter_by_pharmacy(&telematik_id, agent, |md| {
+                check_query(&query, md)
+            })
+            .collect::<Vec<_>>()
+    } else {
+        let kvnr = access_token.kvnr().into_req_err().err_with_type(accept)?;
+
+        state
+            .medication_dispense_iter(&kvnr, agent, |md| check_query(&query, md))
+            .collect::<Vec<_>>()
+    };
 
     // Sort the result
     if let Some(sort) = &query.sort {
@@ -209,16 +257,20 @@ pub async fn get_one(
         .err_with_type_default()?;
 
     access_token
-        .check_profession(|p| p == Profession::Versicherter)
+        .check_profession(|p| {
+            p == Profession::Versicherter
+                || p == Profession::OeffentlicheApotheke
+                || p == Profession::KrankenhausApotheke
+        })
         .into_req_err()
         .err_with_type(accept)?;
 
     let id = id.0;
-    let kvnr = access_token.kvnr().into_req_err().err_with_type(accept)?;
+    let participant_id = access_token.id().into_req_err().err_with_type(accept)?;
     let agent = (&*access_token).into();
-    let mut state = state.lock().await;
+    let state = state.read().await;
     let medication_dispense = state
-        .medication_dispense_get(id, &kvnr, agent)
+        .medication_dispense_get(id, &participant_id, agent)
         .into_req_err()
         .err_with_type(accept)?;
 
@@ -226,6 +278,32 @@ pub async fn get_one(
 }
 
 fn check_query(query: &QueryArgs, value: &MedicationDispense) -> bool {
+    for expected in &query.identifier {
+        if !expected.matches(&value.prescription_id) {
+            return false;
+        }
+    }
+
+    for expected in &query.subject {
+        if !expected.matches(&value.subject) {
+            return false;
+        }
+    }
+
+    for expected in &query.medication_code {
+        match &value.medication.data {
+            MedicationData::Pzn(pzn) if expected.matches(&pzn.code) => (),
+            _ => return false,
+        }
+    }
+
+    for expected in &query.last_updated {
+        match &value.last_updated {
+            Some(actual) if expected.matches(actual) => (),
+            _ => return false,
+        }
+    }
+
     for expected in &query.when_handed_over {
         if !expected.matches(&value.when_handed_over.clone().into()) {
             return false;
@@ -38,6 +38,10 @@ pub struct MedicationDispenseRoutes;
     profile = RESOURCE_PROFILE_MEDICATION_DISPENSE)]
 impl MedicationDispenseRoutes {
     #[interaction(Interaction::Read)]
+    #[search_param(name="identifier", type=SearchParamType::Token)]
+    #[search_param(name="subject", type=SearchParamType::Token)]
+    #[search_param(name="medication.code", type=SearchParamType::Token)]
+    #[search_param(name="_lastUpdated", type=SearchParamType::Date)]
     #[search_param(name="whenhandedover", type=SearchParamType::Date)]
     #[search_param(name="whenprepared", type=SearchParamType::Date)]
     #[search_param(name="performer", type=SearchParamType::String)]
@@ -15,32 +15,32 @@
  *
  */
 
-use std::cell::RefCell;
 use std::collections::{hash_map::Entry, HashMap, HashSet};
-use std::rc::Rc;
 
 use resources::{
     audit_event::{Action, Agent, SubType, Text, What},
-    misc::{Kvnr, PrescriptionId},
+    misc::{Kvnr, ParticipantId, PrescriptionId, TelematikId},
     primitives::Id,
     MedicationDispense,
 };
 
-use crate::state::Inner;
+use crate::state::{Inner, Table};
 
 use super::Error;
 
 #[derive(Default)]
 pub struct MedicationDispenses {
-    by_id: HashMap<Id, MedicationDispense>,
+    by_id: Table<Id, MedicationDispense>,
     by_kvnr: HashMap<Kvnr, HashSet<Id>>,
-    by_prescription_id: HashMap<PrescriptionId, Id>,
+    by_telematik_id: HashMap<TelematikId, HashSet<Id>>,
+    by_prescription_id: HashMap<PrescriptionId, HashSet<Id>>,
 }
 
 impl MedicationDispenses {
     pub fn insert(&mut self, medication_dispense: MedicationDispense) {
         let id = medication_dispense.id.as_ref().unwrap().clone();
         let kvnr = medication_dispense.subject.clone();
+        let telematik_id = medication_dispense.performer.clone();
         let prescription_id = medication_dispense.prescription_id.clone();
 
         match self.by_id.entry(id.clone()) {
@@ -53,46 +53,79 @@ impl MedicationDispenses {
         }
 
         self.by_kvnr.entry(kvnr).or_default().insert(id.clone());
-        self.by_prescription_id.insert(prescription_id, id);
+        self.by_telematik_id
+            .entry(telematik_id)
+            .or_default()
+            .insert(id.clone());
+        self.by_prescription_id
+            .entry(prescription_id)
+            .or_default()
+            .insert(id);
     }
 
     pub fn get_by_id(&self, id: &Id) -> Option<&MedicationDispense> {
         self.by_id.get(id)
     }
 
+    pub fn iter_by_prescription_id<'a>(
+        &'a self,
+        prescription_id: &PrescriptionId,
+    ) -> impl Iterator<Item = &'a MedicationDispense> {
+        let by_id = &self.by_id;
+
+        self.by_prescription_id
+            .get(prescription_id)
+            .into_iter()
+            .flatten()
+            .map(move |id| by_id.get(id).unwrap())
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &MedicationDispense> {
         self.by_id.values()
     }
 
+    pub fn take_changes(&mut self) -> HashSet<Id> {
+        self.by_id.take_changes()
+    }
+
     pub fn remove_by_prescription_id(&mut self, prescription_id: &PrescriptionId) {
-        if let Some(id) = self.by_prescription_id.remove(prescription_id) {
+        let ids = match self.by_prescription_id.remove(prescription_id) {
+            Some(ids) => ids,
+            None => return,
+        };
+
+        for id in ids {
             let md = self.by_id.remove(&id).unwrap();
 
-            let id = md.id.unwrap();
             let kvnr = md.subject;
             if let Some(by_kvnr) = self.by_kvnr.get_mut(&kvnr) {
                 by_kvnr.remove(&id);
             }
+
+            let telematik_id = md.performer;
+            if let Some(by_telematik_id) = self.by_telematik_id.get_mut(&telematik_id) {
+                by_telematik_id.remove(&id);
+            }
         }
     }
 }
 
 impl Inner {
     pub fn medication_dispense_get(
-        &mut self,
+        &self,
         id: Id,
-        kvnr: &Kvnr,
+        participant_id: &ParticipantId,
         agent: Agent,
     ) -> Result<&MedicationDispense, Error> {
         let Self {
             ref medication_dispenses,
-            ref mut timeouts,
-            ref mut audit_events,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
             ..
         } = self;
 
-        let timeouts = Rc::new(RefCell::new(timeouts));
-        Self::logged(audit_events, timeouts.clone(), move |event_builder| {
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
             let md = match medication_dispenses.by_id.get(&id) {
                 Some(md) => md,
                 None => return Err(Error::NotFound(id)),
@@ -102,11 +135,15 @@ impl Inner {
             event_builder.action(Action::Read);
             event_builder.sub_type(SubType::Read);
             event_builder.what(What::MedicationDispense(md.id.clone().unwrap()));
-            event_builder.patient(kvnr.clone());
+            event_builder.patient(md.subject.clone());
             event_builder.description(md.prescription_id.clone());
             event_builder.text(Text::MedicationDispenseGetOne);
 
-            if &md.subject != kvnr {
+            let is_allowed = match participant_id {
+                ParticipantId::Kvnr(kvnr) => &md.subject == kvnr,
+                ParticipantId::TelematikId(telematik_id) => &md.performer == telematik_id,
+            };
+            if !is_allowed {
                 return Err(Error::Forbidden(id));
             }
 
@@ -115,7 +152,7 @@ impl Inner {
     }
 
     pub fn medication_dispense_iter<'a, F>(
-        &'a mut self,
+        &'a self,
         kvnr: &'a Kvnr,
         agent: Agent,
         f: F,
@@ -125,8 +162,9 @@ impl Inner {
     {
         let Self {
             ref medication_dispenses,
-            ref mut timeouts,
-            ref mut audit_events,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
             ..
         } = self;
 
@@ -141,7 +179,7 @@ impl Inner {
         event_builder.what(What::MedicationDispenses);
         event_builder.patient(kvnr.clone());
         event_builder.text(Text::MedicationDispenseGetMany);
-        event_builder.build(audit_events, timeouts, None);
+        event_builder.build(audit_events, timeouts, clock, None);
 
         let items = match medication_dispenses.by_kvnr.get(&kvnr) {
             Some(items) => items,
@@ -159,6 +197,51 @@ impl Inner {
         })
     }
 
+    pub fn medication_dispense_iter_by_pharmacy<'a, F>(
+        &'a self,
+        telematik_id: &TelematikId,
+        agent: Agent,
+        f: F,
+    ) -> impl Iterator<Item = &'a MedicationDispense>
+    where
+        F: Fn(&MedicationDispense) -> bool,
+    {
+        let Self {
+            ref medication_dispenses,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
+            ..
+        } = self;
+
+        lazy_static! {
+            static ref EMPTY: HashSet<Id> = HashSet::new();
+        }
+
+        let mut event_builder = Self::audit_event_builder();
+        event_builder.agent(agent);
+        event_builder.action(Action::Read);
+        event_builder.sub_type(SubType::Read);
+        event_builder.what(What::MedicationDispenses);
+        event_builder.text(Text::MedicationDispenseGetMany);
+        event_builder.build(audit_events, timeouts, clock, None);
+
+        let items = match medication_dispenses.by_telematik_id.get(telematik_id) {
+            Some(items) => items,
+            None => &EMPTY,
+        };
+
+        items.iter().filter_map(move |id| {
+            let v = medication_dispenses.by_id.get(&id).unwrap();
+
+            if f(v) {
+                Some(v)
+            } else {
+                None
+            }
+        })
+    }
+
     pub fn medication_dispense_delete_by_id(&mut self, id: &Id) {
         let Self {
             ref mut medication_dispenses,
@@ -167,16 +250,299 @@ impl Inner {
 
         let medication_dispense = medication_dispenses.by_id.get(id).unwrap();
 
-        medication_dispenses
+        let prescription_id = &medication_dispense.prescription_id;
+        if let Some(ids) = medication_dispenses
             .by_prescription_id
-            .remove(&medication_dispense.prescription_id);
+            .get_mut(prescription_id)
+        {
+            ids.remove(id);
+
+            if ids.is_empty() {
+                medication_dispenses
+                    .by_prescription_id
+                    .remove(prescription_id);
+            }
+        }
         if let Some(ids) = medication_dispenses
             .by_kvnr
             .get_mut(&medication_dispense.subject)
         {
             ids.remove(id);
         }
+        if let Some(ids) = medication_dispenses
+            .by_telematik_id
+            .get_mut(&medication_dispense.performer)
+        {
+            ids.remove(id);
+        }
 
         medication_dispenses.by_id.remove(id);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use resources::{task::Status, types::FlowType};
+
+    use crate::fhir::decode::{tests::load_stream, JsonDecode};
+    use crate::service::{
+        header::XAccessCode,
+        misc::{access_token::tests::access_token, Profession},
+        routes::task::{tests::create_ready_task, Error as TaskError},
+    };
+    use crate::state::tests::signing_state;
+
+    #[tokio::test]
+    async fn dispense_and_close() {
+        let state = signing_state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let pharmacy = access_token(Profession::OeffentlicheApotheke, "606358757");
+        let other_pharmacy = access_token(Profession::OeffentlicheApotheke, "606358758");
+
+        let (id, access_code) =
+            create_ready_task(&mut state, FlowType::ApothekenpflichtigeArzneimittel, &kvnr);
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code),
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+        let prescription_id = task.identifier.prescription_id.clone().unwrap();
+
+        let medication_dispense = load_medication_dispense(&state, &id, "606358757").await;
+        state
+            .task_dispense(
+                id.clone(),
+                secret.clone(),
+                TelematikId::new("606358757"),
+                vec![medication_dispense],
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+
+        // The stored medication dispenses may only be finalized by their performer.
+        let res = state.task_close(
+            id.clone(),
+            secret.clone(),
+            TelematikId::new("606358758"),
+            vec![],
+            None,
+            (&other_pharmacy).into(),
+        );
+        assert!(matches!(res, Err(TaskError::PerformerMismatch)));
+
+        state
+            .task_close(
+                id.clone(),
+                secret,
+                TelematikId::new("606358757"),
+                vec![],
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+
+        let task = &state.tasks.get_by_id(&id).unwrap().task;
+        assert_eq!(task.status, Status::Completed);
+        assert!(task.output.receipt.is_some());
+
+        let medication_dispenses = state
+            .medication_dispenses
+            .iter_by_prescription_id(&prescription_id)
+            .collect::<Vec<_>>();
+        assert_eq!(medication_dispenses.len(), 1);
+        assert_eq!(
+            medication_dispenses[0].performer,
+            TelematikId::new("606358757")
+        );
+    }
+
+    #[tokio::test]
+    async fn dispense_reject_accept_and_close() {
+        let state = signing_state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let pharmacy_a = access_token(Profession::OeffentlicheApotheke, "606358757");
+        let pharmacy_b = access_token(Profession::OeffentlicheApotheke, "606358758");
+
+        let (id, access_code) =
+            create_ready_task(&mut state, FlowType::ApothekenpflichtigeArzneimittel, &kvnr);
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code.clone()),
+                None,
+                (&pharmacy_a).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+        let prescription_id = task.identifier.prescription_id.clone().unwrap();
+
+        let medication_dispense = load_medication_dispense(&state, &id, "606358757").await;
+        state
+            .task_dispense(
+                id.clone(),
+                secret.clone(),
+                TelematikId::new("606358757"),
+                vec![medication_dispense],
+                None,
+                (&pharmacy_a).into(),
+            )
+            .unwrap();
+
+        // The medication dispenses of the rejecting pharmacy are removed.
+        state
+            .task_reject(id.clone(), secret, None, (&pharmacy_a).into())
+            .unwrap();
+        assert_eq!(
+            state
+                .medication_dispenses
+                .iter_by_prescription_id(&prescription_id)
+                .count(),
+            0
+        );
+
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code),
+                None,
+                (&pharmacy_b).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+
+        let res = state.task_close(
+            id.clone(),
+            secret.clone(),
+            TelematikId::new("606358758"),
+            vec![],
+            None,
+            (&pharmacy_b).into(),
+        );
+        assert!(matches!(res, Err(TaskError::MedicationDispenseMissing)));
+
+        let medication_dispense = load_medication_dispense(&state, &id, "606358758").await;
+        state
+            .task_close(
+                id.clone(),
+                secret,
+                TelematikId::new("606358758"),
+                vec![medication_dispense],
+                None,
+                (&pharmacy_b).into(),
+            )
+            .unwrap();
+
+        let medication_dispenses = state
+            .medication_dispenses
+            .iter_by_prescription_id(&prescription_id)
+            .collect::<Vec<_>>();
+        assert_eq!(medication_dispenses.len(), 1);
+        assert_eq!(
+            medication_dispenses[0].performer,
+            TelematikId::new("606358758")
+        );
+    }
+
+    #[tokio::test]
+    async fn dispense_replaces_medication_dispenses() {
+        let state = signing_state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let pharmacy = access_token(Profession::OeffentlicheApotheke, "606358757");
+        let performer = TelematikId::new("606358757");
+
+        let (id, access_code) =
+            create_ready_task(&mut state, FlowType::ApothekenpflichtigeArzneimittel, &kvnr);
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code),
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+        let prescription_id = task.identifier.prescription_id.clone().unwrap();
+
+        let medication_dispense = load_medication_dispense(&state, &id, "606358757").await;
+        let stored_ids = |state: &Inner| {
+            state
+                .medication_dispenses
+                .iter_by_prescription_id(&prescription_id)
+                .map(|md| md.id.clone().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        state
+            .task_dispense(
+                id.clone(),
+                secret.clone(),
+                performer.clone(),
+                vec![medication_dispense.clone(), medication_dispense.clone()],
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        let first_ids = stored_ids(&*state);
+        assert_eq!(first_ids.len(), 2);
+
+        state
+            .task_dispense(
+                id.clone(),
+                secret.clone(),
+                performer.clone(),
+                vec![medication_dispense.clone()],
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        let second_ids = stored_ids(&*state);
+        assert_eq!(second_ids.len(), 1);
+        assert!(!first_ids.contains(&second_ids[0]));
+
+        // Medication dispenses passed to $close replace the dispensed ones as well.
+        state
+            .task_close(
+                id,
+                secret,
+                performer,
+                vec![medication_dispense.clone(), medication_dispense],
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        let closed_ids = stored_ids(&*state);
+        assert_eq!(closed_ids.len(), 2);
+        assert!(!closed_ids.contains(&second_ids[0]));
+    }
+
+    /// Load the example medication dispense and adapt it to the task and the
+    /// performer.
+    async fn load_medication_dispense(
+        state: &Inner,
+        task_id: &Id,
+        performer: &str,
+    ) -> MedicationDispense {
+        let mut stream = load_stream("./examples/medication_dispense.json");
+        let mut medication_dispense = stream.json::<MedicationDispense>().await.unwrap();
+
+        let task = &state.tasks.get_by_id(task_id).unwrap().task;
+        medication_dispense.prescription_id = task.identifier.prescription_id.clone().unwrap();
+        medication_dispense.subject = task.for_.clone().unwrap();
+        medication_dispense.performer = TelematikId::new(performer);
+
+        medication_dispense
+    }
+}
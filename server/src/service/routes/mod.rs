@@ -16,8 +16,10 @@
  */
 
 pub mod audit_event;
+pub mod binary;
 pub mod capabilty_statement;
 pub mod cert_list;
+pub mod charge_item;
 pub mod communication;
 pub mod device;
 pub mod health;
@@ -31,8 +33,10 @@ use actix_web::web::ServiceConfig;
 use proc_macros::capability_statement;
 
 use audit_event::AutidEventRoutes;
+use binary::BinaryRoutes;
 use capabilty_statement::{create as capability_statement_create, get as capability_statement_get};
 use cert_list::configure_routes as cert_list_configure_routes;
+use charge_item::ChargeItemRoutes;
 use communication::CommunicationRoutes;
 use device::DeviceRoutes;
 use health::configure_routes as health_configure_routes;
@@ -52,9 +56,15 @@ pub struct Routes {
     #[resource]
     communication: CommunicationRoutes,
 
+    #[resource]
+    binary: BinaryRoutes,
+
     #[resource]
     medication_dispense: MedicationDispenseRoutes,
 
+    #[resource]
+    charge_item: ChargeItemRoutes,
+
     #[resource]
     audit_event: AutidEventRoutes,
 
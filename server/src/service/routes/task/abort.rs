@@ -24,7 +24,7 @@ use serde::Deserialize;
 
 use crate::{
     service::{
-        header::{Accept, Authorization, XAccessCode},
+        header::{Accept, Authorization, IfMatch, XAccessCode},
         misc::{DataType, Profession},
         IntoReqErrResult, TypedRequestError, TypedRequestResult,
     },
@@ -43,6 +43,7 @@ pub async fn abort(
     accept: Accept,
     access_token: Authorization,
     access_code: Option<XAccessCode>,
+    if_match: Option<IfMatch>,
 ) -> Result<HttpResponse, TypedRequestError> {
     let accept = DataType::from_accept(&accept)
         .and_then(DataType::ignore_any)
@@ -70,7 +71,7 @@ pub async fn abort(
     state
         .lock()
         .await
-        .task_abort(id, &access_token, access_code, secret, agent)
+        .task_abort(id, &access_token, access_code, secret, if_match, agent)
         .into_req_err()
         .err_with_type(accept)?;
 
@@ -16,6 +16,7 @@
  */
 
 use actix_web::{
+    http::StatusCode,
     web::{Data, Path},
     HttpResponse,
 };
@@ -26,14 +27,14 @@ use resources::{
 
 use crate::{
     service::{
-        header::{Accept, Authorization, XAccessCode},
-        misc::{create_response, DataType, Profession},
+        header::{Accept, Authorization, IfMatch, XAccessCode},
+        misc::{create_response_with, DataType, Profession},
         IntoReqErrResult, TypedRequestError, TypedRequestResult,
     },
     state::State,
 };
 
-use super::misc::Resource;
+use super::misc::{set_task_headers, Resource};
 
 pub async fn accept(
     state: Data<State>,
@@ -41,6 +42,7 @@ pub async fn accept(
     accept: Accept,
     access_token: Authorization,
     access_code: XAccessCode,
+    if_match: Option<IfMatch>,
 ) -> Result<HttpResponse, TypedRequestError> {
     let accept = DataType::from_accept(&accept)
         .and_then(DataType::ignore_any)
@@ -59,7 +61,7 @@ pub async fn accept(
     let agent = (&*access_token).into();
     let mut state = state.lock().await;
     let (task, e_prescription) = state
-        .task_accept(id, access_code, agent)
+        .task_accept(id, access_code, if_match, agent)
         .into_req_err()
         .err_with_type(accept)?;
 
@@ -71,5 +73,7 @@ pub async fn accept(
         .entries
         .push(Entry::new(Resource::KbvBinary(e_prescription)));
 
-    create_response(&bundle, accept)
+    create_response_with(&bundle, accept, StatusCode::OK, |res| {
+        set_task_headers(res, task)
+    })
 }
@@ -17,6 +17,7 @@
 
 use actix_web::{
     error::PayloadError,
+    http::StatusCode,
     web::{Data, Path, Payload},
     HttpResponse,
 };
@@ -24,12 +25,14 @@ use bytes::Bytes;
 use futures::{future::ready, stream::once};
 use resources::{primitives::Id, task::TaskActivateParameters, KbvBinary, KbvBundle};
 
+use super::misc::set_task_headers;
+
 use crate::{
     fhir::{decode::XmlDecode, definitions::TaskContainer},
     pki_store::PkiStore,
     service::{
         header::{Accept, Authorization, ContentType, XAccessCode},
-        misc::{create_response, read_payload, DataType, Profession},
+        misc::{create_response_with, read_payload, DataType, Profession},
         IntoReqErrResult, RequestError, TypedRequestError, TypedRequestResult,
     },
     state::State,
@@ -95,5 +98,10 @@ pub async fn activate(
         .into_req_err()
         .err_with_type(accept)?;
 
-    create_response(TaskContainer::for_supplier(task), accept)
+    create_response_with(
+        TaskContainer::for_supplier(task),
+        accept,
+        StatusCode::OK,
+        |res| set_task_headers(res, task),
+    )
 }
@@ -17,15 +17,18 @@
 
 use actix_web::{
     web::{Data, Path, Payload, Query},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
-use resources::{primitives::Id, MedicationDispense};
+use resources::primitives::Id;
 use serde::Deserialize;
 
 use crate::{
+    fhir::definitions::MedicationDispenseList,
     service::{
-        header::{Accept, Authorization, ContentType},
-        misc::{create_response, read_payload, DataType, Profession},
+        header::{Accept, Authorization, ContentType, IdempotencyKey, IfMatch},
+        misc::{
+            create_response, decode_payload, read_payload_bytes, DataType, Profession, RequestKey,
+        },
         IntoReqErrResult, TypedRequestError, TypedRequestResult,
     },
     state::State,
@@ -36,6 +39,7 @@ pub struct QueryArgs {
     secret: Option<String>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn close(
     state: Data<State>,
     id: Path<Id>,
@@ -43,6 +47,9 @@ pub async fn close(
     content_type: ContentType,
     access_token: Authorization,
     query: Query<QueryArgs>,
+    if_match: Option<IfMatch>,
+    idempotency_key: Option<IdempotencyKey>,
+    request: HttpRequest,
     payload: Payload,
 ) -> Result<HttpResponse, TypedRequestError> {
     let data_type = DataType::from_mime(&content_type);
@@ -65,16 +72,35 @@ pub async fn close(
         .telematik_id()
         .into_req_err()
         .err_with_type(accept)?;
-    let medication_dispense = read_payload::<MedicationDispense>(data_type, payload)
-        .await
+    let payload = read_payload_bytes(payload).await.err_with_type(accept)?;
+    let request_key = idempotency_key
+        .map(|key| RequestKey::new(&access_token, key, &request, &payload))
+        .transpose()
+        .into_req_err()
         .err_with_type(accept)?;
+    let medication_dispenses = if payload.is_empty() {
+        Vec::new()
+    } else {
+        decode_payload::<MedicationDispenseList>(data_type, payload)
+            .await
+            .err_with_type(accept)?
+            .0
+    };
     let agent = (&*access_token).into();
 
     let mut state = state.lock().await;
+    if let Some(res) = state
+        .idempotency_replay(request_key.as_ref())
+        .err_with_type(accept)?
+    {
+        return Ok(res);
+    }
+
     let erx_bundle = state
-        .task_close(id, secret, performer, medication_dispense, agent)
+        .task_close(id, secret, performer, medication_dispenses, if_match, agent)
         .into_req_err()
         .err_with_type(accept)?;
+    let res = create_response(erx_bundle, accept)?;
 
-    create_response(erx_bundle, accept)
+    Ok(state.idempotency_store(request_key, res))
 }
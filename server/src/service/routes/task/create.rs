@@ -18,15 +18,18 @@
 use actix_web::{
     http::StatusCode,
     web::{Data, Payload},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
 use resources::task::TaskCreateParameters;
 
 use crate::{
     fhir::definitions::TaskContainer,
     service::{
-        header::{Accept, Authorization, ContentType},
-        misc::{create_response_with, read_payload, DataType, Profession},
+        header::{Accept, Authorization, ContentType, IdempotencyKey},
+        misc::{
+            create_response_with, decode_payload, read_payload_bytes, DataType, Profession,
+            RequestKey,
+        },
         IntoReqErrResult, State, TypedRequestError, TypedRequestResult,
     },
 };
@@ -36,6 +39,8 @@ pub async fn create(
     accept: Accept,
     access_token: Authorization,
     content_type: ContentType,
+    idempotency_key: Option<IdempotencyKey>,
+    request: HttpRequest,
     payload: Payload,
 ) -> Result<HttpResponse, TypedRequestError> {
     let data_type = DataType::from_mime(&content_type);
@@ -55,20 +60,35 @@ pub async fn create(
         .into_req_err()
         .err_with_type(accept)?;
 
-    let args = read_payload::<TaskCreateParameters>(data_type, payload)
+    let payload = read_payload_bytes(payload).await.err_with_type(accept)?;
+    let request_key = idempotency_key
+        .map(|key| RequestKey::new(&access_token, key, &request, &payload))
+        .transpose()
+        .into_req_err()
+        .err_with_type(accept)?;
+    let args = decode_payload::<TaskCreateParameters>(data_type, payload)
         .await
         .err_with_type(accept)?;
 
     let mut state = state.lock().await;
+    if let Some(res) = state
+        .idempotency_replay(request_key.as_ref())
+        .err_with_type(accept)?
+    {
+        return Ok(res);
+    }
+
     let task = state
         .task_create(args)
         .into_req_err()
         .err_with_type(accept)?;
 
-    create_response_with(
+    let res = create_response_with(
         TaskContainer::for_doctor(task),
         accept,
         StatusCode::CREATED,
         |_| (),
-    )
+    )?;
+
+    Ok(state.idempotency_store(request_key, res))
 }
@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use resources::Task;
+
+/// Returns `true` if the task is a direct assignment (flow type 169 or 209).
+///
+/// Direct assignments are passed from the prescriber to the performer
+/// directly, so the patient is not allowed to redeem or abort them.
+pub(super) fn is_direct_assignment(task: &Task) -> bool {
+    task.extension.flow_type.is_direct_assignment()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use resources::{
+        misc::{Kvnr, TelematikId},
+        task::Status,
+        types::FlowType,
+    };
+
+    use crate::service::{
+        header::XAccessCode,
+        misc::{access_token::tests::access_token, Profession},
+    };
+    use crate::state::tests::state;
+
+    use super::super::{tests::create_ready_task, Error};
+
+    #[tokio::test]
+    async fn direct_assignment_patient_access() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let patient = access_token(Profession::Versicherter, "X123456789");
+
+        let (id, access_code) = create_ready_task(&mut state, FlowType::DirekteZuweisung, &kvnr);
+        let task = &state.tasks.get_by_id(&id).unwrap().task;
+        assert!(is_direct_assignment(task));
+
+        // The patient may read the task, but not by using the access code.
+        let res = state.task_get(
+            id.clone(),
+            Some(kvnr.clone()),
+            None,
+            None,
+            (&patient).into(),
+        );
+        assert!(res.is_ok());
+
+        let res = state.task_get(
+            id.clone(),
+            None,
+            Some(XAccessCode(access_code.clone())),
+            None,
+            (&patient).into(),
+        );
+        assert!(matches!(res, Err(Error::Forbidden(_))));
+
+        let count = state
+            .task_iter(
+                None,
+                Some(XAccessCode(access_code.clone())),
+                (&patient).into(),
+                |_| true,
+            )
+            .count();
+        assert_eq!(count, 0);
+
+        // The patient may not abort the task.
+        let res = state.task_abort(id.clone(), &patient, None, None, None, (&patient).into());
+        assert!(matches!(res, Err(Error::Forbidden(_))));
+
+        let res = state.task_abort(
+            id.clone(),
+            &patient,
+            Some(XAccessCode(access_code)),
+            None,
+            None,
+            (&patient).into(),
+        );
+        assert!(matches!(res, Err(Error::Forbidden(_))));
+        assert_eq!(
+            state.tasks.get_by_id(&id).unwrap().task.status,
+            Status::Ready
+        );
+    }
+
+    #[tokio::test]
+    async fn direct_assignment_performer_binding() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let telematik_id_a = TelematikId::new("606358757");
+        let telematik_id_b = TelematikId::new("606358758");
+        let pharmacy_a = access_token(Profession::OeffentlicheApotheke, "606358757");
+        let pharmacy_b = access_token(Profession::OeffentlicheApotheke, "606358758");
+
+        let (id, access_code) = create_ready_task(&mut state, FlowType::DirekteZuweisung, &kvnr);
+
+        // The task is bound to the pharmacy that accepted it.
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code.clone()),
+                None,
+                (&pharmacy_a).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+        assert_eq!(
+            state.tasks.get_by_id(&id).unwrap().designated_performer,
+            Some(telematik_id_a.clone())
+        );
+
+        let res = state.task_dispense(
+            id.clone(),
+            secret.clone(),
+            telematik_id_b.clone(),
+            vec![],
+            None,
+            (&pharmacy_b).into(),
+        );
+        assert!(matches!(res, Err(Error::PerformerMismatch)));
+
+        let res = state.task_dispense(
+            id.clone(),
+            secret.clone(),
+            telematik_id_a,
+            vec![],
+            None,
+            (&pharmacy_a).into(),
+        );
+        assert!(matches!(res, Err(Error::MedicationDispenseMissing)));
+
+        // After the task was rejected it may be accepted by another pharmacy.
+        state
+            .task_reject(id.clone(), secret, None, (&pharmacy_a).into())
+            .unwrap();
+        assert_eq!(
+            state.tasks.get_by_id(&id).unwrap().designated_performer,
+            None
+        );
+
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code),
+                None,
+                (&pharmacy_b).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+        assert_eq!(
+            state.tasks.get_by_id(&id).unwrap().designated_performer,
+            Some(telematik_id_b.clone())
+        );
+
+        let res = state.task_dispense(
+            id,
+            secret,
+            telematik_id_b,
+            vec![],
+            None,
+            (&pharmacy_b).into(),
+        );
+        assert!(matches!(res, Err(Error::MedicationDispenseMissing)));
+    }
+}
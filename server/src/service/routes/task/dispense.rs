@@ -0,0 +1,92 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use actix_web::{
+    web::{Data, Path, Payload, Query},
+    HttpResponse,
+};
+use resources::{
+    bundle::{Bundle, Entry, Type},
+    primitives::Id,
+};
+use serde::Deserialize;
+
+use crate::{
+    fhir::definitions::MedicationDispenseList,
+    service::{
+        header::{Accept, Authorization, ContentType, IfMatch},
+        misc::{create_response, read_payload, DataType, Profession},
+        IntoReqErrResult, TypedRequestError, TypedRequestResult,
+    },
+    state::State,
+};
+
+#[derive(Deserialize)]
+pub struct QueryArgs {
+    secret: Option<String>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn dispense(
+    state: Data<State>,
+    id: Path<Id>,
+    accept: Accept,
+    content_type: ContentType,
+    access_token: Authorization,
+    query: Query<QueryArgs>,
+    if_match: Option<IfMatch>,
+    payload: Payload,
+) -> Result<HttpResponse, TypedRequestError> {
+    let data_type = DataType::from_mime(&content_type);
+    let accept = DataType::from_accept(&accept)
+        .unwrap_or_default()
+        .replace_any(data_type)
+        .check_supported()
+        .err_with_type_default()?;
+
+    access_token
+        .check_profession(|p| {
+            p == Profession::OeffentlicheApotheke || p == Profession::KrankenhausApotheke
+        })
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    let id = id.into_inner();
+    let secret = query.into_inner().secret;
+    let performer = access_token
+        .telematik_id()
+        .into_req_err()
+        .err_with_type(accept)?;
+    let MedicationDispenseList(medication_dispenses) =
+        read_payload::<MedicationDispenseList>(data_type, payload)
+            .await
+            .err_with_type(accept)?;
+    let agent = (&*access_token).into();
+
+    let mut state = state.lock().await;
+    let medication_dispenses = state
+        .task_dispense(id, secret, performer, medication_dispenses, if_match, agent)
+        .into_req_err()
+        .err_with_type(accept)?;
+
+    let mut bundle = Bundle::new(Type::Collection);
+    for medication_dispense in medication_dispenses {
+        bundle.entries.push(Entry::new(medication_dispense));
+    }
+
+    create_response(&bundle, accept)
+}
@@ -15,7 +15,8 @@
  *
  */
 
-use resources::primitives::Id;
+use chrono::NaiveDate;
+use resources::{misc::PrescriptionId, primitives::Id, types::FlowType};
 use thiserror::Error;
 
 use crate::fhir::security::SignedError;
@@ -28,6 +29,9 @@ pub enum Error {
     #[error("Not Found: /Task/{0}!")]
     NotFound(Id),
 
+    #[error("Not Found: /Task/{0}/_history/{1}!")]
+    VersionNotFound(Id, Id),
+
     #[error("Forbidden: /Task/{0}!")]
     Forbidden(Id),
 
@@ -37,12 +41,18 @@ pub enum Error {
     #[error("Gone: /Task/{0}!")]
     Gone(Id),
 
+    #[error("Precondition Failed: /Task/{0}!")]
+    PreconditionFailed(Id),
+
     #[error("Missing e-Prescription Reference!")]
     EPrescriptionMissing,
 
     #[error("e-Prescription does not match!")]
     EPrescriptionMismatch,
 
+    #[error("At least one Medication Dispense is required!")]
+    MedicationDispenseMissing,
+
     #[error("Referenced e-Prescription was not found: {0}!")]
     EPrescriptionNotFound(Id),
 
@@ -84,6 +94,62 @@ pub enum Error {
 
     #[error("Unable to generate prescription id (try again later)!")]
     GeneratePrescriptionId,
+
+    #[error("Flow type is not supported: {0}!")]
+    UnsupportedFlowType(FlowType),
+
+    #[error("Prescription is part of a multiple prescription and can not be redeemed before {0}!")]
+    RedeemPeriodNotStarted(NaiveDate),
+
+    #[error("Invalid KBV Bundle: {0}")]
+    InvalidKbvBundle(KbvBundleError),
+}
+
+#[derive(Error, Debug)]
+pub enum KbvBundleError {
+    #[error("Bundle identifier does not match the prescription ID of the task: {0}!")]
+    PrescriptionIdMismatch(PrescriptionId),
+
+    #[error("Bundle is missing the {0} entry!")]
+    MissingEntry(&'static str),
+
+    #[error("{0}.{1} does not reference the matching entry of the bundle!")]
+    InvalidReference(&'static str, &'static str),
+
+    #[error("Coverage type ({1}) does not match the flow type: {0}!")]
+    CoverageMismatch(FlowType, String),
+
+    #[error("Composition is missing the PKV tariff that is required by the flow type: {0}!")]
+    CompositionMismatch(FlowType),
+
+    #[error("Medication request was authored on {0}, but the bundle was signed on {1}!")]
+    AuthoredOnMismatch(String, NaiveDate),
+
+    #[error("Multiple prescription has an invalid series element or redeem period!")]
+    InvalidMultiPrescription,
+}
+
+impl KbvBundleError {
+    /// FHIRPath expression of the bundle element that caused the error.
+    pub fn expression(&self) -> String {
+        match self {
+            Self::PrescriptionIdMismatch(_) => "Bundle.identifier".into(),
+            Self::MissingEntry(resource) => format!("Bundle.entry.resource.ofType({})", resource),
+            Self::InvalidReference(resource, field) => {
+                format!("Bundle.entry.resource.ofType({}).{}", resource, field)
+            }
+            Self::CoverageMismatch(_, _) => "Bundle.entry.resource.ofType(Coverage).type".into(),
+            Self::CompositionMismatch(_) => {
+                "Bundle.entry.resource.ofType(Composition).extension".into()
+            }
+            Self::AuthoredOnMismatch(_, _) => {
+                "Bundle.entry.resource.ofType(MedicationRequest).authoredOn".into()
+            }
+            Self::InvalidMultiPrescription => {
+                "Bundle.entry.resource.ofType(MedicationRequest).extension".into()
+            }
+        }
+    }
 }
 
 impl From<SignedError> for Error {
@@ -91,3 +157,9 @@ impl From<SignedError> for Error {
         Self::SignedError(err)
     }
 }
+
+impl From<KbvBundleError> for Error {
+    fn from(err: KbvBundleError) -> Self {
+        Self::InvalidKbvBundle(err)
+    }
+}
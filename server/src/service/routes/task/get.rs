@@ -19,6 +19,7 @@ use std::borrow::ToOwned;
 use std::str::FromStr;
 
 use actix_web::{
+    http::StatusCode,
     web::{Data, Path},
     HttpRequest, HttpResponse,
 };
@@ -26,24 +27,30 @@ use chrono::{DateTime, Utc};
 use resources::{
     audit_event::Language,
     bundle::{Bundle, Entry, Relation, Type},
+    misc::{Kvnr, PrescriptionId},
     primitives::Id,
     task::Status,
+    types::PerformerType,
     Task,
 };
 
 use crate::{
     service::{
-        header::{Accept, AcceptLanguage, Authorization, XAccessCode},
+        header::{Accept, AcceptLanguage, Authorization, IfNoneMatch, XAccessCode},
         misc::{
-            create_response, make_page_uri, AccessToken, DataType, FromQuery, Profession, Query,
-            QueryValue, Search, Sort,
+            check_not_modified, create_response, create_response_with, make_page_uri, AccessToken,
+            DataType, FromQuery, Profession, Query, QueryValue, Search, Sort,
         },
         IntoReqErr, IntoReqErrResult, TypedRequestError, TypedRequestResult,
     },
     state::{Inner as StateInner, State},
 };
 
-use super::{misc::Resource, Error};
+use super::{
+    direct_assignment::is_direct_assignment,
+    misc::{set_task_headers, Resource},
+    Error,
+};
 
 #[derive(Default)]
 pub struct GetOneQueryArgs {
@@ -53,12 +60,18 @@ pub struct GetOneQueryArgs {
 
 #[derive(Default)]
 pub struct GetAllQueryArgs {
+    id: Vec<Search<Id>>,
+    identifier: Vec<Search<PrescriptionId>>,
+    for_: Vec<Search<Kvnr>>,
+    performer_type: Vec<Search<PerformerType>>,
     status: Vec<Search<Status>>,
     authored_on: Vec<Search<DateTime<Utc>>>,
     last_modified: Vec<Search<DateTime<Utc>>>,
+    last_updated: Vec<Search<DateTime<Utc>>>,
     sort: Option<Sort<SortArgs>>,
     count: Option<usize>,
     page_id: Option<usize>,
+    total: Option<TotalArgs>,
 }
 
 #[derive(Default, Debug)]
@@ -100,6 +113,12 @@ impl FromQuery for GetOneQueryArgs {
 impl FromQuery for GetAllQueryArgs {
     fn parse_key_value_pair(&mut self, key: &str, value: QueryValue) -> Result<(), String> {
         match key {
+            "_id" => self.id.push(value.ok()?.parse()?),
+            "identifier" => self.identifier.push(value.ok()?.parse()?),
+            "for" => self.for_.push(value.ok()?.parse()?),
+            "performer-type" | "performerType" | "performertype" => {
+                self.performer_type.push(value.ok()?.parse()?)
+            }
             "status" => self.status.push(value.ok()?.parse()?),
             "authoredOn" | "authored-on" | "authoredon" => {
                 self.authored_on.push(value.ok()?.parse()?)
@@ -107,6 +126,8 @@ impl FromQuery for GetAllQueryArgs {
             "lastModified" | "last-modified" | "modified" => {
                 self.last_modified.push(value.ok()?.parse()?)
             }
+            "_lastUpdated" => self.last_updated.push(value.ok()?.parse()?),
+            "_total" => self.total = Some(value.ok()?.parse()?),
             "_sort" => self.sort = Some(value.ok()?.parse()?),
             "_count" => self.count = Some(value.ok()?.parse::<usize>().map_err(|e| e.to_string())?),
             "pageId" | "page-id" | "pageid" => {
@@ -117,6 +138,52 @@ impl FromQuery for GetAllQueryArgs {
 
         Ok(())
     }
+
+    fn is_supported(key: &str) -> bool {
+        matches!(
+            key,
+            "_id"
+                | "identifier"
+                | "for"
+                | "performer-type"
+                | "performerType"
+                | "performertype"
+                | "status"
+                | "authoredOn"
+                | "authored-on"
+                | "authoredon"
+                | "lastModified"
+                | "last-modified"
+                | "modified"
+                | "_lastUpdated"
+                | "_total"
+                | "_sort"
+                | "_count"
+                | "pageId"
+                | "page-id"
+                | "pageid"
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TotalArgs {
+    None,
+    Estimate,
+    Accurate,
+}
+
+impl FromStr for TotalArgs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "estimate" => Ok(Self::Estimate),
+            "accurate" => Ok(Self::Accurate),
+            s => Err(format!("Invalid total: {}", s)),
+        }
+    }
 }
 
 pub enum SortArgs {
@@ -139,18 +206,26 @@ impl FromStr for SortArgs {
 enum TaskReference {
     All(GetAllQueryArgs),
     One(Id, GetOneQueryArgs),
+    History(Id, GetOneQueryArgs),
+    Version(Id, Id, GetOneQueryArgs),
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_all(
     state: Data<State>,
     accept: Accept,
     accept_language: AcceptLanguage,
     access_token: Authorization,
     access_code: Option<XAccessCode>,
+    if_none_match: Option<IfNoneMatch>,
     query: Query<GetAllQueryArgs>,
     request: HttpRequest,
 ) -> Result<HttpResponse, TypedRequestError> {
-    if let Err(err) = access_token.check_profession(|p| p == Profession::Versicherter) {
+    if let Err(err) = access_token.check_profession(|p| {
+        p == Profession::Versicherter
+            || p == Profession::OeffentlicheApotheke
+            || p == Profession::KrankenhausApotheke
+    }) {
         let accept = DataType::from_accept(&accept)
             .and_then(DataType::ignore_any)
             .unwrap_or_default()
@@ -170,8 +245,10 @@ pub async fn get_all(
         request.query_string(),
     )
     .await
+    .map(|res| check_not_modified(res, if_none_match.as_ref()))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_one(
     state: Data<State>,
     id: Path<Id>,
@@ -179,6 +256,7 @@ pub async fn get_one(
     accept_language: AcceptLanguage,
     access_token: Authorization,
     access_code: Option<XAccessCode>,
+    if_none_match: Option<IfNoneMatch>,
     query: Query<GetOneQueryArgs>,
 ) -> Result<HttpResponse, TypedRequestError> {
     get(
@@ -191,6 +269,57 @@ pub async fn get_one(
         "",
     )
     .await
+    .map(|res| check_not_modified(res, if_none_match.as_ref()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn get_history(
+    state: Data<State>,
+    id: Path<Id>,
+    accept: Accept,
+    accept_language: AcceptLanguage,
+    access_token: Authorization,
+    access_code: Option<XAccessCode>,
+    if_none_match: Option<IfNoneMatch>,
+    query: Query<GetOneQueryArgs>,
+) -> Result<HttpResponse, TypedRequestError> {
+    get(
+        &state,
+        TaskReference::History(id.into_inner(), query.0),
+        accept,
+        accept_language,
+        access_token,
+        access_code,
+        "",
+    )
+    .await
+    .map(|res| check_not_modified(res, if_none_match.as_ref()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn get_version(
+    state: Data<State>,
+    path: Path<(Id, Id)>,
+    accept: Accept,
+    accept_language: AcceptLanguage,
+    access_token: Authorization,
+    access_code: Option<XAccessCode>,
+    if_none_match: Option<IfNoneMatch>,
+    query: Query<GetOneQueryArgs>,
+) -> Result<HttpResponse, TypedRequestError> {
+    let (id, version_id) = path.into_inner();
+
+    get(
+        &state,
+        TaskReference::Version(id, version_id, query.0),
+        accept,
+        accept_language,
+        access_token,
+        access_code,
+        "",
+    )
+    .await
+    .map(|res| check_not_modified(res, if_none_match.as_ref()))
 }
 
 async fn get(
@@ -229,15 +358,16 @@ async fn get(
         }
     }
 
-    let mut state = state.lock().await;
+    let state = state.read().await;
 
     match reference {
         TaskReference::One(id, query) => {
-            let (state, task) = state
+            let task = state
                 .task_get(id.clone(), kvnr, access_code, query.secret, agent)
                 .into_req_err()
                 .err_with_type(accept)?;
 
+            let mut audit_events = Vec::new();
             let mut bundle = Bundle::new(Type::Collection);
             add_to_bundle(&mut bundle, &task, &access_token, Some(&state))
                 .into_req_err()
@@ -255,22 +385,80 @@ async fn get(
                         && (target.is_none() || target.as_deref() == Some("AuditEvent")));
 
                 if inc_audit_event {
-                    if let Some(events) = state.audit_event_iter_by_task(&id) {
-                        for event in events {
-                            bundle
-                                .entries
-                                .push(Entry::new(Resource::AuditEvent(event, lang)));
-                        }
-                    }
+                    audit_events.extend(state.audit_event_iter_by_task(&id));
                 }
             }
 
+            for event in &audit_events {
+                bundle
+                    .entries
+                    .push(Entry::new(Resource::AuditEvent(event, lang)));
+            }
+
+            // Audit events are not covered by the version of the task, so the
+            // entity tag is derived from the body if they are included.
+            create_response_with(&bundle, accept, StatusCode::OK, |res| {
+                if audit_events.is_empty() {
+                    set_task_headers(res, task);
+                }
+            })
+        }
+        TaskReference::History(id, query) => {
+            let task_meta = state
+                .task_get_meta(id, kvnr, access_code, query.secret, agent)
+                .into_req_err()
+                .err_with_type(accept)?;
+
+            let mut bundle = Bundle::new(Type::History);
+            for task in task_meta.versions() {
+                add_to_bundle(&mut bundle, task, &access_token, None)
+                    .into_req_err()
+                    .err_with_type(accept)?;
+            }
+
+            bundle.total = Some(bundle.entries.len());
+
             create_response(&bundle, accept)
         }
+        TaskReference::Version(id, version_id, query) => {
+            let task_meta = state
+                .task_get_meta(id.clone(), kvnr, access_code, query.secret, agent)
+                .into_req_err()
+                .err_with_type(accept)?;
+
+            let task = task_meta
+                .version(&version_id)
+                .ok_or(Error::VersionNotFound(id, version_id))
+                .into_req_err()
+                .err_with_type(accept)?;
+
+            let mut bundle = Bundle::new(Type::Collection);
+            add_to_bundle(&mut bundle, task, &access_token, None)
+                .into_req_err()
+                .err_with_type(accept)?;
+
+            create_response_with(&bundle, accept, StatusCode::OK, |res| {
+                set_task_headers(res, task)
+            })
+        }
         TaskReference::All(query) => {
-            let mut tasks = state
-                .task_iter(kvnr, access_code, agent, |t| check_query(&query, t))
-                .collect::<Vec<_>>();
+            // Pharmacies only get the tasks they have accepted and are still
+            // working on.
+            let is_pharmacy = access_token.is_pharmacy();
+            let mut tasks = if is_pharmacy {
+                let telematik_id = access_token
+                    .telematik_id()
+                    .into_req_err()
+                    .err_with_type(accept)?;
+
+                state
+                    .task_iter_by_pharmacy(&telematik_id, agent, |t| check_query(&query, t))
+                    .collect::<Vec<_>>()
+            } else {
+                state
+                    .task_iter(kvnr, access_code, agent, |t| check_query(&query, t))
+                    .collect::<Vec<_>>()
+            };
 
             // Sort the result
             if let Some(sort) = &query.sort {
@@ -302,12 +490,22 @@ async fn get(
 
             let mut bundle = Bundle::new(Type::Searchset);
             for task in tasks.iter().skip(skip).take(take) {
-                add_to_bundle(&mut bundle, &task, &access_token, None)
-                    .into_req_err()
-                    .err_with_type(accept)?;
+                if is_pharmacy {
+                    bundle
+                        .entries
+                        .push(Entry::new(Resource::TaskForSupplierSearch(task)));
+                } else {
+                    add_to_bundle(&mut bundle, &task, &access_token, None)
+                        .into_req_err()
+                        .err_with_type(accept)?;
+                }
             }
 
-            bundle.total = Some(tasks.len());
+            // The total is always known exactly, so it is only omitted if
+            // the client explicitly does not want it.
+            if query.total != Some(TotalArgs::None) {
+                bundle.total = Some(tasks.len());
+            }
 
             if let Some(count) = query.count {
                 let page_count = ((tasks.len() - 1) / count) + 1;
@@ -344,6 +542,36 @@ async fn get(
 }
 
 fn check_query(query: &GetAllQueryArgs, task: &Task) -> bool {
+    for id in &query.id {
+        if !id.matches(&task.id) {
+            return false;
+        }
+    }
+
+    for identifier in &query.identifier {
+        match &task.identifier.prescription_id {
+            Some(prescription_id) if identifier.matches(prescription_id) => (),
+            _ => return false,
+        }
+    }
+
+    for for_ in &query.for_ {
+        match &task.for_ {
+            Some(kvnr) if for_.matches(kvnr) => (),
+            _ => return false,
+        }
+    }
+
+    for performer_type in &query.performer_type {
+        if !task
+            .performer_type
+            .iter()
+            .any(|p| performer_type.matches(p))
+        {
+            return false;
+        }
+    }
+
     for status in &query.status {
         if !status.matches(&task.status) {
             return false;
@@ -358,7 +586,7 @@ fn check_query(query: &GetAllQueryArgs, task: &Task) -> bool {
         }
     }
 
-    for last_modified in &query.last_modified {
+    for last_modified in query.last_modified.iter().chain(&query.last_updated) {
         if let Some(task_last_modified) = &task.last_modified {
             if !last_modified.matches(&task_last_modified.clone().into()) {
                 return false;
@@ -419,7 +647,11 @@ where
 
     #[cfg(feature = "interface-patient")]
     {
-        if access_token.is_patient() {
+        // Patients get the receipt of a direct assignment only after it
+        // was completed by the performer.
+        let is_restricted = is_direct_assignment(task) && task.status != Status::Completed;
+
+        if access_token.is_patient() && !is_restricted {
             if let Some(id) = task.input.patient_receipt.as_ref() {
                 let patient_receipt = state
                     .patient_receipts
@@ -0,0 +1,341 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use chrono::{DateTime, NaiveDate, Utc};
+use resources::{misc::PrescriptionId, types::FlowType, KbvBundle};
+
+use crate::state::local_date;
+
+use super::KbvBundleError;
+
+/// Check that the KBV bundle is consistent in itself and with the task it
+/// should be assigned to.
+///
+/// The bundle has to contain all entries that are needed to dispense the
+/// prescription, the entries have to reference each other and the bundle
+/// has to match the prescription ID, the flow type and the signing time.
+pub(super) fn validate_kbv_bundle(
+    kbv_bundle: &KbvBundle,
+    prescription_id: Option<&PrescriptionId>,
+    flow_type: FlowType,
+    signing_time: DateTime<Utc>,
+) -> Result<(), KbvBundleError> {
+    if let Some(prescription_id) = prescription_id {
+        if &kbv_bundle.identifier != prescription_id {
+            return Err(KbvBundleError::PrescriptionIdMismatch(
+                kbv_bundle.identifier.clone(),
+            ));
+        }
+    }
+
+    /* required entries */
+
+    let entry = &kbv_bundle.entry;
+    let (_, composition) = entry
+        .composition
+        .as_ref()
+        .ok_or(KbvBundleError::MissingEntry("Composition"))?;
+    let (medication_request_url, medication_request) = entry
+        .medication_request
+        .as_ref()
+        .ok_or(KbvBundleError::MissingEntry("MedicationRequest"))?;
+    let (medication_url, _) = entry
+        .medication
+        .as_ref()
+        .ok_or(KbvBundleError::MissingEntry("Medication"))?;
+    let (patient_url, _) = entry
+        .patient
+        .as_ref()
+        .ok_or(KbvBundleError::MissingEntry("Patient"))?;
+    let (practitioner_url, _) = entry
+        .practitioner
+        .as_ref()
+        .ok_or(KbvBundleError::MissingEntry("Practitioner"))?;
+    let (coverage_url, coverage) = entry
+        .coverage
+        .as_ref()
+        .ok_or(KbvBundleError::MissingEntry("Coverage"))?;
+
+    /* references */
+
+    let check = |resource, field, reference: Option<&String>, url: &String| match reference {
+        Some(reference) if is_reference_to(reference, url) => Ok(()),
+        _ => Err(KbvBundleError::InvalidReference(resource, field)),
+    };
+
+    let section = &composition.section;
+    check(
+        "Composition",
+        "subject",
+        composition.subject.as_ref(),
+        patient_url,
+    )?;
+    check(
+        "Composition",
+        "author",
+        Some(&composition.author.doctor),
+        practitioner_url,
+    )?;
+    check(
+        "Composition",
+        "section",
+        section.prescription.as_ref(),
+        medication_request_url,
+    )?;
+    check(
+        "Composition",
+        "section",
+        section.coverage.as_ref(),
+        coverage_url,
+    )?;
+
+    check(
+        "MedicationRequest",
+        "medication",
+        Some(&medication_request.medication),
+        medication_url,
+    )?;
+    check(
+        "MedicationRequest",
+        "subject",
+        Some(&medication_request.subject),
+        patient_url,
+    )?;
+    check(
+        "MedicationRequest",
+        "requester",
+        Some(&medication_request.requester),
+        practitioner_url,
+    )?;
+    check(
+        "MedicationRequest",
+        "insurance",
+        Some(&medication_request.insurance),
+        coverage_url,
+    )?;
+
+    check(
+        "Coverage",
+        "beneficiary",
+        Some(&coverage.beneficiary),
+        patient_url,
+    )?;
+
+    /* flow type */
+
+    let is_pkv_coverage = coverage.type_.code == COVERAGE_TYPE_PKV;
+    if flow_type.is_pkv() != is_pkv_coverage {
+        return Err(KbvBundleError::CoverageMismatch(
+            flow_type,
+            coverage.type_.code.clone(),
+        ));
+    }
+
+    if flow_type.is_pkv() && composition.extension.pkv.is_none() {
+        return Err(KbvBundleError::CompositionMismatch(flow_type));
+    }
+
+    /* signing time */
+
+    let signing_date = local_date(signing_time);
+    let authored_on = parse_date(&medication_request.authored_on);
+    if authored_on != Some(signing_date) {
+        return Err(KbvBundleError::AuthoredOnMismatch(
+            medication_request.authored_on.to_string(),
+            signing_date,
+        ));
+    }
+
+    /* multiple prescription */
+
+    if let Some(multi_prescription) = &medication_request.extension.multi_prescription {
+        let series_element = &multi_prescription.series_element;
+        if series_element.numerator == 0 || series_element.numerator > series_element.denominator {
+            return Err(KbvBundleError::InvalidMultiPrescription);
+        }
+
+        let time_range = &multi_prescription.time_range;
+        let start = time_range.start.as_ref().map(|start| parse_date(start));
+        let end = time_range.end.as_ref().map(|end| parse_date(end));
+        match (start, end) {
+            (Some(None), _) | (_, Some(None)) => {
+                return Err(KbvBundleError::InvalidMultiPrescription)
+            }
+            (Some(Some(start)), Some(Some(end))) if end < start => {
+                return Err(KbvBundleError::InvalidMultiPrescription)
+            }
+            (_, _) => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the date part of a FHIR date or date time value.
+pub(super) fn parse_date(value: &str) -> Option<NaiveDate> {
+    value
+        .get(..10)
+        .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+}
+
+const COVERAGE_TYPE_PKV: &str = "PKV";
+
+/// Returns `true` if the reference of an entry points to the entry with the
+/// passed full URL.
+///
+/// Entries are referenced either by their full URL or relative by their
+/// resource type and ID (e.g. `Patient/<id>`).
+fn is_reference_to(reference: &str, full_url: &str) -> bool {
+    reference == full_url
+        || full_url
+            .strip_suffix(reference)
+            .map_or(false, |base| base.ends_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    use crate::fhir::decode::{tests::load_stream, JsonDecode};
+
+    async fn kbv_bundle() -> KbvBundle {
+        let mut stream = load_stream("./examples/kbv_bundle.json");
+
+        stream.json::<KbvBundle>().await.unwrap()
+    }
+
+    fn validate(kbv_bundle: &KbvBundle) -> Result<(), KbvBundleError> {
+        let prescription_id = "160.123.456.789.123.58".parse().unwrap();
+        let signing_time = Utc.ymd(2020, 2, 3).and_hms(10, 0, 0);
+
+        validate_kbv_bundle(
+            kbv_bundle,
+            Some(&prescription_id),
+            FlowType::ApothekenpflichtigeArzneimittel,
+            signing_time,
+        )
+    }
+
+    #[tokio::test]
+    async fn validate_consistent_bundle() {
+        let kbv_bundle = kbv_bundle().await;
+
+        validate(&kbv_bundle).unwrap();
+    }
+
+    #[tokio::test]
+    async fn validate_prescription_id() {
+        let mut kbv_bundle = kbv_bundle().await;
+        kbv_bundle.identifier =
+            PrescriptionId::new(FlowType::ApothekenpflichtigeArzneimittel, 123456789124);
+
+        let res = validate(&kbv_bundle);
+
+        assert!(matches!(
+            res,
+            Err(KbvBundleError::PrescriptionIdMismatch(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn validate_missing_entry() {
+        let mut kbv_bundle = kbv_bundle().await;
+        kbv_bundle.entry.coverage = None;
+
+        let res = validate(&kbv_bundle);
+
+        assert!(matches!(res, Err(KbvBundleError::MissingEntry("Coverage"))));
+    }
+
+    #[tokio::test]
+    async fn validate_references() {
+        let mut kbv_bundle = kbv_bundle().await;
+        kbv_bundle
+            .entry
+            .medication_request
+            .as_mut()
+            .unwrap()
+            .1
+            .subject = "Patient/00000000-0000-0000-0000-000000000000".into();
+
+        let res = validate(&kbv_bundle);
+
+        assert!(matches!(
+            res,
+            Err(KbvBundleError::InvalidReference(
+                "MedicationRequest",
+                "subject"
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn validate_flow_type() {
+        let prescription_id = PrescriptionId::new(FlowType::ApothekenpflichtigeArzneimittelPkv, 1);
+        let mut kbv_bundle = kbv_bundle().await;
+        kbv_bundle.identifier = prescription_id.clone();
+
+        let res = validate_kbv_bundle(
+            &kbv_bundle,
+            Some(&prescription_id),
+            FlowType::ApothekenpflichtigeArzneimittelPkv,
+            Utc.ymd(2020, 2, 3).and_hms(10, 0, 0),
+        );
+
+        assert!(matches!(res, Err(KbvBundleError::CoverageMismatch(_, _))));
+    }
+
+    #[tokio::test]
+    async fn validate_multi_prescription() {
+        let mut kbv_bundle = kbv_bundle().await;
+        let medication_request = &mut kbv_bundle.entry.medication_request.as_mut().unwrap().1;
+        let multi_prescription = medication_request
+            .extension
+            .multi_prescription
+            .as_mut()
+            .unwrap();
+        multi_prescription.series_element.numerator = 5;
+
+        let res = validate(&kbv_bundle);
+
+        assert!(matches!(res, Err(KbvBundleError::InvalidMultiPrescription)));
+    }
+
+    #[tokio::test]
+    async fn validate_signing_time() {
+        let kbv_bundle = kbv_bundle().await;
+        let prescription_id = "160.123.456.789.123.58".parse().unwrap();
+
+        for (signing_time, is_ok) in &[
+            (Utc.ymd(2020, 2, 2).and_hms(23, 30, 0), true),
+            (Utc.ymd(2020, 2, 3).and_hms(22, 30, 0), true),
+            (Utc.ymd(2020, 2, 3).and_hms(23, 30, 0), false),
+            (Utc.ymd(2020, 2, 4).and_hms(10, 0, 0), false),
+        ] {
+            let res = validate_kbv_bundle(
+                &kbv_bundle,
+                Some(&prescription_id),
+                FlowType::ApothekenpflichtigeArzneimittel,
+                *signing_time,
+            );
+
+            assert_eq!(res.is_ok(), *is_ok, "{}", signing_time);
+        }
+    }
+}
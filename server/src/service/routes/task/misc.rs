@@ -15,6 +15,13 @@
  *
  */
 
+use std::time::SystemTime;
+
+use actix_web::{
+    dev::HttpResponseBuilder,
+    http::header::{ETag, EntityTag, LastModified},
+};
+use chrono::{DateTime, Utc};
 use resources::{audit_event::Language, AuditEvent, ErxBundle, KbvBinary, KbvBundle, Task};
 
 use crate::fhir::{
@@ -25,6 +32,7 @@ use crate::fhir::{
 #[derive(Clone)]
 pub enum Resource<'a> {
     TaskForSupplier(&'a Task),
+    TaskForSupplierSearch(&'a Task),
     TaskForPatient(&'a Task),
     KbvBinary(&'a KbvBinary),
     KbvBundle(&'a KbvBundle),
@@ -41,6 +49,7 @@ impl Encode for Resource<'_> {
     {
         match self {
             Self::TaskForSupplier(v) => TaskContainer::for_supplier(v).encode(stream),
+            Self::TaskForSupplierSearch(v) => TaskContainer::for_supplier_search(v).encode(stream),
             Self::TaskForPatient(v) => TaskContainer::for_patient(v).encode(stream),
             Self::KbvBinary(v) => v.encode(stream),
             Self::KbvBundle(v) => v.encode(stream),
@@ -51,3 +60,25 @@ impl Encode for Resource<'_> {
         }
     }
 }
+
+/// Get the entity tag of the current version of the task.
+pub fn task_etag(task: &Task) -> EntityTag {
+    let version_id = match &task.version_id {
+        Some(version_id) => version_id.to_string(),
+        None => "1".into(),
+    };
+
+    EntityTag::weak(version_id)
+}
+
+/// Set the `ETag` and the `Last-Modified` header of a response that
+/// contains the passed task.
+pub fn set_task_headers(res: &mut HttpResponseBuilder, task: &Task) {
+    res.set(ETag(task_etag(task)));
+
+    if let Some(last_modified) = &task.last_modified {
+        let last_modified: DateTime<Utc> = last_modified.clone().into();
+
+        res.set(LastModified(SystemTime::from(last_modified).into()));
+    }
+}
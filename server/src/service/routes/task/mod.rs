@@ -22,15 +22,22 @@ mod activate;
 mod close;
 #[cfg(feature = "interface-supplier")]
 mod create;
+mod direct_assignment;
+#[cfg(feature = "interface-supplier")]
+mod dispense;
 mod error;
 mod get;
+mod kbv_bundle;
 mod misc;
 mod reject;
 mod state;
 
-pub use error::Error;
+pub use error::{Error, KbvBundleError};
 pub use state::{TaskMeta, Tasks};
 
+#[cfg(test)]
+pub use state::tests;
+
 use abort::abort;
 #[cfg(feature = "interface-supplier")]
 use actix_web::web::post;
@@ -40,7 +47,7 @@ use resources::capability_statement::{Interaction, SearchParamType, Type};
 
 use crate::fhir::definitions::{
     OPERATION_TASK_ABORT, OPERATION_TASK_ACCEPT, OPERATION_TASK_ACTIVATE, OPERATION_TASK_CLOSE,
-    OPERATION_TASK_CREATE, OPERATION_TASK_REJECT, RESOURCE_PROFILE_TASK,
+    OPERATION_TASK_CREATE, OPERATION_TASK_DISPENSE, OPERATION_TASK_REJECT, RESOURCE_PROFILE_TASK,
 };
 
 #[cfg(feature = "interface-supplier")]
@@ -51,7 +58,9 @@ use activate::activate;
 use close::close;
 #[cfg(feature = "interface-supplier")]
 use create::create;
-use get::{get_all, get_one};
+#[cfg(feature = "interface-supplier")]
+use dispense::dispense;
+use get::{get_all, get_history, get_one, get_version};
 #[cfg(feature = "interface-supplier")]
 use reject::reject;
 
@@ -67,23 +76,37 @@ impl TaskRoutes {
     #[operation(name="activate", definition = OPERATION_TASK_ACTIVATE)]
     #[operation(name="accept", definition = OPERATION_TASK_ACCEPT)]
     #[operation(name="reject", definition = OPERATION_TASK_REJECT)]
+    #[operation(name="dispense", definition = OPERATION_TASK_DISPENSE)]
     #[operation(name="close", definition = OPERATION_TASK_CLOSE)]
     fn configure_supplier(&self, cfg: &mut ServiceConfig) {
         cfg.service(resource("/Task/$create").route(post().to(create)));
         cfg.service(resource("/Task/{id:[A-Za-z0-9-]+}/$activate").route(post().to(activate)));
         cfg.service(resource("/Task/{id:[A-Za-z0-9-]+}/$accept").route(post().to(accept)));
         cfg.service(resource("/Task/{id:[A-Za-z0-9-]+}/$reject").route(post().to(reject)));
+        cfg.service(resource("/Task/{id:[A-Za-z0-9-]+}/$dispense").route(post().to(dispense)));
         cfg.service(resource("/Task/{id:[A-Za-z0-9-]+}/$close").route(post().to(close)));
     }
 
     #[interaction(Interaction::Read)]
+    #[interaction(Interaction::Vread)]
+    #[interaction(Interaction::HistoryInstance)]
+    #[search_param(name="_id", type=SearchParamType::Token)]
+    #[search_param(name="identifier", type=SearchParamType::Token)]
+    #[search_param(name="for", type=SearchParamType::Token)]
+    #[search_param(name="performer-type", type=SearchParamType::Token)]
     #[search_param(name="status", type=SearchParamType::Token)]
     #[search_param(name="authored-on", type=SearchParamType::Date)]
     #[search_param(name="modified", type=SearchParamType::Date)]
+    #[search_param(name="_lastUpdated", type=SearchParamType::Date)]
     #[operation(name="abort", definition = OPERATION_TASK_ABORT)]
     fn configure_all(&self, cfg: &mut ServiceConfig) {
         cfg.service(resource("/Task").route(get().to(get_all)));
         cfg.service(resource("/Task/{id:[A-Za-z0-9-]+}").route(get().to(get_one)));
+        cfg.service(resource("/Task/{id:[A-Za-z0-9-]+}/_history").route(get().to(get_history)));
+        cfg.service(
+            resource("/Task/{id:[A-Za-z0-9-]+}/_history/{vid:[A-Za-z0-9-]+}")
+                .route(get().to(get_version)),
+        );
         cfg.service(resource("/Task/{id:[A-Za-z0-9-]+}/$abort").route(post().to(abort)));
     }
 }
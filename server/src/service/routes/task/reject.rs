@@ -24,7 +24,7 @@ use serde::Deserialize;
 
 use crate::{
     service::{
-        header::{Accept, Authorization},
+        header::{Accept, Authorization, IfMatch},
         misc::{DataType, Profession},
         IntoReqErrResult, TypedRequestError, TypedRequestResult,
     },
@@ -42,6 +42,7 @@ pub async fn reject(
     accept: Accept,
     access_token: Authorization,
     query: Query<QueryArgs>,
+    if_match: Option<IfMatch>,
 ) -> Result<HttpResponse, TypedRequestError> {
     let accept = DataType::from_accept(&accept)
         .and_then(DataType::ignore_any)
@@ -63,7 +64,7 @@ pub async fn reject(
     state
         .lock()
         .await
-        .task_reject(id, secret, agent)
+        .task_reject(id, secret, if_match, agent)
         .into_req_err()
         .err_with_type(accept)?;
 
@@ -15,20 +15,20 @@
  *
  */
 
-use std::cell::RefCell;
-use std::collections::hash_map::{Entry, HashMap};
-use std::convert::TryInto;
-use std::ops::Add;
-use std::rc::Rc;
-
-use bdays::easter::easter_naive_date;
-use chrono::{Date, DateTime, Datelike, Duration, Utc, Weekday};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::convert::{TryFrom, TryInto};
+use std::hash::Hash;
+use std::iter::once;
+use std::ops::{Deref, DerefMut};
+
+use chrono::{Date, DateTime, Duration, NaiveDate, Utc};
 use rand::{distributions::Standard, rngs::OsRng, Rng};
 use resources::{
     audit_event::{Action, Agent, SubType, Text, What},
     composition::LegalBasis,
     erx_bundle::{Entry as ErxEntry, ErxBundle},
-    misc::{Kvnr, PrescriptionId, TelematikId},
+    medication_request::MultiPrescription,
+    misc::{Kvnr, ParticipantId, PrescriptionId, TelematikId},
     primitives::Id,
     task::{Extension, Identifier, Status, Task, TaskCreateParameters},
     types::{FlowType, PerformerType},
@@ -36,15 +36,36 @@ use resources::{
 };
 
 use crate::{
-    service::{header::XAccessCode, misc::AccessToken, misc::DEVICE},
-    state::Inner,
+    service::{
+        header::{IfMatch, XAccessCode},
+        misc::AccessToken,
+        misc::DEVICE,
+    },
+    state::{local_date, Calendar, Inner, Table},
 };
 
-use super::Error;
+use super::{
+    direct_assignment::is_direct_assignment,
+    kbv_bundle::{parse_date, validate_kbv_bundle},
+    misc::task_etag,
+    Error,
+};
 
 #[derive(Default)]
 pub struct Tasks {
-    by_id: HashMap<Id, TaskMeta>,
+    by_id: Table<Id, TaskMeta>,
+    index: TaskIndex,
+}
+
+/// Secondary indices of the tasks.
+///
+/// The indices must be updated whenever the indexed values of a task are
+/// changed, so tasks are only modified through `TaskMetaMut`.
+#[derive(Default)]
+struct TaskIndex {
+    by_kvnr: HashMap<Kvnr, HashSet<Id>>,
+    by_telematik_id: HashMap<TelematikId, HashSet<Id>>,
+    by_prescription_id: HashMap<PrescriptionId, Id>,
 }
 
 impl Tasks {
@@ -56,11 +77,13 @@ impl Tasks {
     pub fn insert_task_meta(&mut self, task_meta: TaskMeta) {
         let id = task_meta.task.id.clone();
 
-        match self.by_id.entry(id) {
+        match self.by_id.entry(id.clone()) {
             Entry::Occupied(e) => {
                 panic!("Task with this ID ({}) does already exist!", e.key());
             }
             Entry::Vacant(entry) => {
+                self.index.insert(&id, &task_meta);
+
                 entry.insert(task_meta);
             }
         }
@@ -70,26 +93,206 @@ impl Tasks {
         self.by_id.get(id)
     }
 
-    pub fn get_mut_by_id(&mut self, id: &Id) -> Option<&mut TaskMeta> {
-        self.by_id.get_mut(id)
+    pub fn get_by_prescription_id(&self, prescription_id: &PrescriptionId) -> Option<&TaskMeta> {
+        let id = self.index.by_prescription_id.get(prescription_id)?;
+
+        self.by_id.get(id)
+    }
+
+    /// Get a mutable reference to the task with the passed ID.
+    ///
+    /// The returned reference updates the task indices when it is dropped,
+    /// so the indexed values of the task may be changed freely.
+    pub fn get_mut_by_id(&mut self, id: &Id) -> Option<TaskMetaMut<'_>> {
+        let Self { by_id, index } = self;
+
+        let task_meta = by_id.get_mut(id)?;
+        index.remove(id, task_meta);
+
+        Some(TaskMetaMut {
+            id: id.clone(),
+            task_meta,
+            index,
+        })
+    }
+
+    pub fn remove_by_id(&mut self, id: &Id) {
+        let task_meta = self.by_id.remove(id).expect("Task not found!");
+
+        self.index.remove(id, &task_meta);
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &TaskMeta> {
         self.by_id.values()
     }
+
+    /// Iterate over the tasks of the patient with the passed KVNR.
+    pub fn iter_by_kvnr<'a>(&'a self, kvnr: &Kvnr) -> impl Iterator<Item = &'a TaskMeta> {
+        let ids = self.index.by_kvnr.get(kvnr);
+
+        self.iter_ids(ids)
+    }
+
+    /// Iterate over the tasks that were accepted by the pharmacy with the
+    /// passed telematik ID.
+    pub fn iter_by_telematik_id<'a>(
+        &'a self,
+        telematik_id: &TelematikId,
+    ) -> impl Iterator<Item = &'a TaskMeta> {
+        let ids = self.index.by_telematik_id.get(telematik_id);
+
+        self.iter_ids(ids)
+    }
+
+    fn iter_ids<'a>(&'a self, ids: Option<&'a HashSet<Id>>) -> impl Iterator<Item = &'a TaskMeta> {
+        let by_id = &self.by_id;
+
+        ids.into_iter()
+            .flatten()
+            .map(move |id| by_id.get(id).expect("Task index is inconsistent!"))
+    }
+
+    pub fn take_changes(&mut self) -> HashSet<Id> {
+        self.by_id.take_changes()
+    }
+}
+
+/// Mutable reference to a task that keeps the task indices up to date.
+///
+/// The task is removed from the indices when the reference is created and
+/// is inserted again when the reference is dropped.
+pub struct TaskMetaMut<'a> {
+    id: Id,
+    task_meta: &'a mut TaskMeta,
+    index: &'a mut TaskIndex,
+}
+
+impl Deref for TaskMetaMut<'_> {
+    type Target = TaskMeta;
+
+    fn deref(&self) -> &Self::Target {
+        self.task_meta
+    }
+}
+
+impl DerefMut for TaskMetaMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.task_meta
+    }
+}
+
+impl Drop for TaskMetaMut<'_> {
+    fn drop(&mut self) {
+        self.index.insert(&self.id, self.task_meta);
+    }
+}
+
+impl TaskIndex {
+    fn insert(&mut self, id: &Id, task_meta: &TaskMeta) {
+        let task = &task_meta.task;
+
+        if let Some(kvnr) = &task.for_ {
+            self.by_kvnr
+                .entry(kvnr.clone())
+                .or_default()
+                .insert(id.clone());
+        }
+
+        if let Some(telematik_id) = &task_meta.accepted_by {
+            self.by_telematik_id
+                .entry(telematik_id.clone())
+                .or_default()
+                .insert(id.clone());
+        }
+
+        if let Some(prescription_id) = &task.identifier.prescription_id {
+            self.by_prescription_id
+                .insert(prescription_id.clone(), id.clone());
+        }
+    }
+
+    fn remove(&mut self, id: &Id, task_meta: &TaskMeta) {
+        let task = &task_meta.task;
+
+        if let Some(kvnr) = &task.for_ {
+            remove_from_index(&mut self.by_kvnr, kvnr, id);
+        }
+
+        if let Some(telematik_id) = &task_meta.accepted_by {
+            remove_from_index(&mut self.by_telematik_id, telematik_id, id);
+        }
+
+        if let Some(prescription_id) = &task.identifier.prescription_id {
+            self.by_prescription_id.remove(prescription_id);
+        }
+    }
+}
+
+fn remove_from_index<K>(index: &mut HashMap<K, HashSet<Id>>, key: &K, id: &Id)
+where
+    K: Eq + Hash,
+{
+    if let Some(ids) = index.get_mut(key) {
+        ids.remove(id);
+
+        if ids.is_empty() {
+            index.remove(key);
+        }
+    }
 }
 
 pub struct TaskMeta {
     pub task: Task,
+    pub history: Vec<Task>,
     pub accept_timestamp: Option<DateTime<Utc>>,
+    pub accepted_by: Option<TelematikId>,
+    pub designated_performer: Option<TelematikId>,
     pub communication_count: usize,
 }
 
+impl TaskMeta {
+    /// Get the version of the task with the passed version ID.
+    pub fn version(&self, version_id: &Id) -> Option<&Task> {
+        self.versions()
+            .find(|task| task.version_id.as_ref() == Some(version_id))
+    }
+
+    /// Iterate over all versions of the task, starting with the current one.
+    pub fn versions(&self) -> impl Iterator<Item = &Task> {
+        once(&self.task).chain(self.history.iter().rev())
+    }
+
+    /// Store the current version of the task in the history and assign the
+    /// next version ID to the task.
+    fn new_version(&mut self) {
+        let mut task = self.task.clone();
+        if task.version_id.is_none() {
+            task.version_id = Some(version_id(self.history.len() + 1));
+        }
+
+        self.history.push(task);
+        self.task.version_id = Some(version_id(self.history.len() + 1));
+    }
+
+    /// Remove the personal data of the patient from all previous versions of
+    /// the task, so that the history stays available without them.
+    fn anonymize_history(&mut self) {
+        for task in &mut self.history {
+            task.for_ = None;
+            task.identifier.secret = None;
+            task.identifier.access_code = None;
+        }
+    }
+}
+
 impl From<Task> for TaskMeta {
     fn from(task: Task) -> Self {
         Self {
             task,
+            history: Vec::new(),
             accept_timestamp: None,
+            accepted_by: None,
+            designated_performer: None,
             communication_count: 0,
         }
     }
@@ -99,15 +302,22 @@ impl Inner {
     pub fn task_create(&mut self, args: TaskCreateParameters) -> Result<&Task, Error> {
         let id = Id::generate().unwrap();
         let flow_type = args.flow_type;
+        if flow_type_durations(flow_type).is_none() {
+            return Err(Error::UnsupportedFlowType(flow_type));
+        }
+
         let access_code = random_id();
         let prescription_id =
             PrescriptionId::generate(flow_type).map_err(|()| Error::GeneratePrescriptionId)?;
+        let now = self.clock.now();
 
         let task = Task {
             id: id.clone(),
+            version_id: Some(version_id(1)),
             extension: Extension {
                 accept_date: None,
                 expiry_date: None,
+                multi_prescription: None,
                 flow_type,
             },
             identifier: Identifier {
@@ -117,19 +327,16 @@ impl Inner {
             },
             status: Status::Draft,
             for_: None,
-            authored_on: Some(Utc::now().to_rfc3339().try_into().unwrap()),
-            last_modified: Some(Utc::now().to_rfc3339().try_into().unwrap()),
+            authored_on: Some(now.to_rfc3339().try_into().unwrap()),
+            last_modified: Some(now.to_rfc3339().try_into().unwrap()),
             performer_type: vec![PerformerType::PublicPharmacy],
             input: Default::default(),
             output: Default::default(),
         };
 
-        let task_meta = match self.tasks.by_id.entry(id) {
-            Entry::Occupied(e) => panic!("Task does already exists: {}", e.key()),
-            Entry::Vacant(e) => e.insert(task.into()),
-        };
+        self.tasks.insert_task(task);
 
-        Ok(&task_meta.task)
+        Ok(&self.tasks.get_by_id(&id).unwrap().task)
     }
 
     pub fn task_activate(
@@ -143,15 +350,16 @@ impl Inner {
     ) -> Result<&Task, Error> {
         let Self {
             ref mut tasks,
-            ref mut audit_events,
+            ref audit_events,
             ref mut e_prescriptions,
             ref mut patient_receipts,
-            ref mut timeouts,
+            ref timeouts,
+            ref calendar,
+            ref clock,
             ..
         } = self;
 
-        let timeouts = Rc::new(RefCell::new(timeouts));
-        Self::logged(audit_events, timeouts.clone(), move |event_builder| {
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
             let kvnr: Kvnr = match kbv_bundle
                 .entry
                 .patient
@@ -165,7 +373,7 @@ impl Inner {
                 None => return Err(Error::KvnrMissing),
             };
 
-            let task_meta = match tasks.get_mut_by_id(&id) {
+            let mut task_meta = match tasks.get_mut_by_id(&id) {
                 Some(task_meta) => task_meta,
                 None => return Err(Error::NotFound(id)),
             };
@@ -194,6 +402,31 @@ impl Inner {
                 return Err(Error::EPrescriptionAlreadyRegistered(kbv_bundle.id));
             }
 
+            validate_kbv_bundle(
+                &kbv_bundle,
+                task.identifier.prescription_id.as_ref(),
+                task.extension.flow_type,
+                signing_time,
+            )?;
+
+            let legal_basis = kbv_bundle
+                .entry
+                .composition
+                .as_ref()
+                .and_then(|(_, c)| c.extension.legal_basis.as_ref());
+            let multi_prescription = kbv_bundle
+                .entry
+                .medication_request
+                .as_ref()
+                .and_then(|(_, mr)| mr.extension.multi_prescription.clone());
+            let (accept_date, expiry_date) = accept_and_expiry_date(
+                calendar,
+                task.extension.flow_type,
+                signing_time,
+                legal_basis,
+                multi_prescription.as_ref(),
+            )?;
+
             /* create / update resources */
 
             let mut patient_receipt = kbv_bundle.clone();
@@ -204,42 +437,22 @@ impl Inner {
             let e_prescription_id = kbv_bundle.id.clone();
             e_prescriptions.insert(e_prescription_id.clone(), kbv_binary);
 
+            task_meta.new_version();
+
             let mut task = &mut task_meta.task;
             task.for_ = Some(kvnr);
             task.status = Status::Ready;
+            task.last_modified = Some(clock.now().into());
             task.input.e_prescription = Some(e_prescription_id);
             task.input.patient_receipt = Some(patient_receipt_id);
+            task.extension.accept_date = Some(accept_date.into());
+            task.extension.expiry_date = Some(expiry_date.into());
+            task.extension.multi_prescription = multi_prescription;
 
-            let (accept_duration, expiry_duration) = match task.extension.flow_type {
-                FlowType::ApothekenpflichtigeArzneimittel => {
-                    (Duration::days(30), Duration::days(92))
-                }
-                _ => unimplemented!(),
-            };
-
-            task.extension.accept_date = Some(signing_time.add(accept_duration).date().into());
-            task.extension.expiry_date = Some(signing_time.add(expiry_duration).date().into());
-
-            if let Some(LegalBasis::DischargeManagement) = kbv_bundle
-                .entry
-                .composition
-                .as_ref()
-                .and_then(|(_, c)| c.extension.legal_basis.as_ref())
-            {
-                let mut date = signing_time.date();
-
-                for _ in 0..3 {
-                    date = date.add(Duration::days(1));
-                    while is_holiday(&date) {
-                        date = date.add(Duration::days(1));
-                    }
-                }
-
-                task.extension.accept_date = Some(date.into());
-            }
+            drop(task_meta);
 
-            let task = &*task;
-            timeouts.borrow_mut().insert(task);
+            let task = &tasks.get_by_id(&id).unwrap().task;
+            timeouts.insert(task);
 
             Ok(task)
         })
@@ -249,23 +462,30 @@ impl Inner {
         &mut self,
         id: Id,
         access_code: XAccessCode,
+        if_match: Option<IfMatch>,
         agent: Agent,
     ) -> Result<(&Task, &KbvBinary), Error> {
         let Self {
             ref mut tasks,
-            ref mut audit_events,
+            ref audit_events,
             ref mut e_prescriptions,
-            ref mut timeouts,
+            ref timeouts,
+            ref clock,
             ..
         } = self;
 
-        let timeouts = Rc::new(RefCell::new(timeouts));
-        Self::logged(audit_events, timeouts.clone(), move |event_builder| {
-            let mut task_meta = match tasks.by_id.get_mut(&id) {
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
+            let mut task_meta = match tasks.get_mut_by_id(&id) {
                 Some(task_meta) => task_meta,
                 None => return Err(Error::NotFound(id)),
             };
 
+            let agent_telematik_id = agent
+                .who
+                .as_ref()
+                .and_then(ParticipantId::telematik_id)
+                .cloned();
+
             let task = &task_meta.task;
             event_builder.agent(agent);
             event_builder.action(Action::Update);
@@ -281,6 +501,8 @@ impl Inner {
                 None => return Err(Error::Gone(id)),
             }
 
+            check_if_match(task, &if_match)?;
+
             match task.status {
                 Status::Completed | Status::InProgress | Status::Draft => {
                     return Err(Error::Conflict(id))
@@ -289,11 +511,16 @@ impl Inner {
                 _ => (),
             }
 
-            task_meta.accept_timestamp = Some(Utc::now());
+            if let Some(start) = redeem_period_start(task) {
+                if local_date(clock.now()) < start {
+                    return Err(Error::RedeemPeriodNotStarted(start));
+                }
+            }
 
-            let mut task = &mut task_meta.task;
-            task.status = Status::InProgress;
-            task.identifier.secret = Some(random_id());
+            let is_direct = is_direct_assignment(task);
+            if is_direct && agent_telematik_id.is_none() {
+                return Err(Error::Forbidden(id));
+            }
 
             let e_prescription = task
                 .input
@@ -305,8 +532,30 @@ impl Inner {
                 .get_by_id(&e_prescription)
                 .ok_or(Error::EPrescriptionNotFound(e_prescription))?;
 
-            let task = &*task;
-            timeouts.borrow_mut().insert(task);
+            /* update task */
+
+            task_meta.new_version();
+
+            // Tasks of a direct assignment are bound to the pharmacy that
+            // accepted them, until the pharmacy rejects them again.
+            if is_direct {
+                task_meta.designated_performer = agent_telematik_id.clone();
+            }
+
+            let now = clock.now();
+
+            task_meta.accept_timestamp = Some(now);
+            task_meta.accepted_by = agent_telematik_id;
+
+            let mut task = &mut task_meta.task;
+            task.status = Status::InProgress;
+            task.last_modified = Some(now.into());
+            task.identifier.secret = Some(random_id());
+
+            drop(task_meta);
+
+            let task = &tasks.get_by_id(&id).unwrap().task;
+            timeouts.insert(task);
 
             Ok((task, e_prescription))
         })
@@ -316,18 +565,20 @@ impl Inner {
         &mut self,
         id: Id,
         secret: Option<String>,
+        if_match: Option<IfMatch>,
         agent: Agent,
     ) -> Result<(), Error> {
         let Self {
             ref mut tasks,
-            ref mut audit_events,
-            ref mut timeouts,
+            ref audit_events,
+            ref mut medication_dispenses,
+            ref timeouts,
+            ref clock,
             ..
         } = self;
 
-        let timeouts = Rc::new(RefCell::new(timeouts));
-        Self::logged(audit_events, timeouts.clone(), move |event_builder| {
-            let mut task_meta = match tasks.by_id.get_mut(&id) {
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
+            let mut task_meta = match tasks.get_mut_by_id(&id) {
                 Some(task_meta) => task_meta,
                 None => return Err(Error::NotFound(id)),
             };
@@ -345,39 +596,120 @@ impl Inner {
                 return Err(Error::Forbidden(id));
             }
 
+            check_if_match(task, &if_match)?;
+
+            /* the medication was not handed out by the rejecting pharmacy */
+            if let Some(prescription_id) = &task.identifier.prescription_id {
+                medication_dispenses.remove_by_prescription_id(prescription_id);
+            }
+
+            task_meta.new_version();
+
             let mut task = &mut task_meta.task;
             task.status = Status::Ready;
+            task.last_modified = Some(clock.now().into());
             task.identifier.secret = None;
 
-            timeouts.borrow_mut().insert(&*task);
+            timeouts.insert(&*task);
 
             task_meta.accept_timestamp = None;
+            task_meta.accepted_by = None;
+            task_meta.designated_performer = None;
 
             Ok(())
         })
     }
 
+    pub fn task_dispense(
+        &mut self,
+        id: Id,
+        secret: Option<String>,
+        performer: TelematikId,
+        mut medication_dispense_list: Vec<MedicationDispense>,
+        if_match: Option<IfMatch>,
+        agent: Agent,
+    ) -> Result<Vec<&MedicationDispense>, Error> {
+        let Self {
+            ref tasks,
+            ref audit_events,
+            ref mut medication_dispenses,
+            ref timeouts,
+            ref clock,
+            ..
+        } = self;
+
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
+            let task_meta = match tasks.by_id.get(&id) {
+                Some(task_meta) => task_meta,
+                None => return Err(Error::NotFound(id)),
+            };
+
+            let task = &task_meta.task;
+            event_builder.agent(agent);
+            event_builder.action(Action::Update);
+            event_builder.sub_type(SubType::Update);
+            event_builder.what(What::Task(id.clone()));
+            event_builder.patient_opt(task.for_.clone());
+            event_builder.description_opt(task.identifier.prescription_id.clone());
+            event_builder.text(Text::TaskDispense);
+
+            /* check the preconditions */
+
+            check_medication_dispenses(
+                task_meta,
+                &secret,
+                &performer,
+                &mut medication_dispense_list,
+            )?;
+            check_if_match(task, &if_match)?;
+
+            if medication_dispense_list.is_empty() {
+                return Err(Error::MedicationDispenseMissing);
+            }
+
+            /* replace the medication dispenses of a previous dispense */
+
+            let now = clock.now();
+            let prescription_id = task.identifier.prescription_id.as_ref().unwrap();
+            medication_dispenses.remove_by_prescription_id(prescription_id);
+
+            for mut medication_dispense in medication_dispense_list {
+                medication_dispense.last_updated = Some(now.into());
+
+                timeouts.insert(&medication_dispense);
+
+                medication_dispenses.insert(medication_dispense);
+            }
+
+            Ok(medication_dispenses
+                .iter_by_prescription_id(prescription_id)
+                .collect())
+        })
+    }
+
     pub fn task_close(
         &mut self,
         id: Id,
         secret: Option<String>,
         performer: TelematikId,
-        mut medication_dispense: MedicationDispense,
+        mut medication_dispense_list: Vec<MedicationDispense>,
+        if_match: Option<IfMatch>,
         agent: Agent,
     ) -> Result<&ErxBundle, Error> {
         let Self {
             ref mut tasks,
             ref mut erx_receipts,
             ref mut communications,
-            ref mut audit_events,
+            ref mut binaries,
+            ref audit_events,
             ref mut medication_dispenses,
-            ref mut timeouts,
+            ref timeouts,
+            ref clock,
             ..
         } = self;
 
-        let timeouts = Rc::new(RefCell::new(timeouts));
-        Self::logged(audit_events, timeouts.clone(), move |event_builder| {
-            let task_meta = match tasks.by_id.get_mut(&id) {
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
+            let mut task_meta = match tasks.get_mut_by_id(&id) {
                 Some(task_meta) => task_meta,
                 None => return Err(Error::NotFound(id)),
             };
@@ -393,45 +725,50 @@ impl Inner {
 
             /* check the preconditions */
 
-            if task.status != Status::InProgress || task.identifier.secret != secret {
-                return Err(Error::Forbidden(id));
-            }
-
-            let prescription_id = task
-                .identifier
-                .prescription_id
-                .as_ref()
-                .ok_or(Error::EPrescriptionMissing)?;
-            if &medication_dispense.prescription_id != prescription_id {
-                return Err(Error::EPrescriptionMismatch);
-            }
-
-            let subject = task.for_.as_ref().ok_or(Error::SubjectMissing)?;
-            if &medication_dispense.subject != subject {
-                return Err(Error::SubjectMismatch);
-            }
+            check_medication_dispenses(
+                &task_meta,
+                &secret,
+                &performer,
+                &mut medication_dispense_list,
+            )?;
+            check_if_match(task, &if_match)?;
+
+            let accept_timestamp = task_meta
+                .accept_timestamp
+                .ok_or(Error::AcceptTimestampMissing)?;
+            let prescription_id = task.identifier.prescription_id.clone().unwrap();
+
+            // New medication dispenses replace the ones of a previous
+            // dispense, otherwise the stored ones are finalized.
+            if medication_dispense_list.is_empty() {
+                let mut stored = medication_dispenses
+                    .iter_by_prescription_id(&prescription_id)
+                    .peekable();
+                if stored.peek().is_none() {
+                    return Err(Error::MedicationDispenseMissing);
+                }
 
-            if medication_dispense.performer != performer {
-                return Err(Error::PerformerMismatch);
+                if !stored.all(|md| md.performer == performer) {
+                    return Err(Error::PerformerMismatch);
+                }
             }
 
             /* create erx bundle */
 
-            let now = Utc::now();
+            // The receipt does not reference the medication dispenses, they
+            // are linked to the task by their supporting information.
+            let now = clock.now();
             let erx_bundle = ErxBundle {
                 id: Id::generate().unwrap(),
                 identifier: prescription_id.clone(),
-                timestamp: Utc::now().into(),
+                timestamp: now.into(),
                 entry: ErxEntry {
                     composition: Some(ErxComposition {
                         id: Id::generate().unwrap(),
                         beneficiary: performer,
                         date: now.clone().into(),
                         author: DEVICE.id.clone().into(),
-                        event_start: task_meta
-                            .accept_timestamp
-                            .ok_or(Error::AcceptTimestampMissing)?
-                            .into(),
+                        event_start: accept_timestamp.into(),
                         event_end: now.into(),
                     }),
                     device: Some(DEVICE.clone()),
@@ -439,26 +776,35 @@ impl Inner {
                 signature: vec![],
             };
 
-            medication_dispense.id = Some(Id::generate().unwrap());
-            medication_dispense.supporting_information = vec![format!("/Task/{}", id)];
-
             /* add new resources to state */
 
-            timeouts.borrow_mut().insert(&medication_dispense);
-
-            medication_dispenses.insert(medication_dispense);
             let erx_bundle = erx_receipts.insert_erx_bundle(erx_bundle)?;
 
+            if !medication_dispense_list.is_empty() {
+                medication_dispenses.remove_by_prescription_id(&prescription_id);
+
+                for mut medication_dispense in medication_dispense_list {
+                    medication_dispense.last_updated = Some(now.into());
+
+                    timeouts.insert(&medication_dispense);
+
+                    medication_dispenses.insert(medication_dispense);
+                }
+            }
+
             /* update task */
 
+            task_meta.new_version();
+
             let task = &mut task_meta.task;
             task.status = Status::Completed;
+            task.last_modified = Some(now.into());
             task.output.receipt = Some(erx_bundle.id.clone());
 
-            timeouts.borrow_mut().insert(&*task);
+            timeouts.insert(&*task);
 
             /* remove communications associated to this task */
-            communications.remove_by_task_id(&id);
+            communications.remove_by_task_id(&id, binaries);
 
             Ok(&**erx_bundle)
         })
@@ -470,6 +816,7 @@ impl Inner {
         access_token: &AccessToken,
         mut access_code: Option<XAccessCode>,
         secret: Option<String>,
+        if_match: Option<IfMatch>,
         agent: Agent,
     ) -> Result<(), Error> {
         let Self {
@@ -477,15 +824,16 @@ impl Inner {
             ref mut erx_receipts,
             ref mut e_prescriptions,
             ref mut patient_receipts,
-            ref mut audit_events,
+            ref audit_events,
             ref mut medication_dispenses,
-            ref mut timeouts,
+            ref mut charge_items,
+            ref timeouts,
+            ref clock,
             ..
         } = self;
 
-        let timeouts = Rc::new(RefCell::new(timeouts));
-        Self::logged(audit_events, timeouts.clone(), move |event_builder| {
-            let task_meta = match tasks.by_id.get_mut(&id) {
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
+            let mut task_meta = match tasks.get_mut_by_id(&id) {
                 Some(task_meta) => task_meta,
                 None => return Err(Error::NotFound(id)),
             };
@@ -511,6 +859,10 @@ impl Inner {
                 Text::TaskAbortPatient
             });
 
+            if is_patient && is_direct_assignment(task) {
+                return Err(Error::Forbidden(id));
+            }
+
             if is_patient {
                 access_code = None;
             }
@@ -531,12 +883,19 @@ impl Inner {
                 return Err(Error::Forbidden(id));
             }
 
+            check_if_match(task, &if_match)?;
+
+            task_meta.new_version();
+
+            task_meta.accepted_by = None;
+            task_meta.anonymize_history();
+
             let mut task = &mut task_meta.task;
             task.for_ = None;
             task.status = Status::Cancelled;
             task.identifier.secret = None;
             task.identifier.access_code = None;
-            task.last_modified = Some(Utc::now().into());
+            task.last_modified = Some(clock.now().into());
 
             let prescription_id = task
                 .identifier
@@ -545,6 +904,7 @@ impl Inner {
                 .ok_or(Error::EPrescriptionMissing)?;
 
             medication_dispenses.remove_by_prescription_id(prescription_id);
+            charge_items.remove_by_prescription_id(prescription_id);
 
             if let Some(e_prescription) = task.input.e_prescription.take() {
                 e_prescriptions.remove_by_id(&e_prescription);
@@ -558,29 +918,41 @@ impl Inner {
                 erx_receipts.remove_by_id(&receipt);
             }
 
-            timeouts.borrow_mut().insert(&*task);
+            timeouts.insert(&*task);
 
             Ok(())
         })
     }
 
     pub fn task_get(
-        &mut self,
+        &self,
         id: Id,
         kvnr: Option<Kvnr>,
         access_code: Option<XAccessCode>,
         secret: Option<String>,
         agent: Agent,
-    ) -> Result<(&Self, &Task), Error> {
+    ) -> Result<&Task, Error> {
+        self.task_get_meta(id, kvnr, access_code, secret, agent)
+            .map(|task_meta| &task_meta.task)
+    }
+
+    pub fn task_get_meta(
+        &self,
+        id: Id,
+        kvnr: Option<Kvnr>,
+        access_code: Option<XAccessCode>,
+        secret: Option<String>,
+        agent: Agent,
+    ) -> Result<&TaskMeta, Error> {
         let Self {
             ref tasks,
-            ref mut timeouts,
-            ref mut audit_events,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
             ..
         } = self;
 
-        let timeouts = Rc::new(RefCell::new(timeouts));
-        let ret = Self::logged(audit_events, timeouts.clone(), move |event_builder| {
+        Self::logged(audit_events, timeouts, clock, move |event_builder| {
             let task_meta = match tasks.by_id.get(&id) {
                 Some(task_meta) => task_meta,
                 None => return Err(Error::NotFound(id)),
@@ -601,21 +973,24 @@ impl Inner {
                 Text::TaskGetOnePatient
             });
 
+            // The access code of a direct assignment is only known by the
+            // prescriber and the designated performer.
+            let access_code = if is_direct_assignment(task) {
+                None
+            } else {
+                access_code
+            };
+
             if !Self::task_matches(&task, &kvnr, &access_code, &secret) {
                 return Err(Error::Forbidden(id));
             }
 
-            Ok(task)
-        });
-
-        match ret {
-            Ok(ref task) => Ok((&*self, task)),
-            Err(err) => Err(err),
-        }
+            Ok(task_meta)
+        })
     }
 
     pub fn task_iter<F>(
-        &mut self,
+        &self,
         kvnr: Option<Kvnr>,
         access_code: Option<XAccessCode>,
         agent: Agent,
@@ -626,8 +1001,9 @@ impl Inner {
     {
         let Self {
             ref tasks,
-            ref mut timeouts,
-            ref mut audit_events,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
             ..
         } = self;
 
@@ -638,12 +1014,25 @@ impl Inner {
         event_builder.what(What::Tasks);
         event_builder.patient_opt(kvnr.clone());
         event_builder.text(Text::TaskGetManyPatient);
-        event_builder.build(audit_events, timeouts, None);
+        event_builder.build(audit_events, timeouts, clock, None);
+
+        // Without an access code only the tasks of the patient may match, so
+        // the index is used instead of scanning all tasks.
+        let task_metas: Box<dyn Iterator<Item = &TaskMeta> + '_> = match (&kvnr, &access_code) {
+            (Some(kvnr), None) => Box::new(tasks.iter_by_kvnr(kvnr)),
+            (_, _) => Box::new(tasks.iter()),
+        };
 
-        tasks.by_id.iter().filter_map(move |(_, task_meta)| {
+        task_metas.filter_map(move |task_meta| {
             let task = &task_meta.task;
 
-            if !Self::task_matches(&task, &kvnr, &access_code, &None) {
+            let is_match = if is_direct_assignment(task) {
+                Self::task_matches(&task, &kvnr, &None, &None)
+            } else {
+                Self::task_matches(&task, &kvnr, &access_code, &None)
+            };
+
+            if !is_match {
                 return None;
             }
 
@@ -655,6 +1044,44 @@ impl Inner {
         })
     }
 
+    pub fn task_iter_by_pharmacy<'a, F>(
+        &'a self,
+        telematik_id: &TelematikId,
+        agent: Agent,
+        mut f: F,
+    ) -> impl Iterator<Item = &'a Task>
+    where
+        F: FnMut(&Task) -> bool,
+    {
+        let Self {
+            ref tasks,
+            ref timeouts,
+            ref audit_events,
+            ref clock,
+            ..
+        } = self;
+
+        let mut event_builder = Self::audit_event_builder();
+        event_builder.agent(agent);
+        event_builder.action(Action::Read);
+        event_builder.sub_type(SubType::Read);
+        event_builder.what(What::Tasks);
+        event_builder.text(Text::TaskGetManyPharmacy);
+        event_builder.build(audit_events, timeouts, clock, None);
+
+        tasks
+            .iter_by_telematik_id(telematik_id)
+            .filter_map(move |task_meta| {
+                let task = &task_meta.task;
+
+                if task.status == Status::InProgress && f(task) {
+                    Some(task)
+                } else {
+                    None
+                }
+            })
+    }
+
     pub fn task_delete_by_id(&mut self, id: &Id) {
         let Self {
             ref mut tasks,
@@ -662,6 +1089,9 @@ impl Inner {
             ref mut e_prescriptions,
             ref mut patient_receipts,
             ref mut medication_dispenses,
+            ref mut charge_items,
+            ref mut communications,
+            ref mut binaries,
             ..
         } = self;
 
@@ -670,6 +1100,7 @@ impl Inner {
 
         if let Some(prescription_id) = &task.identifier.prescription_id {
             medication_dispenses.remove_by_prescription_id(prescription_id);
+            charge_items.remove_by_prescription_id(prescription_id);
         }
 
         if let Some(e_prescription) = &task.input.e_prescription {
@@ -684,7 +1115,9 @@ impl Inner {
             erx_receipts.remove_by_id(&receipt);
         }
 
-        tasks.by_id.remove(id);
+        communications.remove_by_task_id(id, binaries);
+
+        tasks.remove_by_id(id);
     }
 
     pub fn task_matches(
@@ -712,6 +1145,70 @@ impl Inner {
     }
 }
 
+/// Returns an error if the task was changed since the client received the
+/// version referenced by the `If-Match` header.
+fn check_if_match(task: &Task, if_match: &Option<IfMatch>) -> Result<(), Error> {
+    match if_match {
+        Some(if_match) if !if_match.matches(&task_etag(task)) => {
+            Err(Error::PreconditionFailed(task.id.clone()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check the preconditions that are shared by `$dispense` and `$close` and
+/// prepare the passed medication dispenses to be stored.
+///
+/// The task has to be in progress, the secret has to match and the
+/// medication dispenses have to belong to the prescription and the patient
+/// of the task and to the pharmacy that accepted it.
+fn check_medication_dispenses(
+    task_meta: &TaskMeta,
+    secret: &Option<String>,
+    performer: &TelematikId,
+    medication_dispense_list: &mut [MedicationDispense],
+) -> Result<(), Error> {
+    let task = &task_meta.task;
+    if task.status != Status::InProgress || &task.identifier.secret != secret {
+        return Err(Error::Forbidden(task.id.clone()));
+    }
+
+    let prescription_id = task
+        .identifier
+        .prescription_id
+        .as_ref()
+        .ok_or(Error::EPrescriptionMissing)?;
+    let subject = task.for_.as_ref().ok_or(Error::SubjectMissing)?;
+    for medication_dispense in medication_dispense_list.iter() {
+        if &medication_dispense.prescription_id != prescription_id {
+            return Err(Error::EPrescriptionMismatch);
+        }
+
+        if &medication_dispense.subject != subject {
+            return Err(Error::SubjectMismatch);
+        }
+
+        if &medication_dispense.performer != performer {
+            return Err(Error::PerformerMismatch);
+        }
+    }
+
+    if is_direct_assignment(task) && task_meta.designated_performer.as_ref() != Some(performer) {
+        return Err(Error::PerformerMismatch);
+    }
+
+    for medication_dispense in medication_dispense_list.iter_mut() {
+        medication_dispense.id = Some(Id::generate().unwrap());
+        medication_dispense.supporting_information = vec![format!("/Task/{}", task.id)];
+    }
+
+    Ok(())
+}
+
+fn version_id(version: usize) -> Id {
+    Id::try_from(version.to_string()).unwrap()
+}
+
 fn random_id() -> String {
     OsRng
         .sample_iter(&Standard)
@@ -721,22 +1218,661 @@ fn random_id() -> String {
         .join("")
 }
 
-fn is_holiday(date: &Date<Utc>) -> bool {
-    let easter_sunday = easter_naive_date(date.year()).unwrap();
-    let easter_sunday = Date::<Utc>::from_utc(easter_sunday, Utc);
-    let easter_monday = easter_sunday + Duration::days(1);
-    let easter_friday = easter_sunday - Duration::days(2);
-    let whitsun_monday = easter_sunday + Duration::days(50);
-    let ascension = easter_sunday + Duration::days(39);
-
-    date.weekday() == Weekday::Sun
-    || (date.day() == 1 && date.month() == 1) // 01.01.
-    || (date.day() == 1 && date.month() == 5) // 01.05
-    || (date.day() == 3 && date.month() == 10) // 03.10
-    || (date.day() == 25 && date.month() == 12) // 25.12
-    || (date.day() == 26 && date.month() == 12) // 26.12
-    || date == &easter_friday
-    || date == &easter_monday
-    || date == &whitsun_monday
-    || date == &ascension
+/// Returns the first day the task can be redeemed at, if the task is part of
+/// a multiple prescription.
+fn redeem_period_start(task: &Task) -> Option<NaiveDate> {
+    let multi_prescription = task.extension.multi_prescription.as_ref()?;
+    let start = multi_prescription.time_range.start.as_ref()?;
+
+    parse_date(start)
+}
+
+/// Durations (accept, expiry) of the prescriptions of the given flow type,
+/// or `None` if the flow type is not supported by the service.
+fn flow_type_durations(flow_type: FlowType) -> Option<(Duration, Duration)> {
+    match flow_type {
+        FlowType::ApothekenpflichtigeArzneimittel | FlowType::DirekteZuweisung => {
+            Some((Duration::days(30), Duration::days(92)))
+        }
+        FlowType::ApothekenpflichtigeArzneimittelPkv | FlowType::DirekteZuweisungPkv => {
+            Some((Duration::days(92), Duration::days(92)))
+        }
+        FlowType::Sanitaetsbedarf
+        | FlowType::Heilmittel
+        | FlowType::Hilfsmittel
+        | FlowType::Sprechstundenbedarf
+        | FlowType::Betaeubungsmittel
+        | FlowType::TRezepte => None,
+    }
+}
+
+/// Calculate the accept and the expiry date of a prescription that was
+/// signed at the given time. The dates are counted from the german date of
+/// the signing time.
+///
+/// Prescriptions of the discharge management can only be redeemed within
+/// three working days, so the holidays of the calendar are skipped.
+///
+/// Prescriptions that are part of a multiple prescription can be redeemed
+/// until the end of their redeem period, or one year after they were signed
+/// if the period has no end.
+fn accept_and_expiry_date(
+    calendar: &Calendar,
+    flow_type: FlowType,
+    signing_time: DateTime<Utc>,
+    legal_basis: Option<&LegalBasis>,
+    multi_prescription: Option<&MultiPrescription>,
+) -> Result<(Date<Utc>, Date<Utc>), Error> {
+    let (accept_duration, expiry_duration) =
+        flow_type_durations(flow_type).ok_or(Error::UnsupportedFlowType(flow_type))?;
+
+    let signing_date = local_date(signing_time);
+
+    if let Some(multi_prescription) = multi_prescription {
+        let end_date = multi_prescription
+            .time_range
+            .end
+            .as_ref()
+            .and_then(|end| parse_date(end))
+            .unwrap_or_else(|| signing_date + Duration::days(365));
+        let end_date = Date::from_utc(end_date, Utc);
+
+        return Ok((end_date, end_date));
+    }
+
+    let mut accept_date = signing_date + accept_duration;
+    let expiry_date = signing_date + expiry_duration;
+
+    if let Some(LegalBasis::DischargeManagement) = legal_basis {
+        accept_date = calendar.add_working_days(signing_date, 3);
+    }
+
+    Ok((
+        Date::from_utc(accept_date, Utc),
+        Date::from_utc(expiry_date, Utc),
+    ))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use actix_web::http::header::{EntityTag, IfMatch as IfMatchHeader};
+    use chrono::TimeZone;
+    use resources::medication_request::{SeriesElement, TimeRange};
+
+    use crate::service::misc::{access_token::tests::access_token, Profession};
+    use crate::state::{tests::state, Clock, FederalState};
+
+    #[tokio::test]
+    async fn task_timestamps_with_fixed_clock() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let now = Utc.ymd(2021, 5, 31).and_hms(10, 0, 0);
+        state.set_clock(Clock::Fixed(now));
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let pharmacy = access_token(Profession::OeffentlicheApotheke, "606358757");
+
+        let (id, access_code) =
+            create_ready_task(&mut state, FlowType::ApothekenpflichtigeArzneimittel, &kvnr);
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code),
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        assert_eq!(
+            DateTime::<Utc>::from(task.authored_on.clone().unwrap()),
+            now
+        );
+        assert_eq!(
+            DateTime::<Utc>::from(task.last_modified.clone().unwrap()),
+            now
+        );
+        assert_eq!(
+            state.tasks.get_by_id(&id).unwrap().accept_timestamp,
+            Some(now)
+        );
+
+        let events = state.audit_event_iter_by_task(&id).collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(*events[0].recorded, now);
+
+        // The accepted task expires after the default retention period.
+        state.set_clock(Clock::Fixed(now + Duration::days(99)));
+        state.progress_timeouts();
+        assert!(state.tasks.get_by_id(&id).is_some());
+
+        state.set_clock(Clock::Fixed(now + Duration::days(100)));
+        state.progress_timeouts();
+        assert!(state.tasks.get_by_id(&id).is_none());
+    }
+
+    #[test]
+    fn accept_date_of_discharge_management() {
+        let signing_time = Utc.ymd(2021, 6, 1).and_hms(10, 0, 0);
+
+        let (accept_date, _) = accept_and_expiry_date(
+            &Calendar::default(),
+            FlowType::ApothekenpflichtigeArzneimittel,
+            signing_time,
+            Some(&LegalBasis::DischargeManagement),
+            None,
+        )
+        .unwrap();
+        assert_eq!(accept_date, Utc.ymd(2021, 6, 4));
+
+        let (accept_date, _) = accept_and_expiry_date(
+            &Calendar::new(Some(FederalState::Bayern)),
+            FlowType::ApothekenpflichtigeArzneimittel,
+            signing_time,
+            Some(&LegalBasis::DischargeManagement),
+            None,
+        )
+        .unwrap();
+        assert_eq!(accept_date, Utc.ymd(2021, 6, 5));
+    }
+
+    #[test]
+    fn accept_and_expiry_date_of_all_flow_types() {
+        let signing_time = Utc.ymd(2021, 6, 1).and_hms(10, 0, 0);
+        let calendar = Calendar::default();
+
+        for flow_type in &[
+            FlowType::ApothekenpflichtigeArzneimittel,
+            FlowType::DirekteZuweisung,
+        ] {
+            let (accept_date, expiry_date) =
+                accept_and_expiry_date(&calendar, *flow_type, signing_time, None, None).unwrap();
+
+            assert_eq!(accept_date, Utc.ymd(2021, 7, 1));
+            assert_eq!(expiry_date, Utc.ymd(2021, 9, 1));
+        }
+
+        for flow_type in &[
+            FlowType::ApothekenpflichtigeArzneimittelPkv,
+            FlowType::DirekteZuweisungPkv,
+        ] {
+            let (accept_date, expiry_date) =
+                accept_and_expiry_date(&calendar, *flow_type, signing_time, None, None).unwrap();
+
+            assert_eq!(accept_date, Utc.ymd(2021, 9, 1));
+            assert_eq!(expiry_date, Utc.ymd(2021, 9, 1));
+        }
+
+        for flow_type in &[
+            FlowType::Sanitaetsbedarf,
+            FlowType::Heilmittel,
+            FlowType::Hilfsmittel,
+            FlowType::Sprechstundenbedarf,
+            FlowType::Betaeubungsmittel,
+            FlowType::TRezepte,
+        ] {
+            let res = accept_and_expiry_date(&calendar, *flow_type, signing_time, None, None);
+
+            assert!(matches!(res, Err(Error::UnsupportedFlowType(f)) if f == *flow_type));
+        }
+    }
+
+    #[test]
+    fn accept_and_expiry_date_of_german_date() {
+        // 2021-06-01 22:30 UTC is already the 2nd of June in Germany
+        let signing_time = Utc.ymd(2021, 6, 1).and_hms(22, 30, 0);
+
+        let (accept_date, expiry_date) = accept_and_expiry_date(
+            &Calendar::default(),
+            FlowType::ApothekenpflichtigeArzneimittel,
+            signing_time,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(accept_date, Utc.ymd(2021, 7, 2));
+        assert_eq!(expiry_date, Utc.ymd(2021, 9, 2));
+
+        let (accept_date, _) = accept_and_expiry_date(
+            &Calendar::default(),
+            FlowType::ApothekenpflichtigeArzneimittel,
+            signing_time,
+            Some(&LegalBasis::DischargeManagement),
+            None,
+        )
+        .unwrap();
+        assert_eq!(accept_date, Utc.ymd(2021, 6, 5));
+    }
+
+    #[test]
+    fn task_versions() {
+        let task = Task {
+            id: Id::generate().unwrap(),
+            version_id: None,
+            extension: Extension {
+                flow_type: FlowType::ApothekenpflichtigeArzneimittel,
+                accept_date: None,
+                expiry_date: None,
+                multi_prescription: None,
+            },
+            identifier: Default::default(),
+            status: Status::Ready,
+            for_: Some(Kvnr::new("X123456789").unwrap()),
+            authored_on: None,
+            last_modified: None,
+            performer_type: vec![PerformerType::PublicPharmacy],
+            input: Default::default(),
+            output: Default::default(),
+        };
+
+        let mut task_meta = TaskMeta::from(task);
+        task_meta.new_version();
+        task_meta.task.status = Status::InProgress;
+        task_meta.new_version();
+        task_meta.task.status = Status::Completed;
+
+        let versions = task_meta
+            .versions()
+            .map(|task| (task.version_id.clone().unwrap(), task.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            versions,
+            vec![
+                (version_id(3), Status::Completed),
+                (version_id(2), Status::InProgress),
+                (version_id(1), Status::Ready),
+            ]
+        );
+
+        assert_eq!(
+            task_meta.version(&version_id(2)).unwrap().status,
+            Status::InProgress
+        );
+        assert!(task_meta.version(&version_id(4)).is_none());
+
+        task_meta.anonymize_history();
+        assert!(task_meta.history.iter().all(|task| task.for_.is_none()));
+        assert!(task_meta.task.for_.is_some());
+    }
+
+    #[test]
+    fn accept_and_expiry_date_of_multi_prescription() {
+        let signing_time = Utc.ymd(2021, 6, 1).and_hms(10, 0, 0);
+        let calendar = Calendar::default();
+
+        let mut multi_prescription = MultiPrescription {
+            series_element: SeriesElement {
+                numerator: 2,
+                denominator: 4,
+            },
+            time_range: TimeRange {
+                start: Some("2021-09-01".try_into().unwrap()),
+                end: Some("2021-12-31".try_into().unwrap()),
+            },
+        };
+
+        let (accept_date, expiry_date) = accept_and_expiry_date(
+            &calendar,
+            FlowType::ApothekenpflichtigeArzneimittel,
+            signing_time,
+            None,
+            Some(&multi_prescription),
+        )
+        .unwrap();
+        assert_eq!(accept_date, Utc.ymd(2021, 12, 31));
+        assert_eq!(expiry_date, Utc.ymd(2021, 12, 31));
+
+        multi_prescription.time_range.end = None;
+
+        let (accept_date, expiry_date) = accept_and_expiry_date(
+            &calendar,
+            FlowType::ApothekenpflichtigeArzneimittel,
+            signing_time,
+            None,
+            Some(&multi_prescription),
+        )
+        .unwrap();
+        assert_eq!(accept_date, Utc.ymd(2022, 6, 1));
+        assert_eq!(expiry_date, Utc.ymd(2022, 6, 1));
+    }
+
+    #[tokio::test]
+    async fn task_index_consistency() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let telematik_id = TelematikId::new("606358757");
+        let patient = access_token(Profession::Versicherter, "X123456789");
+        let other_patient = access_token(Profession::Versicherter, "X234567890");
+        let pharmacy = access_token(Profession::OeffentlicheApotheke, "606358757");
+
+        // create
+        let (id, access_code) =
+            create_ready_task(&mut state, FlowType::ApothekenpflichtigeArzneimittel, &kvnr);
+        let prescription_id = state
+            .tasks
+            .get_by_id(&id)
+            .unwrap()
+            .task
+            .identifier
+            .prescription_id
+            .clone()
+            .unwrap();
+        assert_indexed(
+            &state.tasks,
+            &id,
+            &prescription_id,
+            &kvnr,
+            &telematik_id,
+            true,
+            false,
+        );
+
+        // accept
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code),
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+        assert_indexed(
+            &state.tasks,
+            &id,
+            &prescription_id,
+            &kvnr,
+            &telematik_id,
+            true,
+            true,
+        );
+
+        // reject
+        state
+            .task_reject(id.clone(), secret, None, (&pharmacy).into())
+            .unwrap();
+        assert_indexed(
+            &state.tasks,
+            &id,
+            &prescription_id,
+            &kvnr,
+            &telematik_id,
+            true,
+            false,
+        );
+
+        // failed abort
+        let res = state.task_abort(
+            id.clone(),
+            &other_patient,
+            None,
+            None,
+            None,
+            (&other_patient).into(),
+        );
+        assert!(matches!(res, Err(Error::Forbidden(_))));
+        assert_indexed(
+            &state.tasks,
+            &id,
+            &prescription_id,
+            &kvnr,
+            &telematik_id,
+            true,
+            false,
+        );
+
+        // abort
+        state
+            .task_abort(id.clone(), &patient, None, None, None, (&patient).into())
+            .unwrap();
+        assert_indexed(
+            &state.tasks,
+            &id,
+            &prescription_id,
+            &kvnr,
+            &telematik_id,
+            false,
+            false,
+        );
+
+        // delete
+        state.task_delete_by_id(&id);
+        assert!(state.tasks.get_by_id(&id).is_none());
+        assert!(state
+            .tasks
+            .get_by_prescription_id(&prescription_id)
+            .is_none());
+        assert_eq!(state.tasks.iter_by_kvnr(&kvnr).count(), 0);
+        assert_eq!(state.tasks.iter_by_telematik_id(&telematik_id).count(), 0);
+    }
+
+    #[tokio::test]
+    async fn failed_accept_does_not_modify_the_task() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let telematik_id = TelematikId::new("606358757");
+        let pharmacy = access_token(Profession::OeffentlicheApotheke, "606358757");
+
+        let (id, access_code) =
+            create_ready_task(&mut state, FlowType::ApothekenpflichtigeArzneimittel, &kvnr);
+        let task = state.tasks.get_by_id(&id).unwrap().task.clone();
+        let e_prescription = task.input.e_prescription.clone().unwrap();
+        state.e_prescriptions.remove_by_id(&e_prescription);
+
+        let res = state.task_accept(
+            id.clone(),
+            XAccessCode(access_code),
+            None,
+            (&pharmacy).into(),
+        );
+        assert!(matches!(res, Err(Error::EPrescriptionNotFound(_))));
+
+        let task_meta = state.tasks.get_by_id(&id).unwrap();
+        assert_eq!(task_meta.task, task);
+        assert!(task_meta.history.is_empty());
+        assert_eq!(task_meta.accept_timestamp, None);
+        assert_eq!(task_meta.accepted_by, None);
+        assert_eq!(state.tasks.iter_by_telematik_id(&telematik_id).count(), 0);
+    }
+
+    #[tokio::test]
+    async fn task_history_after_abort() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let pharmacy = access_token(Profession::OeffentlicheApotheke, "606358757");
+
+        let (id, access_code) =
+            create_ready_task(&mut state, FlowType::ApothekenpflichtigeArzneimittel, &kvnr);
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code),
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+        let prescription_id = task.identifier.prescription_id.clone();
+
+        state
+            .task_abort(
+                id.clone(),
+                &pharmacy,
+                None,
+                secret,
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+
+        // The versions of the aborted task are still available, but without
+        // the personal data of the patient.
+        let task_meta = state.tasks.get_by_id(&id).unwrap();
+        let versions = task_meta
+            .versions()
+            .map(|task| (task.version_id.clone().unwrap(), task.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            versions,
+            vec![
+                (version_id(3), Status::Cancelled),
+                (version_id(2), Status::InProgress),
+                (version_id(1), Status::Ready),
+            ]
+        );
+
+        for task in task_meta.versions() {
+            assert_eq!(task.for_, None);
+            assert_eq!(task.identifier.secret, None);
+            assert_eq!(task.identifier.access_code, None);
+            assert_eq!(task.identifier.prescription_id, prescription_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn if_match_is_checked_after_authorization() {
+        let state = state();
+        let mut state = state.lock().await;
+
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let pharmacy = access_token(Profession::OeffentlicheApotheke, "606358757");
+
+        let (id, access_code) =
+            create_ready_task(&mut state, FlowType::ApothekenpflichtigeArzneimittel, &kvnr);
+        let (task, _) = state
+            .task_accept(
+                id.clone(),
+                XAccessCode(access_code),
+                None,
+                (&pharmacy).into(),
+            )
+            .unwrap();
+        let secret = task.identifier.secret.clone();
+        let etag = task_etag(task);
+
+        let outdated = EntityTag::weak("1".into());
+
+        let res = state.task_reject(
+            id.clone(),
+            None,
+            if_match(outdated.clone()),
+            (&pharmacy).into(),
+        );
+        assert!(matches!(res, Err(Error::Forbidden(_))));
+
+        let res = state.task_reject(
+            id.clone(),
+            secret.clone(),
+            if_match(outdated),
+            (&pharmacy).into(),
+        );
+        assert!(matches!(res, Err(Error::PreconditionFailed(_))));
+
+        state
+            .task_reject(id, secret, if_match(etag), (&pharmacy).into())
+            .unwrap();
+    }
+
+    #[test]
+    fn task_index_of_changed_task() {
+        let mut tasks = Tasks::default();
+        let kvnr = Kvnr::new("X123456789").unwrap();
+        let other_kvnr = Kvnr::new("X234567890").unwrap();
+        let telematik_id = TelematikId::new("606358757");
+
+        let task = Task {
+            id: Id::generate().unwrap(),
+            version_id: None,
+            extension: Extension {
+                flow_type: FlowType::ApothekenpflichtigeArzneimittel,
+                accept_date: None,
+                expiry_date: None,
+                multi_prescription: None,
+            },
+            identifier: Default::default(),
+            status: Status::Ready,
+            for_: Some(kvnr.clone()),
+            authored_on: None,
+            last_modified: None,
+            performer_type: vec![PerformerType::PublicPharmacy],
+            input: Default::default(),
+            output: Default::default(),
+        };
+        let id = task.id.clone();
+        tasks.insert_task(task);
+
+        {
+            let mut task_meta = tasks.get_mut_by_id(&id).unwrap();
+            task_meta.task.for_ = Some(other_kvnr.clone());
+            task_meta.accepted_by = Some(telematik_id.clone());
+        }
+
+        assert_eq!(tasks.iter_by_kvnr(&kvnr).count(), 0);
+        assert_eq!(tasks.iter_by_kvnr(&other_kvnr).count(), 1);
+        assert_eq!(tasks.iter_by_telematik_id(&telematik_id).count(), 1);
+    }
+
+    /// Create a task that is ready to be accepted, without the need of a
+    /// signed KBV bundle. Returns the ID and the access code of the task.
+    pub fn create_ready_task(state: &mut Inner, flow_type: FlowType, kvnr: &Kvnr) -> (Id, String) {
+        let task = state
+            .task_create(TaskCreateParameters { flow_type })
+            .unwrap();
+        let id = task.id.clone();
+        let access_code = task.identifier.access_code.clone().unwrap();
+
+        let e_prescription = Id::generate().unwrap();
+        state.e_prescriptions.insert(
+            e_prescription.clone(),
+            KbvBinary {
+                id: e_prescription.clone(),
+                data: Default::default(),
+            },
+        );
+
+        let mut task_meta = state.tasks.get_mut_by_id(&id).unwrap();
+        task_meta.task.for_ = Some(kvnr.clone());
+        task_meta.task.status = Status::Ready;
+        task_meta.task.input.e_prescription = Some(e_prescription);
+
+        (id, access_code)
+    }
+
+    fn if_match(etag: EntityTag) -> Option<IfMatch> {
+        Some(IfMatch(IfMatchHeader::Items(vec![etag])))
+    }
+
+    fn assert_indexed(
+        tasks: &Tasks,
+        id: &Id,
+        prescription_id: &PrescriptionId,
+        kvnr: &Kvnr,
+        telematik_id: &TelematikId,
+        by_kvnr: bool,
+        by_telematik_id: bool,
+    ) {
+        let task_meta = tasks.get_by_prescription_id(prescription_id).unwrap();
+        assert_eq!(&task_meta.task.id, id);
+
+        let ids = tasks
+            .iter_by_kvnr(kvnr)
+            .map(|task_meta| task_meta.task.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids == vec![id.clone()], by_kvnr);
+        assert_eq!(ids.is_empty(), !by_kvnr);
+
+        let ids = tasks
+            .iter_by_telematik_id(telematik_id)
+            .map(|task_meta| task_meta.task.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids == vec![id.clone()], by_telematik_id);
+        assert_eq!(ids.is_empty(), !by_telematik_id);
+    }
 }
@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::collections::HashSet;
+
+use resources::{primitives::Id, Binary};
+
+use super::Table;
+
+/// Content addressed store of binaries.
+///
+/// The ID of a binary is the hash of its data, so the same data is only
+/// stored once, even if it is referenced by multiple resources.
+#[derive(Default)]
+pub struct Binaries {
+    by_id: Table<Id, Binary>,
+}
+
+impl Binaries {
+    pub fn insert(&mut self, binary: Binary) {
+        if !self.by_id.contains_key(&binary.id) {
+            self.by_id.insert(binary.id.clone(), binary);
+        }
+    }
+
+    pub fn contains(&self, id: &Id) -> bool {
+        self.by_id.contains_key(id)
+    }
+
+    pub fn get_by_id(&self, id: &Id) -> Option<&Binary> {
+        self.by_id.get(id)
+    }
+
+    pub fn remove_by_id(&mut self, id: &Id) {
+        self.by_id.remove(id);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Binary> {
+        self.by_id.values()
+    }
+
+    pub fn take_changes(&mut self) -> HashSet<Id> {
+        self.by_id.take_changes()
+    }
+}
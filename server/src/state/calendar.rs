@@ -0,0 +1,474 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::read_to_string;
+use std::path::Path;
+use std::str::FromStr;
+
+use bdays::easter::easter_naive_date;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+
+use crate::error::Error;
+
+/// Calendar of the public holidays that is used to calculate the accept
+/// date of tasks.
+///
+/// By default the built-in holidays of Germany are used. Regional holidays
+/// are only respected if a federal state is selected. If the holidays are
+/// loaded from a file, only the holidays of this file are used.
+#[derive(Clone, Debug, Default)]
+pub struct Calendar {
+    federal_state: Option<FederalState>,
+    holidays: Option<Vec<Holiday>>,
+}
+
+/// Federal states of Germany (ISO 3166-2:DE).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FederalState {
+    BadenWuerttemberg,
+    Bayern,
+    Berlin,
+    Brandenburg,
+    Bremen,
+    Hamburg,
+    Hessen,
+    MecklenburgVorpommern,
+    Niedersachsen,
+    NordrheinWestfalen,
+    RheinlandPfalz,
+    Saarland,
+    Sachsen,
+    SachsenAnhalt,
+    SchleswigHolstein,
+    Thueringen,
+}
+
+#[derive(Clone, Debug)]
+struct Holiday {
+    date: NaiveDate,
+    federal_states: Vec<FederalState>,
+}
+
+impl Calendar {
+    pub fn new(federal_state: Option<FederalState>) -> Self {
+        Self {
+            federal_state,
+            holidays: None,
+        }
+    }
+
+    /// Load the holidays from a CSV or an ICS file.
+    ///
+    /// Each line of a CSV file contains the date of the holiday (YYYY-MM-DD),
+    /// an optional name and an optional list of federal states separated by
+    /// spaces. If no federal state is given, the holiday applies to all
+    /// states. Of ICS files only the start dates of the events are used.
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        federal_state: Option<FederalState>,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let content = read_to_string(path)?;
+
+        let is_ics = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("ics"))
+            .unwrap_or_default();
+        let holidays = if is_ics {
+            parse_ics(&content)?
+        } else {
+            parse_csv(&content)?
+        };
+
+        Ok(Self {
+            federal_state,
+            holidays: Some(holidays),
+        })
+    }
+
+    pub fn federal_state(&self) -> Option<FederalState> {
+        self.federal_state
+    }
+
+    /// Returns `true` if no prescriptions can be redeemed at the given date,
+    /// which are sundays and public holidays.
+    pub fn is_holiday(&self, date: &NaiveDate) -> bool {
+        if date.weekday() == Weekday::Sun {
+            return true;
+        }
+
+        match &self.holidays {
+            Some(holidays) => holidays
+                .iter()
+                .any(|holiday| &holiday.date == date && holiday.applies_to(self.federal_state)),
+            None => is_builtin_holiday(date, self.federal_state),
+        }
+    }
+
+    /// Add the given number of days to the date, skipping all holidays.
+    pub fn add_working_days(&self, mut date: NaiveDate, days: usize) -> NaiveDate {
+        for _ in 0..days {
+            date = date + Duration::days(1);
+            while self.is_holiday(&date) {
+                date = date + Duration::days(1);
+            }
+        }
+
+        date
+    }
+}
+
+impl Holiday {
+    fn applies_to(&self, federal_state: Option<FederalState>) -> bool {
+        match federal_state {
+            _ if self.federal_states.is_empty() => true,
+            Some(federal_state) => self.federal_states.contains(&federal_state),
+            None => false,
+        }
+    }
+}
+
+impl FromStr for FederalState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim();
+        let code = code.strip_prefix("DE-").unwrap_or(code);
+
+        match code.to_ascii_uppercase().as_str() {
+            "BW" => Ok(Self::BadenWuerttemberg),
+            "BY" => Ok(Self::Bayern),
+            "BE" => Ok(Self::Berlin),
+            "BB" => Ok(Self::Brandenburg),
+            "HB" => Ok(Self::Bremen),
+            "HH" => Ok(Self::Hamburg),
+            "HE" => Ok(Self::Hessen),
+            "MV" => Ok(Self::MecklenburgVorpommern),
+            "NI" => Ok(Self::Niedersachsen),
+            "NW" => Ok(Self::NordrheinWestfalen),
+            "RP" => Ok(Self::RheinlandPfalz),
+            "SL" => Ok(Self::Saarland),
+            "SN" => Ok(Self::Sachsen),
+            "ST" => Ok(Self::SachsenAnhalt),
+            "SH" => Ok(Self::SchleswigHolstein),
+            "TH" => Ok(Self::Thueringen),
+            _ => Err(format!("Unknown federal state: {}", s)),
+        }
+    }
+}
+
+impl Display for FederalState {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let code = match self {
+            Self::BadenWuerttemberg => "BW",
+            Self::Bayern => "BY",
+            Self::Berlin => "BE",
+            Self::Brandenburg => "BB",
+            Self::Bremen => "HB",
+            Self::Hamburg => "HH",
+            Self::Hessen => "HE",
+            Self::MecklenburgVorpommern => "MV",
+            Self::Niedersachsen => "NI",
+            Self::NordrheinWestfalen => "NW",
+            Self::RheinlandPfalz => "RP",
+            Self::Saarland => "SL",
+            Self::Sachsen => "SN",
+            Self::SachsenAnhalt => "ST",
+            Self::SchleswigHolstein => "SH",
+            Self::Thueringen => "TH",
+        };
+
+        write!(f, "DE-{}", code)
+    }
+}
+
+/// Get the date of the passed time in the german time zone (Europe/Berlin).
+///
+/// All dates of prescriptions (signing date, accept and expiry date) are
+/// german dates, so they are calculated by this function.
+///
+/// Central European Summer Time starts on the last sunday of march and ends
+/// on the last sunday of october, both at 01:00 UTC.
+pub fn local_date(time: DateTime<Utc>) -> NaiveDate {
+    let year = time.year();
+    let dst_start = last_sunday(year, 3).and_hms(1, 0, 0);
+    let dst_end = last_sunday(year, 10).and_hms(1, 0, 0);
+
+    let naive = time.naive_utc();
+    let offset = if naive >= dst_start && naive < dst_end {
+        Duration::hours(2)
+    } else {
+        Duration::hours(1)
+    };
+
+    (naive + offset).date()
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let last_day = NaiveDate::from_ymd(year, month, 31);
+    let days_from_sunday = last_day.weekday().num_days_from_sunday();
+
+    last_day - Duration::days(days_from_sunday as i64)
+}
+
+fn is_builtin_holiday(date: &NaiveDate, federal_state: Option<FederalState>) -> bool {
+    use FederalState::*;
+
+    let year = date.year();
+    let easter_sunday = easter_naive_date(year).unwrap();
+    let is = |day, month| date.day() == day && date.month() == month;
+    let is_state =
+        |states: &[FederalState]| matches!(federal_state, Some(s) if states.contains(&s));
+
+    let nationwide = is(1, 1) // Neujahr
+        || date == &(easter_sunday - Duration::days(2)) // Karfreitag
+        || date == &(easter_sunday + Duration::days(1)) // Ostermontag
+        || is(1, 5) // Tag der Arbeit
+        || date == &(easter_sunday + Duration::days(39)) // Christi Himmelfahrt
+        || date == &(easter_sunday + Duration::days(50)) // Pfingstmontag
+        || is(3, 10) // Tag der Deutschen Einheit
+        || (is(31, 10) && year == 2017) // Reformationstag (500. Jahrestag)
+        || is(25, 12) // 1. Weihnachtstag
+        || is(26, 12); // 2. Weihnachtstag
+
+    if nationwide {
+        return true;
+    }
+
+    let reformation_day = if year >= 2018 {
+        &[
+            Brandenburg,
+            Bremen,
+            Hamburg,
+            MecklenburgVorpommern,
+            Niedersachsen,
+            Sachsen,
+            SachsenAnhalt,
+            SchleswigHolstein,
+            Thueringen,
+        ][..]
+    } else {
+        &[
+            Brandenburg,
+            MecklenburgVorpommern,
+            Sachsen,
+            SachsenAnhalt,
+            Thueringen,
+        ][..]
+    };
+
+    (is(6, 1) && is_state(&[BadenWuerttemberg, Bayern, SachsenAnhalt])) // Heilige Drei Könige
+        || (is(8, 3) && year >= 2019 && is_state(&[Berlin])) // Frauentag
+        || (is(8, 3) && year >= 2023 && is_state(&[MecklenburgVorpommern])) // Frauentag
+        || (date == &(easter_sunday + Duration::days(60))
+            && is_state(&[
+                BadenWuerttemberg,
+                Bayern,
+                Hessen,
+                NordrheinWestfalen,
+                RheinlandPfalz,
+                Saarland,
+            ])) // Fronleichnam
+        || (is(15, 8) && is_state(&[Saarland])) // Mariä Himmelfahrt
+        || (is(20, 9) && year >= 2019 && is_state(&[Thueringen])) // Weltkindertag
+        || (is(31, 10) && is_state(reformation_day)) // Reformationstag
+        || (is(1, 11)
+            && is_state(&[
+                BadenWuerttemberg,
+                Bayern,
+                NordrheinWestfalen,
+                RheinlandPfalz,
+                Saarland,
+            ])) // Allerheiligen
+        || (date.month() == 11
+            && (16..=22).contains(&date.day())
+            && date.weekday() == Weekday::Wed
+            && is_state(&[Sachsen])) // Buß- und Bettag
+}
+
+fn parse_csv(content: &str) -> Result<Vec<Holiday>, Error> {
+    let mut holidays = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut columns = line.split(',').map(str::trim);
+
+        let date = columns.next().unwrap_or_default();
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| Error::Generic(format!("Invalid holiday: {}", line)))?;
+
+        let _name = columns.next();
+
+        let federal_states = columns
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(FederalState::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        holidays.push(Holiday {
+            date,
+            federal_states,
+        });
+    }
+
+    Ok(holidays)
+}
+
+fn parse_ics(content: &str) -> Result<Vec<Holiday>, Error> {
+    let mut holidays = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if !line.starts_with("DTSTART") {
+            continue;
+        }
+
+        let value = line.rsplit(':').next().unwrap_or_default();
+        let date = value.get(..8).unwrap_or_default();
+        let date = NaiveDate::parse_from_str(date, "%Y%m%d")
+            .map_err(|_| Error::Generic(format!("Invalid holiday: {}", line)))?;
+
+        holidays.push(Holiday {
+            date,
+            federal_states: Vec::new(),
+        });
+    }
+
+    Ok(holidays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn builtin_holidays() {
+        let calendar = Calendar::default();
+
+        assert!(calendar.is_holiday(&date("2021-04-02"))); // Karfreitag
+        assert!(calendar.is_holiday(&date("2021-05-13"))); // Christi Himmelfahrt
+        assert!(calendar.is_holiday(&date("2021-06-06"))); // Sonntag
+        assert!(!calendar.is_holiday(&date("2021-06-03"))); // Fronleichnam
+        assert!(!calendar.is_holiday(&date("2021-06-05"))); // Samstag
+
+        let calendar = Calendar::new(Some(FederalState::Bayern));
+
+        assert!(calendar.is_holiday(&date("2021-06-03"))); // Fronleichnam
+        assert!(calendar.is_holiday(&date("2021-11-01"))); // Allerheiligen
+        assert!(!calendar.is_holiday(&date("2021-10-31"))); // Reformationstag
+
+        let calendar = Calendar::new(Some(FederalState::Sachsen));
+
+        assert!(calendar.is_holiday(&date("2021-11-17"))); // Buß- und Bettag
+        assert!(!calendar.is_holiday(&date("2021-06-03"))); // Fronleichnam
+    }
+
+    #[test]
+    fn holidays_from_csv() {
+        let holidays = parse_csv(
+            r##"
+            # date, name, federal states
+            2021-12-24, Heiligabend
+            2021-12-31, Silvester, DE-BE HH
+            "##,
+        )
+        .unwrap();
+
+        let calendar = Calendar {
+            federal_state: Some(FederalState::Berlin),
+            holidays: Some(holidays.clone()),
+        };
+
+        assert!(calendar.is_holiday(&date("2021-12-24")));
+        assert!(calendar.is_holiday(&date("2021-12-31")));
+        assert!(!calendar.is_holiday(&date("2021-12-25")));
+
+        let calendar = Calendar {
+            federal_state: Some(FederalState::Bayern),
+            holidays: Some(holidays),
+        };
+
+        assert!(calendar.is_holiday(&date("2021-12-24")));
+        assert!(!calendar.is_holiday(&date("2021-12-31")));
+    }
+
+    #[test]
+    fn holidays_from_ics() {
+        let holidays = parse_ics(
+            r##"BEGIN:VCALENDAR
+BEGIN:VEVENT
+DTSTART;VALUE=DATE:20211224
+SUMMARY:Heiligabend
+END:VEVENT
+END:VCALENDAR"##,
+        )
+        .unwrap();
+
+        assert_eq!(holidays.len(), 1);
+        assert_eq!(holidays[0].date, date("2021-12-24"));
+    }
+
+    #[test]
+    fn add_working_days() {
+        let calendar = Calendar::default();
+
+        assert_eq!(
+            calendar.add_working_days(date("2021-03-31"), 3),
+            date("2021-04-06")
+        );
+        assert_eq!(
+            calendar.add_working_days(date("2021-06-02"), 3),
+            date("2021-06-05")
+        );
+
+        let calendar = Calendar::new(Some(FederalState::NordrheinWestfalen));
+
+        assert_eq!(
+            calendar.add_working_days(date("2021-06-02"), 3),
+            date("2021-06-07")
+        );
+    }
+
+    #[test]
+    fn local_date_respects_summer_time() {
+        assert_eq!(
+            local_date(Utc.ymd(2021, 3, 27).and_hms(23, 30, 0)),
+            NaiveDate::from_ymd(2021, 3, 28)
+        );
+        assert_eq!(
+            local_date(Utc.ymd(2021, 6, 1).and_hms(22, 30, 0)),
+            NaiveDate::from_ymd(2021, 6, 2)
+        );
+        assert_eq!(
+            local_date(Utc.ymd(2021, 11, 1).and_hms(22, 30, 0)),
+            NaiveDate::from_ymd(2021, 11, 1)
+        );
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use chrono::{DateTime, Utc};
+
+/// Clock that is used to get the current time when resources are modified.
+///
+/// A fixed clock can be used to make the timestamps and the calculated
+/// dates of the resources reproducible for testing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clock {
+    System,
+    Fixed(DateTime<Utc>),
+}
+
+impl Clock {
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Self::System => Utc::now(),
+            Self::Fixed(now) => *now,
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::System
+    }
+}
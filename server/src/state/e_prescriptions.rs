@@ -15,13 +15,15 @@
  *
  */
 
-use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{hash_map::Entry, HashSet};
 
 use resources::{primitives::Id, KbvBinary};
 
+use super::Table;
+
 #[derive(Default)]
 pub struct EPrescriptions {
-    by_id: HashMap<Id, KbvBinary>,
+    by_id: Table<Id, KbvBinary>,
 }
 
 impl EPrescriptions {
@@ -54,4 +56,8 @@ impl EPrescriptions {
     pub fn iter(&self) -> impl Iterator<Item = (&Id, &KbvBinary)> {
         self.by_id.iter()
     }
+
+    pub fn take_changes(&mut self) -> HashSet<Id> {
+        self.by_id.take_changes()
+    }
 }
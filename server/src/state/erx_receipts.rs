@@ -15,7 +15,7 @@
  *
  */
 
-use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{hash_map::Entry, HashSet};
 
 use openssl::{
     pkey::{PKey, Private},
@@ -25,11 +25,13 @@ use resources::{primitives::Id, ErxBundle, SignatureType};
 
 use crate::fhir::security::{Signed, SignedError};
 
+use super::Table;
+
 pub struct ErxReceipts {
     sig_key: PKey<Private>,
     sig_cert: X509,
 
-    by_id: HashMap<Id, Signed<ErxBundle>>,
+    by_id: Table<Id, Signed<ErxBundle>>,
 }
 
 impl ErxReceipts {
@@ -77,4 +79,8 @@ impl ErxReceipts {
     pub fn iter(&self) -> impl Iterator<Item = &Signed<ErxBundle>> {
         self.by_id.values()
     }
+
+    pub fn take_changes(&mut self) -> HashSet<Id> {
+        self.by_id.take_changes()
+    }
 }
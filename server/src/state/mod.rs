@@ -15,49 +15,104 @@
  *
  */
 
+mod binaries;
+mod calendar;
+mod clock;
 mod e_prescriptions;
 mod erx_receipts;
 mod patient_receipts;
 mod persist;
+mod retention;
+mod snapshot;
+mod storage;
+mod table;
 mod timeouts;
 
-use std::sync::Arc;
+use std::ops::{Deref, DerefMut};
+use std::process::abort;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use log::error;
 
 use openssl::{
     pkey::{PKey, Private},
     x509::X509,
 };
 use tokio::{
-    sync::{Mutex, MutexGuard},
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
     time::{delay_for, Duration},
 };
 
-use crate::service::{AuditEvents, Communications, MedicationDispenses, Tasks};
+use crate::{
+    error::Error,
+    service::{
+        AttachmentTypes, AuditEvents, ChargeItems, Communications, Idempotency,
+        MedicationDispenses, Tasks,
+    },
+};
 
+pub use binaries::Binaries;
+pub use calendar::{local_date, Calendar, FederalState};
+pub use clock::Clock;
 pub use e_prescriptions::EPrescriptions;
 pub use erx_receipts::ErxReceipts;
 pub use patient_receipts::PatientReceipts;
+pub use retention::{
+    AuditEventRetention, CommunicationRetention, MedicationDispenseRetention, Period, Retention,
+    TaskRetention, LEGAL_AUDIT_EVENT_RETENTION_DAYS,
+};
+pub use storage::{Change, Journal, Kind, Record, Sqlite, Storage, Transaction};
+pub use table::Table;
 pub use timeouts::{ResourceId, Timeouts};
 
+/// State of the service.
+///
+/// The state is guarded by a read-write lock. Operations that modify
+/// resources use `lock` to get exclusive access, so changes that affect
+/// multiple collections are still atomic. Operations that only read
+/// resources use `read` and may run concurrently.
 #[derive(Clone)]
 pub struct State {
-    inner: Arc<Mutex<Inner>>,
+    inner: Arc<RwLock<Inner>>,
     config: Arc<Config>,
 }
 
 pub struct Inner {
     pub(super) max_communications: usize,
+    pub(super) attachment_types: AttachmentTypes,
 
     pub(super) tasks: Tasks,
     pub(super) e_prescriptions: EPrescriptions,
     pub(super) patient_receipts: PatientReceipts,
     pub(super) erx_receipts: ErxReceipts,
     pub(super) communications: Communications,
+    pub(super) binaries: Binaries,
     pub(super) medication_dispenses: MedicationDispenses,
+    pub(super) charge_items: ChargeItems,
     pub(super) audit_events: AuditEvents,
     pub(super) timeouts: Timeouts,
+    pub(super) calendar: Calendar,
+    pub(super) clock: Clock,
+    pub(super) idempotency: Idempotency,
+
+    storage: Mutex<Option<Box<dyn Storage>>>,
+    state_key: Option<Vec<u8>>,
 }
 
+/// Guard that gives exclusive access to the state.
+///
+/// All changes made while the guard is alive are committed to the attached
+/// storage as soon as the guard is dropped. If the commit fails the service
+/// is terminated (see `commit_failed`).
+pub struct Guard<'a>(RwLockWriteGuard<'a, Inner>);
+
+/// Guard that gives shared access to the state.
+///
+/// Audit events that are recorded while the guard is alive are committed to
+/// the attached storage as soon as the guard is dropped.
+pub struct ReadGuard<'a>(RwLockReadGuard<'a, Inner>);
+
 struct Config {
     throttling: usize,
     throttling_header: String,
@@ -73,17 +128,26 @@ impl State {
     ) -> Self {
         let inner = Inner {
             max_communications,
+            attachment_types: Default::default(),
 
             tasks: Default::default(),
             e_prescriptions: Default::default(),
             patient_receipts: PatientReceipts::new(sig_key.clone(), sig_cert.clone()),
             erx_receipts: ErxReceipts::new(sig_key, sig_cert),
             communications: Default::default(),
+            binaries: Default::default(),
             medication_dispenses: Default::default(),
+            charge_items: Default::default(),
             audit_events: Default::default(),
             timeouts: Default::default(),
+            calendar: Default::default(),
+            clock: Default::default(),
+            idempotency: Default::default(),
+
+            storage: Mutex::new(None),
+            state_key: None,
         };
-        let inner = Arc::new(Mutex::new(inner));
+        let inner = Arc::new(RwLock::new(inner));
 
         let config = Config {
             throttling,
@@ -97,8 +161,12 @@ impl State {
         ret
     }
 
-    pub async fn lock(&self) -> MutexGuard<'_, Inner> {
-        self.inner.lock().await
+    pub async fn lock(&self) -> Guard<'_> {
+        Guard(self.inner.write().await)
+    }
+
+    pub async fn read(&self) -> ReadGuard<'_> {
+        ReadGuard(self.inner.read().await)
     }
 
     pub async fn throttle(&self) -> Option<String> {
@@ -111,3 +179,131 @@ impl State {
         }
     }
 }
+
+impl Inner {
+    /// Set the calendar that is used to calculate the accept date of tasks.
+    pub fn set_calendar(&mut self, calendar: Calendar) {
+        self.calendar = calendar;
+    }
+
+    /// Set the clock that is used to get the current time.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+        self.timeouts.clock = clock;
+    }
+
+    /// Get the current time of the clock of the state.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Set the MIME types (and their maximum size) that are allowed for the
+    /// attachments of communications.
+    pub fn set_attachment_types(&mut self, attachment_types: AttachmentTypes) {
+        self.attachment_types = attachment_types;
+    }
+
+    /// Set the time the responses of requests with an `Idempotency-Key` are
+    /// kept (`0` disables the replay of responses).
+    pub fn set_idempotency_window(&mut self, window: Duration) {
+        self.idempotency.set_window(window);
+    }
+}
+
+impl Deref for Guard<'_> {
+    type Target = Inner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Guard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.commit() {
+            commit_failed("changes", err);
+        }
+    }
+}
+
+impl Deref for ReadGuard<'_> {
+    type Target = Inner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for ReadGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.commit_audit_events() {
+            commit_failed("audit events", err);
+        }
+    }
+}
+
+/// Called if the changes of a guard could not be written to the storage.
+///
+/// The state in memory is ahead of the storage in this case and the response
+/// of the current request would acknowledge a change that was never
+/// persisted. So the service is terminated before the response is sent, and
+/// is restored from the last consistent state of the storage on restart.
+fn commit_failed(what: &str, err: Error) -> ! {
+    error!("Unable to commit {} to storage: {}", what, err);
+
+    abort();
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use openssl::{
+        asn1::Asn1Time,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        nid::Nid,
+        x509::X509NameBuilder,
+    };
+
+    /// Create a state with a throwaway signing key and an empty certificate.
+    pub fn state() -> State {
+        let sig_key = PKey::generate_ed448().unwrap();
+        let sig_cert = X509::builder().unwrap().build();
+
+        State::new(sig_key, sig_cert, 10, 500, "999 Throttling active".into())
+    }
+
+    /// Create a state with a self-signed certificate, so that receipts can be
+    /// signed by $close.
+    pub fn signing_state() -> State {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let sig_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "ErxService").unwrap();
+        let name = name.build();
+
+        let mut sig_cert = X509::builder().unwrap();
+        sig_cert.set_version(2).unwrap();
+        sig_cert.set_subject_name(&name).unwrap();
+        sig_cert.set_issuer_name(&name).unwrap();
+        sig_cert.set_pubkey(&sig_key).unwrap();
+        sig_cert
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        sig_cert
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        sig_cert.sign(&sig_key, MessageDigest::sha256()).unwrap();
+        let sig_cert = sig_cert.build();
+
+        State::new(sig_key, sig_cert, 10, 500, "999 Throttling active".into())
+    }
+}
@@ -15,7 +15,7 @@
  *
  */
 
-use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{hash_map::Entry, HashSet};
 
 use openssl::{
     pkey::{PKey, Private},
@@ -25,11 +25,13 @@ use resources::{primitives::Id, KbvBundle, SignatureType};
 
 use crate::fhir::security::{Signed, SignedError};
 
+use super::Table;
+
 pub struct PatientReceipts {
     sig_key: PKey<Private>,
     sig_cert: X509,
 
-    by_id: HashMap<Id, Signed<KbvBundle>>,
+    by_id: Table<Id, Signed<KbvBundle>>,
 }
 
 impl PatientReceipts {
@@ -78,4 +80,8 @@ impl PatientReceipts {
     pub fn iter(&self) -> impl Iterator<Item = &Signed<KbvBundle>> {
         self.by_id.values()
     }
+
+    pub fn take_changes(&mut self) -> HashSet<Id> {
+        self.by_id.take_changes()
+    }
 }
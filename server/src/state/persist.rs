@@ -15,32 +15,57 @@
  *
  */
 
+use std::convert::TryFrom;
 use std::io::{Read, Write};
 use std::ops::Deref;
 
 use chrono::{serde::ts_nanoseconds_option, DateTime, Utc};
+use miscellaneous::{
+    envelope::Envelope,
+    migration::{is_encrypted, migrate},
+};
 use resources::{
-    primitives::Id, AuditEvent, Communication, ErxBundle, KbvBinary, KbvBundle, MedicationDispense,
-    Task,
+    misc::TelematikId, primitives::Id, AuditEvent, Binary, ChargeItem, Communication, ErxBundle,
+    KbvBinary, KbvBundle, MedicationDispense, Task,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::{from_reader, to_writer};
+use serde_json::{from_reader, from_slice, from_str, from_value, to_vec, to_writer, Value};
 
 use crate::{error::Error, fhir::security::Signed, service::TaskMeta};
 
-use super::Inner;
+use super::{
+    storage::{Change, Kind, Record, Transaction},
+    Inner,
+};
 
 impl Inner {
+    /// Set the key that is used to encrypt the state when it is saved.
+    /// Encrypted states can only be loaded if the key is set.
+    pub fn set_state_key(&mut self, key: Option<Vec<u8>>) {
+        self.state_key = key;
+    }
+
     pub fn load<R>(&mut self, reader: R) -> Result<(), Error>
     where
         R: Read,
     {
-        let version: Version = from_reader(reader)?;
+        let mut value: Value = from_reader(reader)?;
+        if is_encrypted(&value) {
+            value = self.decrypt(from_value(value)?)?;
+        }
+
+        migrate(&mut value)?;
 
-        match version {
-            Version::Old(data) => old::load(self, data),
-            Version::V3(data) => v3::load(self, data),
+        match from_value(value)? {
+            Version::V3(data) => v3::load(self, data)?,
+            Version::Encrypted(_) => {
+                return Err(Error::Generic("State must not be encrypted twice!".into()))
+            }
         }
+
+        self.rebuild_timeouts();
+
+        Ok(())
     }
 
     pub fn save<W>(&self, writer: W) -> Result<(), Error>
@@ -48,165 +73,200 @@ impl Inner {
         W: Write,
     {
         let version = Version::V3(v3::save(self));
+        let version = match &self.state_key {
+            Some(key) => Version::Encrypted(Envelope::seal(key, &to_vec(&version)?)?),
+            None => version,
+        };
 
         to_writer(writer, &version)?;
 
         Ok(())
     }
-}
 
-#[derive(Serialize, Deserialize)]
-#[serde(tag = "version")]
-enum Version {
-    #[serde(alias = "v3")]
-    V3(v3::Data),
+    fn decrypt(&self, envelope: Envelope) -> Result<Value, Error> {
+        let key = self.state_key.as_ref().ok_or_else(|| {
+            Error::Generic("State is encrypted, but no state key was provided!".into())
+        })?;
 
-    #[serde(alias = "old")]
-    Old(old::Data),
-}
+        let data = envelope.open(key)?;
+        let value = from_slice(&data)?;
 
-mod v3 {
-    use super::*;
+        Ok(value)
+    }
 
-    pub fn load(inner: &mut Inner, data: Data) -> Result<(), Error> {
-        for task in data.tasks {
-            let task_meta = TaskMeta {
-                task: task.task,
-                accept_timestamp: task.accept_timestamp,
-                communication_count: task.communication_count,
-            };
+    pub(super) fn apply(&mut self, change: Change) -> Result<(), Error> {
+        match change {
+            Change::Put(Record { kind, id, data }) => {
+                let id = parse_id(id)?;
+                self.remove_record(kind, &id);
+
+                let mut v = v3::Data::default();
+                match kind {
+                    Kind::Task => v.tasks.push(from_str(&data)?),
+                    Kind::EPrescription => v.e_prescriptions.push((id, from_str(&data)?)),
+                    Kind::PatientReceipt => v.patient_receipts.push(from_str(&data)?),
+                    Kind::ErxReceipt => v.erx_receipts.push(from_str(&data)?),
+                    Kind::Communication => v.communications.push(from_str(&data)?),
+                    Kind::Binary => v.binaries.push(from_str(&data)?),
+                    Kind::MedicationDispense => v.medication_dispenses.push(from_str(&data)?),
+                    Kind::ChargeItem => v.charge_items.push(from_str(&data)?),
+                    Kind::AuditEvent => v.audit_events.push(from_str(&data)?),
+                }
+
+                v3::load(self, v)
+            }
+            Change::Delete { kind, id } => {
+                let id = parse_id(id)?;
+                self.remove_record(kind, &id);
 
-            inner.tasks.insert_task_meta(task_meta);
+                Ok(())
+            }
         }
+    }
 
-        for (id, kbv_binary) in data.e_prescriptions {
-            inner.e_prescriptions.insert(id, kbv_binary);
+    fn remove_record(&mut self, kind: Kind, id: &Id) {
+        match kind {
+            Kind::Task if self.tasks.get_by_id(id).is_some() => self.tasks.remove_by_id(id),
+            Kind::EPrescription if self.e_prescriptions.contains(id) => {
+                self.e_prescriptions.remove_by_id(id)
+            }
+            Kind::PatientReceipt if self.patient_receipts.get_by_id(id).is_some() => {
+                self.patient_receipts.remove_by_id(id)
+            }
+            Kind::ErxReceipt if self.erx_receipts.get_by_id(id).is_some() => {
+                self.erx_receipts.remove_by_id(id)
+            }
+            Kind::Communication => self.communication_delete_by_id(id),
+            Kind::Binary => self.binaries.remove_by_id(id),
+            Kind::MedicationDispense if self.medication_dispenses.get_by_id(id).is_some() => {
+                self.medication_dispense_delete_by_id(id)
+            }
+            Kind::ChargeItem => self.charge_item_delete_by_id(id),
+            Kind::AuditEvent => self.audit_event_delete_by_id(id),
+            _ => (),
         }
+    }
 
-        for patient_receipt in data.patient_receipts {
-            inner
-                .patient_receipts
-                .insert_signed(Signed::new(patient_receipt));
+    pub(super) fn take_transaction(&mut self) -> Result<Transaction, Error> {
+        let mut transaction = Transaction::default();
+
+        for id in self.tasks.take_changes() {
+            match self.tasks.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::Task, &id, &v3::TaskData::from(v))?,
+                None => transaction.delete(Kind::Task, &id),
+            }
         }
 
-        for erx_receipt in data.erx_receipts {
-            inner.erx_receipts.insert_signed(Signed::new(erx_receipt));
+        for id in self.e_prescriptions.take_changes() {
+            match self.e_prescriptions.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::EPrescription, &id, v)?,
+                None => transaction.delete(Kind::EPrescription, &id),
+            }
         }
 
-        for communication in data.communications {
-            inner.communications.insert(communication);
+        for id in self.patient_receipts.take_changes() {
+            match self.patient_receipts.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::PatientReceipt, &id, v.deref())?,
+                None => transaction.delete(Kind::PatientReceipt, &id),
+            }
         }
 
-        for medication_dispense in data.medication_dispenses {
-            inner.medication_dispenses.insert(medication_dispense);
+        for id in self.erx_receipts.take_changes() {
+            match self.erx_receipts.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::ErxReceipt, &id, v.deref())?,
+                None => transaction.delete(Kind::ErxReceipt, &id),
+            }
         }
 
-        for audit_event in data.audit_events {
-            inner.audit_events.insert(audit_event)
+        for id in self.communications.take_changes() {
+            match self.communications.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::Communication, &id, v)?,
+                None => transaction.delete(Kind::Communication, &id),
+            }
         }
 
-        Ok(())
-    }
+        for id in self.binaries.take_changes() {
+            match self.binaries.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::Binary, &id, v)?,
+                None => transaction.delete(Kind::Binary, &id),
+            }
+        }
 
-    pub fn save(inner: &Inner) -> Data {
-        let mut data = Data {
-            tasks: inner.tasks.iter().map(From::from).collect(),
-            e_prescriptions: inner
-                .e_prescriptions
-                .iter()
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect(),
-            patient_receipts: inner
-                .patient_receipts
-                .iter()
-                .map(Deref::deref)
-                .cloned()
-                .collect(),
-            erx_receipts: inner
-                .erx_receipts
-                .iter()
-                .map(Deref::deref)
-                .cloned()
-                .collect(),
-            communications: inner.communications.iter().cloned().collect(),
-            medication_dispenses: inner.medication_dispenses.iter().cloned().collect(),
-            audit_events: inner.audit_events.iter().cloned().collect(),
-        };
+        for id in self.medication_dispenses.take_changes() {
+            match self.medication_dispenses.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::MedicationDispense, &id, v)?,
+                None => transaction.delete(Kind::MedicationDispense, &id),
+            }
+        }
 
-        data.tasks.sort_by(|a, b| {
-            let a = &a.task.id;
-            let b = &b.task.id;
+        for id in self.charge_items.take_changes() {
+            match self.charge_items.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::ChargeItem, &id, v)?,
+                None => transaction.delete(Kind::ChargeItem, &id),
+            }
+        }
 
-            a.cmp(&b)
-        });
-        data.e_prescriptions.sort_by(|(a, _), (b, _)| a.cmp(&b));
-        data.patient_receipts.sort_by(|a, b| a.id.cmp(&b.id));
-        data.erx_receipts.sort_by(|a, b| a.id.cmp(&b.id));
-        data.communications.sort_by(|a, b| {
-            let a = a.id().as_ref().unwrap();
-            let b = b.id().as_ref().unwrap();
+        self.take_audit_event_changes(&mut transaction)?;
 
-            a.cmp(&b)
-        });
-        data.medication_dispenses.sort_by(|a, b| {
-            let a = a.id.as_ref().unwrap();
-            let b = b.id.as_ref().unwrap();
+        Ok(transaction)
+    }
 
-            a.cmp(&b)
-        });
-        data.audit_events.sort_by(|a, b| a.id.cmp(&b.id));
+    /// Add the changes of the audit events to the passed transaction.
+    ///
+    /// Audit events may be changed using a shared reference, so this does
+    /// not need exclusive access to the state.
+    pub(super) fn take_audit_event_changes(
+        &self,
+        transaction: &mut Transaction,
+    ) -> Result<(), Error> {
+        for id in self.audit_events.take_changes() {
+            match self.audit_events.get_by_id(&id) {
+                Some(v) => transaction.put(Kind::AuditEvent, &id, &v)?,
+                None => transaction.delete(Kind::AuditEvent, &id),
+            }
+        }
 
-        data
+        Ok(())
     }
 
-    #[derive(Serialize, Deserialize)]
-    pub struct Data {
-        tasks: Vec<TaskData>,
-        e_prescriptions: Vec<(Id, KbvBinary)>,
-        patient_receipts: Vec<KbvBundle>,
-        erx_receipts: Vec<ErxBundle>,
-        communications: Vec<Communication>,
-        medication_dispenses: Vec<MedicationDispense>,
-        audit_events: Vec<AuditEvent>,
+    pub(super) fn discard_changes(&mut self) {
+        self.tasks.take_changes();
+        self.e_prescriptions.take_changes();
+        self.patient_receipts.take_changes();
+        self.erx_receipts.take_changes();
+        self.communications.take_changes();
+        self.binaries.take_changes();
+        self.medication_dispenses.take_changes();
+        self.charge_items.take_changes();
+        self.audit_events.take_changes();
     }
+}
 
-    #[derive(Serialize, Deserialize)]
-    pub struct TaskData {
-        task: Task,
-
-        #[serde(with = "ts_nanoseconds_option")]
-        accept_timestamp: Option<DateTime<Utc>>,
+fn parse_id(id: String) -> Result<Id, Error> {
+    Id::try_from(id).map_err(|id| Error::Generic(format!("Invalid ID: {}", id)))
+}
 
-        #[serde(default)]
-        communication_count: usize,
-    }
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "version")]
+enum Version {
+    #[serde(alias = "v3")]
+    V3(v3::Data),
 
-    impl From<&TaskMeta> for TaskData {
-        fn from(v: &TaskMeta) -> Self {
-            Self {
-                task: v.task.clone(),
-                accept_timestamp: v.accept_timestamp,
-                communication_count: v.communication_count,
-            }
-        }
-    }
+    #[serde(alias = "encrypted")]
+    Encrypted(Envelope),
 }
 
-mod old {
+mod v3 {
     use super::*;
 
     pub fn load(inner: &mut Inner, data: Data) -> Result<(), Error> {
         for task in data.tasks {
             let task_meta = TaskMeta {
-                task: task
-                    .history
-                    .into_iter()
-                    .last()
-                    .ok_or_else(|| {
-                        Error::Generic("State must contain at least one task version!".into())
-                    })?
-                    .resource,
+                task: task.task,
+                history: task.history,
                 accept_timestamp: task.accept_timestamp,
+                accepted_by: task.accepted_by,
+                designated_performer: task.designated_performer,
                 communication_count: task.communication_count,
             };
 
@@ -231,10 +291,18 @@ mod old {
             inner.communications.insert(communication);
         }
 
+        for binary in data.binaries {
+            inner.binaries.insert(binary);
+        }
+
         for medication_dispense in data.medication_dispenses {
             inner.medication_dispenses.insert(medication_dispense);
         }
 
+        for charge_item in data.charge_items {
+            inner.charge_items.insert(charge_item);
+        }
+
         for audit_event in data.audit_events {
             inner.audit_events.insert(audit_event)
         }
@@ -242,7 +310,6 @@ mod old {
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn save(inner: &Inner) -> Data {
         let mut data = Data {
             tasks: inner.tasks.iter().map(From::from).collect(),
@@ -264,13 +331,15 @@ mod old {
                 .cloned()
                 .collect(),
             communications: inner.communications.iter().cloned().collect(),
+            binaries: inner.binaries.iter().cloned().collect(),
             medication_dispenses: inner.medication_dispenses.iter().cloned().collect(),
-            audit_events: inner.audit_events.iter().cloned().collect(),
+            charge_items: inner.charge_items.iter().cloned().collect(),
+            audit_events: inner.audit_events.to_vec(),
         };
 
         data.tasks.sort_by(|a, b| {
-            let a = &a.history.first().unwrap().resource.id;
-            let b = &b.history.first().unwrap().resource.id;
+            let a = &a.task.id;
+            let b = &b.task.id;
 
             a.cmp(&b)
         });
@@ -283,55 +352,71 @@ mod old {
 
             a.cmp(&b)
         });
+        data.binaries.sort_by(|a, b| a.id.cmp(&b.id));
         data.medication_dispenses.sort_by(|a, b| {
             let a = a.id.as_ref().unwrap();
             let b = b.id.as_ref().unwrap();
 
             a.cmp(&b)
         });
+        data.charge_items.sort_by(|a, b| {
+            let a = a.id.as_ref().unwrap();
+            let b = b.id.as_ref().unwrap();
+
+            a.cmp(&b)
+        });
         data.audit_events.sort_by(|a, b| a.id.cmp(&b.id));
 
         data
     }
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Default, Serialize, Deserialize)]
     pub struct Data {
-        tasks: Vec<TaskData>,
-        e_prescriptions: Vec<(Id, KbvBinary)>,
-        patient_receipts: Vec<KbvBundle>,
-        erx_receipts: Vec<ErxBundle>,
-        communications: Vec<Communication>,
-        medication_dispenses: Vec<MedicationDispense>,
-        audit_events: Vec<AuditEvent>,
+        pub tasks: Vec<TaskData>,
+        pub e_prescriptions: Vec<(Id, KbvBinary)>,
+        pub patient_receipts: Vec<KbvBundle>,
+        pub erx_receipts: Vec<ErxBundle>,
+        pub communications: Vec<Communication>,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub binaries: Vec<Binary>,
+
+        pub medication_dispenses: Vec<MedicationDispense>,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub charge_items: Vec<ChargeItem>,
+
+        pub audit_events: Vec<AuditEvent>,
     }
 
     #[derive(Serialize, Deserialize)]
     pub struct TaskData {
-        history: Vec<TaskVersion>,
+        task: Task,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        history: Vec<Task>,
 
         #[serde(with = "ts_nanoseconds_option")]
         accept_timestamp: Option<DateTime<Utc>>,
 
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        accepted_by: Option<TelematikId>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        designated_performer: Option<TelematikId>,
+
         #[serde(default)]
         communication_count: usize,
     }
 
-    #[derive(Serialize, Deserialize)]
-    pub struct TaskVersion {
-        version_id: usize,
-        timestamp: usize,
-        resource: Task,
-    }
-
     impl From<&TaskMeta> for TaskData {
         fn from(v: &TaskMeta) -> Self {
             Self {
-                history: vec![TaskVersion {
-                    version_id: 0,
-                    timestamp: 0,
-                    resource: v.task.clone(),
-                }],
+                task: v.task.clone(),
+                history: v.history.clone(),
                 accept_timestamp: v.accept_timestamp,
+                accepted_by: v.accepted_by.clone(),
+                designated_performer: v.designated_performer.clone(),
                 communication_count: v.communication_count,
             }
         }
@@ -340,21 +425,23 @@ mod old {
 
 #[cfg(test)]
 pub mod tests {
-    use std::fs::read_to_string;
+    use std::env::temp_dir;
+    use std::fs::{read_to_string, remove_file};
     use std::str::from_utf8;
 
-    use openssl::{pkey::PKey, x509::X509};
+    use chrono::Utc;
+    use resources::{
+        misc::{Kvnr, ParticipantId, TelematikId},
+        primitives::DateTime,
+    };
 
     use crate::fhir::tests::trim_json_str;
 
-    use super::super::State;
+    use super::super::{tests::state, Journal, Sqlite};
 
     #[tokio::test]
     pub async fn load_save_v1() {
-        let sig_key = PKey::generate_ed448().unwrap();
-        let sig_cert = X509::builder().unwrap().build();
-
-        let state = State::new(sig_key, sig_cert, 10, 500, "999 Throttling active".into());
+        let state = state();
         let mut state = state.lock().await;
 
         let content = read_to_string("./examples/state_load_v1.json").unwrap();
@@ -373,10 +460,7 @@ pub mod tests {
 
     #[tokio::test]
     pub async fn load_save_v2() {
-        let sig_key = PKey::generate_ed448().unwrap();
-        let sig_cert = X509::builder().unwrap().build();
-
-        let state = State::new(sig_key, sig_cert, 10, 500, "999 Throttling active".into());
+        let state = state();
         let mut state = state.lock().await;
 
         let content = read_to_string("./examples/state_load_v2.json").unwrap();
@@ -395,15 +479,156 @@ pub mod tests {
 
     #[tokio::test]
     pub async fn load_save_v3() {
-        let sig_key = PKey::generate_ed448().unwrap();
-        let sig_cert = X509::builder().unwrap().build();
+        let state = state();
+        let mut state = state.lock().await;
+
+        let content = read_to_string("./examples/state_load_v3.json").unwrap();
+        let content = trim_json_str(&content);
+        state.load(content.as_bytes()).unwrap();
 
-        let state = State::new(sig_key, sig_cert, 10, 500, "999 Throttling active".into());
+        let expected = read_to_string("./examples/state_save_v3_to_v3.json").unwrap();
+        let expected = trim_json_str(&expected);
+
+        let mut actual = Vec::new();
+        state.save(&mut actual).unwrap();
+
+        let actual = from_utf8(&actual).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    pub async fn commit_only_modified_communications() {
+        let state = state();
         let mut state = state.lock().await;
 
         let content = read_to_string("./examples/state_load_v3.json").unwrap();
         let content = trim_json_str(&content);
         state.load(content.as_bytes()).unwrap();
+        state.discard_changes();
+
+        let now: DateTime = Utc::now().into();
+        let sender = ParticipantId::Kvnr(Kvnr::new("X234567890").unwrap());
+        let recipient = ParticipantId::TelematikId(TelematikId::new("606358757"));
+
+        // Reading as sender does not modify the communication
+        let count = state
+            .communication_iter(&sender, Some(now.clone()), |_| true)
+            .count();
+        assert_eq!(count, 1);
+        assert!(state.take_transaction().unwrap().is_empty());
+
+        // Reading as recipient marks the communication as received once
+        let count = state
+            .communication_iter(&recipient, Some(now.clone()), |_| true)
+            .count();
+        assert_eq!(count, 1);
+        assert_eq!(state.take_transaction().unwrap().changes.len(), 1);
+
+        let count = state
+            .communication_iter(&recipient, Some(now), |_| true)
+            .count();
+        assert_eq!(count, 1);
+        assert!(state.take_transaction().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    pub async fn load_save_storage() {
+        let path = temp_dir().join("ref-erx-fd-server-load-save-storage.db");
+        let _ = remove_file(&path);
+
+        {
+            let state = state();
+            let mut state = state.lock().await;
+
+            let storage = Sqlite::open(&path).unwrap();
+            assert_eq!(0, state.attach_storage(Box::new(storage)).unwrap());
+
+            let content = read_to_string("./examples/state_load_v3.json").unwrap();
+            let content = trim_json_str(&content);
+            state.load(content.as_bytes()).unwrap();
+        }
+
+        let state = state();
+        let mut state = state.lock().await;
+
+        let storage = Sqlite::open(&path).unwrap();
+        assert_ne!(0, state.attach_storage(Box::new(storage)).unwrap());
+
+        let expected = read_to_string("./examples/state_save_v3_to_v3.json").unwrap();
+        let expected = trim_json_str(&expected);
+
+        let mut actual = Vec::new();
+        state.save(&mut actual).unwrap();
+
+        let actual = from_utf8(&actual).unwrap();
+        assert_eq!(actual, expected);
+
+        let _ = remove_file(&path);
+    }
+
+    #[tokio::test]
+    pub async fn load_save_journal() {
+        let path = temp_dir().join("ref-erx-fd-server-load-save-journal.journal");
+        let _ = remove_file(&path);
+
+        {
+            let state = state();
+            let mut state = state.lock().await;
+
+            let storage = Journal::open(&path, None).unwrap();
+            assert_eq!(0, state.attach_storage(Box::new(storage)).unwrap());
+
+            let content = read_to_string("./examples/state_load_v3.json").unwrap();
+            let content = trim_json_str(&content);
+            state.load(content.as_bytes()).unwrap();
+        }
+
+        let state = state();
+        let mut state = state.lock().await;
+
+        let storage = Journal::open(&path, None).unwrap();
+        assert_ne!(0, state.attach_storage(Box::new(storage)).unwrap());
+
+        let expected = read_to_string("./examples/state_save_v3_to_v3.json").unwrap();
+        let expected = trim_json_str(&expected);
+
+        let mut actual = Vec::new();
+        state.save(&mut actual).unwrap();
+
+        let actual = from_utf8(&actual).unwrap();
+        assert_eq!(actual, expected);
+
+        let _ = remove_file(&path);
+    }
+
+    #[tokio::test]
+    pub async fn load_save_encrypted() {
+        let key = vec![42u8; 32];
+
+        let encrypted = {
+            let state = state();
+            let mut state = state.lock().await;
+            state.set_state_key(Some(key.clone()));
+
+            let content = read_to_string("./examples/state_load_v3.json").unwrap();
+            let content = trim_json_str(&content);
+            state.load(content.as_bytes()).unwrap();
+
+            let mut encrypted = Vec::new();
+            state.save(&mut encrypted).unwrap();
+            let content = from_utf8(&encrypted).unwrap();
+            assert!(content.contains(r#""version":"Encrypted""#));
+
+            encrypted
+        };
+
+        let state = state();
+        let mut state = state.lock().await;
+        assert!(state.load(&encrypted[..]).is_err());
+
+        state.set_state_key(Some(key));
+        state.load(&encrypted[..]).unwrap();
+        state.set_state_key(None);
 
         let expected = read_to_string("./examples/state_save_v3_to_v3.json").unwrap();
         let expected = trim_json_str(&expected);
@@ -0,0 +1,271 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::convert::TryFrom;
+use std::fs::read_to_string;
+use std::ops::Deref;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::Duration;
+use serde::Deserialize;
+
+use crate::error::Error;
+
+/// Minimum number of days audit events must be kept by law.
+pub const LEGAL_AUDIT_EVENT_RETENTION_DAYS: i64 = 3 * 365;
+
+/// Defines how long the different resources are kept in the state.
+///
+/// Resources that belong to a task (the e-prescription, the receipts and the
+/// communications) are removed together with the task.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retention {
+    /// Interval to check the state for expired resources.
+    pub purge_interval: Period,
+    pub tasks: TaskRetention,
+    pub medication_dispenses: MedicationDispenseRetention,
+    pub communications: CommunicationRetention,
+    pub audit_events: AuditEventRetention,
+}
+
+/// Retention of tasks depending on their status.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaskRetention {
+    /// Counted from 'authoredOn'.
+    pub draft: Period,
+
+    /// Counted from the expiry date of the prescription.
+    pub ready: Period,
+
+    /// Counted from 'lastModified'.
+    pub in_progress: Period,
+
+    /// Counted from 'lastModified'.
+    pub completed: Period,
+
+    /// Counted from 'lastModified'.
+    pub cancelled: Period,
+}
+
+/// Retention of medication dispenses, counted from 'whenHandedOver'.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MedicationDispenseRetention {
+    pub retention: Period,
+}
+
+/// Retention of communications, counted from 'sent'.
+///
+/// If no retention is set, communications are kept until their task is
+/// removed.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommunicationRetention {
+    pub retention: Option<Period>,
+}
+
+/// Retention of audit events, counted from 'recorded'.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditEventRetention {
+    pub retention: Period,
+
+    /// Allow a retention below the legal minimum (for testing only).
+    pub ignore_legal_minimum: bool,
+}
+
+/// Period of time that is parsed from strings like '10d' or '3y'.
+///
+/// Supported units are 's' (seconds), 'm' (minutes), 'h' (hours), 'd' (days),
+/// 'w' (weeks) and 'y' (years of 365 days).
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Period(Duration);
+
+impl Retention {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let retention = read_to_string(path)?;
+        let retention = toml::from_str::<Self>(&retention)?;
+
+        retention.validate()?;
+
+        Ok(retention)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if *self.purge_interval <= Duration::zero() {
+            return Err(Error::Generic(
+                "Invalid retention: Purge interval must be positive!".into(),
+            ));
+        }
+
+        let legal_minimum = Duration::days(LEGAL_AUDIT_EVENT_RETENTION_DAYS);
+        if *self.audit_events.retention < legal_minimum && !self.audit_events.ignore_legal_minimum {
+            return Err(Error::Generic(format!(
+                "Invalid retention: Audit events must be kept for at least {} days!",
+                LEGAL_AUDIT_EVENT_RETENTION_DAYS
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            purge_interval: Period(Duration::seconds(60)),
+            tasks: Default::default(),
+            medication_dispenses: Default::default(),
+            communications: Default::default(),
+            audit_events: Default::default(),
+        }
+    }
+}
+
+impl Default for TaskRetention {
+    fn default() -> Self {
+        Self {
+            draft: Period(Duration::days(1)),
+            ready: Period(Duration::days(10)),
+            in_progress: Period(Duration::days(100)),
+            completed: Period(Duration::days(100)),
+            cancelled: Period(Duration::days(10)),
+        }
+    }
+}
+
+impl Default for MedicationDispenseRetention {
+    fn default() -> Self {
+        Self {
+            retention: Period(Duration::days(100)),
+        }
+    }
+}
+
+impl Default for AuditEventRetention {
+    fn default() -> Self {
+        Self {
+            retention: Period(Duration::days(LEGAL_AUDIT_EVENT_RETENTION_DAYS)),
+            ignore_legal_minimum: false,
+        }
+    }
+}
+
+impl Deref for Period {
+    type Target = Duration;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let unit = s.len() - s.trim_end_matches(|c: char| c.is_ascii_alphabetic()).len();
+        let (value, unit) = s.split_at(s.len() - unit);
+
+        let value = value
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| format!("Invalid period: {}", s))?;
+
+        let duration = match unit {
+            "s" => Duration::seconds(value),
+            "m" => Duration::minutes(value),
+            "h" => Duration::hours(value),
+            "d" => Duration::days(value),
+            "w" => Duration::weeks(value),
+            "y" => Duration::days(365 * value),
+            _ => return Err(format!("Invalid period unit: {}", s)),
+        };
+
+        Ok(Self(duration))
+    }
+}
+
+impl TryFrom<String> for Period {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_period() {
+        assert_eq!(Period::from_str("30s"), Ok(Period(Duration::seconds(30))));
+        assert_eq!(Period::from_str("5m"), Ok(Period(Duration::minutes(5))));
+        assert_eq!(Period::from_str("12h"), Ok(Period(Duration::hours(12))));
+        assert_eq!(Period::from_str("10 d"), Ok(Period(Duration::days(10))));
+        assert_eq!(Period::from_str("2w"), Ok(Period(Duration::weeks(2))));
+        assert_eq!(Period::from_str("3y"), Ok(Period(Duration::days(3 * 365))));
+
+        assert!(Period::from_str("10").is_err());
+        assert!(Period::from_str("d").is_err());
+        assert!(Period::from_str("10x").is_err());
+    }
+
+    #[test]
+    fn parse_retention() {
+        let retention = toml::from_str::<Retention>(
+            r##"
+            [tasks]
+            completed = "30d"
+
+            [communications]
+            retention = "14d"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(*retention.tasks.completed, Duration::days(30));
+        assert_eq!(*retention.tasks.cancelled, Duration::days(10));
+        assert_eq!(
+            retention.communications.retention,
+            Some(Period(Duration::days(14)))
+        );
+        assert!(retention.validate().is_ok());
+    }
+
+    #[test]
+    fn legal_minimum_of_audit_events() {
+        let mut retention = toml::from_str::<Retention>(
+            r##"
+            [audit_events]
+            retention = "1m"
+            "##,
+        )
+        .unwrap();
+
+        assert!(retention.validate().is_err());
+
+        retention.audit_events.ignore_legal_minimum = true;
+
+        assert!(retention.validate().is_ok());
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::ffi::OsString;
+use std::fs::{rename, File};
+use std::path::{Path, PathBuf};
+
+use log::error;
+use tokio::{
+    spawn,
+    time::{delay_for, Duration},
+};
+
+use crate::error::Error;
+
+use super::{Inner, State};
+
+impl State {
+    /// Spawn a task that writes a snapshot of the state to the passed file
+    /// each time the passed interval has elapsed.
+    pub fn spawn_snapshot_task(&self, path: PathBuf, interval: Duration) {
+        let state = self.clone();
+
+        spawn(snapshot_task(state, path, interval));
+    }
+}
+
+impl Inner {
+    /// Write a snapshot of the state to the passed file.
+    ///
+    /// The snapshot is written to a temporary file first, that is renamed
+    /// afterwards, so the file always contains a complete state. Once the
+    /// snapshot is written, the attached storage is compacted.
+    pub fn snapshot(&mut self, path: &Path) -> Result<(), Error> {
+        let mut tmp: OsString = path.into();
+        tmp.push(".tmp");
+
+        let file = File::create(&tmp)?;
+        self.save(&file)?;
+        file.sync_all()?;
+
+        rename(&tmp, path)?;
+
+        if let Some(storage) = self.storage().as_mut() {
+            storage.compact()?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn snapshot_task(state: State, path: PathBuf, interval: Duration) {
+    loop {
+        delay_for(interval).await;
+
+        if let Err(err) = state.lock().await.snapshot(&path) {
+            error!("Unable to write snapshot of state: {}", err);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use log::warn;
+use miscellaneous::envelope::Envelope;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_slice, to_vec};
+
+use crate::error::Error;
+
+use super::{Change, Storage, Transaction};
+
+/// Append-only journal that contains all transactions since the last
+/// snapshot of the state was written.
+///
+/// Each transaction is written as a single line of JSON and synced to disk
+/// before the commit returns. If a key is passed, each transaction is
+/// encrypted using this key.
+pub struct Journal {
+    file: File,
+    key: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Encrypted(Envelope),
+    Plain(Transaction),
+}
+
+impl Journal {
+    pub fn open<P>(path: P, key: Option<Vec<u8>>) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        Ok(Self { file, key })
+    }
+}
+
+impl Storage for Journal {
+    fn load(&mut self) -> Result<Vec<Change>, Error> {
+        self.file.seek(SeekFrom::Start(0))?;
+
+        let mut reader = BufReader::new(&self.file);
+        let mut changes = Vec::new();
+        let mut line = Vec::new();
+        let mut len = 0;
+
+        loop {
+            line.clear();
+
+            let n = reader.read_until(b'\n', &mut line)?;
+            if n == 0 {
+                break;
+            }
+
+            if line.last() != Some(&b'\n') {
+                // The last transaction was not written completely, so it was
+                // never committed. Remove it to be able to append new ones.
+                warn!("Dropping incomplete transaction at the end of the journal");
+
+                self.file.set_len(len)?;
+
+                break;
+            }
+
+            let transaction = match from_slice(&line)? {
+                Line::Plain(transaction) => transaction,
+                Line::Encrypted(envelope) => {
+                    let key = self.key.as_ref().ok_or_else(|| {
+                        Error::Generic(
+                            "Journal is encrypted, but no state key was provided!".into(),
+                        )
+                    })?;
+
+                    from_slice(&envelope.open(key)?)?
+                }
+            };
+            changes.extend(transaction.changes);
+
+            len += n as u64;
+        }
+
+        Ok(changes)
+    }
+
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error> {
+        let line = match &self.key {
+            Some(key) => Line::Encrypted(Envelope::seal(key, &to_vec(&transaction)?)?),
+            None => Line::Plain(transaction),
+        };
+
+        let mut line = to_vec(&line)?;
+        line.push(b'\n');
+
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+
+        Ok(())
+    }
+
+    fn compact(&mut self) -> Result<(), Error> {
+        self.file.set_len(0)?;
+        self.file.sync_all()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+mod journal;
+mod sqlite;
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use std::sync::MutexGuard;
+
+use log::info;
+use resources::primitives::Id;
+use serde::{Deserialize, Serialize};
+use serde_json::to_string;
+
+use crate::error::Error;
+
+use super::Inner;
+
+pub use journal::Journal;
+pub use sqlite::Sqlite;
+
+/// Backend that is used to persist the resources of the service.
+///
+/// All changes that are made while the state is locked are collected in a
+/// single `Transaction` and committed to the storage as soon as the lock is
+/// released. So each workflow operation is stored atomically.
+pub trait Storage: Send {
+    /// Load the changes that need to be applied to the state to restore
+    /// the data stored in the backend.
+    fn load(&mut self) -> Result<Vec<Change>, Error>;
+
+    /// Apply all changes of the passed transaction atomically.
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error>;
+
+    /// Called after a snapshot of the whole state was written successfully.
+    /// Backends that only record the changes since the last snapshot
+    /// may drop them.
+    fn compact(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Kind {
+    Task,
+    EPrescription,
+    PatientReceipt,
+    ErxReceipt,
+    Communication,
+    Binary,
+    MedicationDispense,
+    ChargeItem,
+    AuditEvent,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub kind: Kind,
+    pub id: String,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Change {
+    Put(Record),
+    Delete { kind: Kind, id: String },
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct Transaction {
+    pub changes: Vec<Change>,
+}
+
+impl Inner {
+    /// Attach the passed storage to the state.
+    ///
+    /// All changes that are stored in the storage are applied to the state.
+    /// Returns the number of applied changes.
+    pub fn attach_storage(&mut self, mut storage: Box<dyn Storage>) -> Result<usize, Error> {
+        let changes = storage.load()?;
+        let count = changes.len();
+
+        for change in changes {
+            self.apply(change)?;
+        }
+
+        self.rebuild_timeouts();
+        self.discard_changes();
+        *self.storage() = Some(storage);
+
+        info!("Applied {} changes from storage", count);
+
+        Ok(count)
+    }
+
+    /// Write all changes since the last commit to the attached storage.
+    pub fn commit(&mut self) -> Result<(), Error> {
+        if self.storage().is_none() {
+            self.discard_changes();
+
+            return Ok(());
+        }
+
+        let transaction = self.take_transaction()?;
+        if transaction.is_empty() {
+            return Ok(());
+        }
+
+        self.storage().as_mut().unwrap().commit(transaction)
+    }
+
+    /// Write all changes of the audit events since the last commit to the
+    /// attached storage.
+    ///
+    /// This is used to commit the audit events that were recorded while the
+    /// state was only locked for reading.
+    pub fn commit_audit_events(&self) -> Result<(), Error> {
+        let mut storage = self.storage();
+        let storage = match storage.as_mut() {
+            Some(storage) => storage,
+            None => {
+                self.audit_events.take_changes();
+
+                return Ok(());
+            }
+        };
+
+        let mut transaction = Transaction::default();
+        self.take_audit_event_changes(&mut transaction)?;
+        if transaction.is_empty() {
+            return Ok(());
+        }
+
+        storage.commit(transaction)
+    }
+
+    pub(super) fn storage(&self) -> MutexGuard<'_, Option<Box<dyn Storage>>> {
+        self.storage.lock().expect("Storage lock is poisoned!")
+    }
+}
+
+impl Transaction {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn put<T>(&mut self, kind: Kind, id: &Id, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let record = Record {
+            kind,
+            id: id.to_string(),
+            data: to_string(value)?,
+        };
+
+        self.changes.push(Change::Put(record));
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, kind: Kind, id: &Id) {
+        self.changes.push(Change::Delete {
+            kind,
+            id: id.to_string(),
+        });
+    }
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Task => "Task",
+            Self::EPrescription => "EPrescription",
+            Self::PatientReceipt => "PatientReceipt",
+            Self::ErxReceipt => "ErxReceipt",
+            Self::Communication => "Communication",
+            Self::Binary => "Binary",
+            Self::MedicationDispense => "MedicationDispense",
+            Self::ChargeItem => "ChargeItem",
+            Self::AuditEvent => "AuditEvent",
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Kind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Task" => Ok(Self::Task),
+            "EPrescription" => Ok(Self::EPrescription),
+            "PatientReceipt" => Ok(Self::PatientReceipt),
+            "ErxReceipt" => Ok(Self::ErxReceipt),
+            "Communication" => Ok(Self::Communication),
+            "Binary" => Ok(Self::Binary),
+            "MedicationDispense" => Ok(Self::MedicationDispense),
+            "ChargeItem" => Ok(Self::ChargeItem),
+            "AuditEvent" => Ok(Self::AuditEvent),
+            s => Err(Error::Generic(format!("Unknown record kind: {}", s))),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::error::Error;
+
+use super::{Change, Record, Storage, Transaction};
+
+/// Storage that keeps all resources in a SQLite database.
+pub struct Sqlite {
+    connection: Connection,
+}
+
+impl Sqlite {
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = FULL;
+             CREATE TABLE IF NOT EXISTS resources (
+                 kind TEXT NOT NULL,
+                 id TEXT NOT NULL,
+                 data TEXT NOT NULL,
+                 PRIMARY KEY (kind, id)
+             );",
+        )?;
+
+        Ok(Self { connection })
+    }
+}
+
+impl Storage for Sqlite {
+    fn load(&mut self) -> Result<Vec<Change>, Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT kind, id, data FROM resources")?;
+        let rows = statement.query_map(params![], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        let mut changes = Vec::new();
+        for row in rows {
+            let (kind, id, data) = row?;
+
+            changes.push(Change::Put(Record {
+                kind: kind.parse()?,
+                id,
+                data,
+            }));
+        }
+
+        Ok(changes)
+    }
+
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error> {
+        let tx = self.connection.transaction()?;
+
+        for change in transaction.changes {
+            match change {
+                Change::Put(record) => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO resources (kind, id, data) VALUES (?1, ?2, ?3)",
+                        params![record.kind.as_str(), record.id, record.data],
+                    )?;
+                }
+                Change::Delete { kind, id } => {
+                    tx.execute(
+                        "DELETE FROM resources WHERE kind = ?1 AND id = ?2",
+                        params![kind.as_str(), id],
+                    )?;
+                }
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+}
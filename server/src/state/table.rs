@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::collections::hash_map::{Entry, HashMap, Iter, Values};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::mem::take;
+
+/// Map that keeps track of all keys that were changed since the changes
+/// were taken the last time. This is used to write only the modified
+/// resources to the storage backend.
+pub struct Table<K, V> {
+    items: HashMap<K, V>,
+    changed: HashSet<K>,
+}
+
+impl<K, V> Default for Table<K, V> {
+    fn default() -> Self {
+        Self {
+            items: HashMap::new(),
+            changed: HashSet::new(),
+        }
+    }
+}
+
+impl<K, V> Table<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.items.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let value = self.items.get_mut(key)?;
+
+        self.changed.insert(key.clone());
+
+        Some(value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.items.contains_key(key)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        self.changed.insert(key.clone());
+
+        self.items.entry(key)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.changed.insert(key.clone());
+
+        self.items.insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.items.remove(key)?;
+
+        self.changed.insert(key.clone());
+
+        Some(value)
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let Self { items, changed } = self;
+
+        items.retain(|key, value| {
+            let keep = f(key, value);
+
+            if !keep {
+                changed.insert(key.clone());
+            }
+
+            keep
+        });
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.items.iter()
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        self.items.values()
+    }
+
+    /// Take the keys of all values that were changed since the last call.
+    pub fn take_changes(&mut self) -> HashSet<K> {
+        take(&mut self.changed)
+    }
+}
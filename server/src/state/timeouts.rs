@@ -16,32 +16,47 @@
  */
 
 use std::mem::swap;
+use std::sync::{Mutex, MutexGuard};
 
-use chrono::{naive::NaiveDate, DateTime, Duration, Utc};
+use chrono::{naive::NaiveDate, DateTime, Utc};
 use tokio::{
     spawn,
     time::{delay_for, Duration as TokioDuration},
 };
 
-use resources::{primitives::Id, task::Status, AuditEvent, MedicationDispense, Task};
+use resources::{
+    primitives::Id, task::Status, AuditEvent, Communication, MedicationDispense, Task,
+};
 
-use super::{Inner, State};
+use super::{Clock, Inner, Retention, State};
 
 #[derive(Debug)]
 pub enum ResourceId {
     Task(Id),
     AuditEvent(Id),
     MedicationDispense(Id),
+    Communication(Id),
 }
 
+/// Queue of the resources that will time out.
+///
+/// Requests that only read the state may record audit events, which are
+/// added to the queue, so the queue is guarded by its own lock.
 #[derive(Default)]
 pub struct Timeouts {
-    items: Vec<Item>,
+    items: Mutex<Vec<Item>>,
+    retention: Retention,
+    clock: Clock,
 }
 
 pub trait TimeoutResource {
     fn id(&self) -> ResourceId;
-    fn timeout(&self) -> DateTime<Utc>;
+
+    /// Point in time the resource expires at, or `None` if the resource is
+    /// kept until it is removed together with the resource it belongs to.
+    ///
+    /// `now` is used as timestamp of resources that do not have one.
+    fn timeout(&self, retention: &Retention, now: DateTime<Utc>) -> Option<DateTime<Utc>>;
 }
 
 struct Item {
@@ -50,42 +65,61 @@ struct Item {
 }
 
 impl Timeouts {
-    pub fn insert<R>(&mut self, resource: &R)
+    pub fn insert<R>(&self, resource: &R)
     where
         R: TimeoutResource,
     {
+        let timeout = match self.timeout(resource) {
+            Some(timeout) => timeout,
+            None => return,
+        };
+
         let item = Item {
             id: resource.id(),
-            timeout: resource.timeout(),
+            timeout,
         };
 
-        let index = match self
-            .items
-            .binary_search_by(|i| i.timeout.cmp(&item.timeout))
-        {
+        let mut items = self.items();
+        let index = match items.binary_search_by(|i| i.timeout.cmp(&item.timeout)) {
             Ok(index) => index,
             Err(index) => index,
         };
 
-        self.items.insert(index, item);
+        items.insert(index, item);
+    }
+
+    pub fn timeout<R>(&self, resource: &R) -> Option<DateTime<Utc>>
+    where
+        R: TimeoutResource,
+    {
+        resource.timeout(&self.retention, self.clock.now())
+    }
+
+    pub fn retention(&self) -> &Retention {
+        &self.retention
     }
 
-    fn split_of_timeouts(&mut self, now: &DateTime<Utc>) -> Vec<Item> {
-        let mut index = match self.items.binary_search_by_key(now, |i| i.timeout) {
+    fn split_of_timeouts(&self, now: &DateTime<Utc>) -> Vec<Item> {
+        let mut items = self.items();
+        let mut index = match items.binary_search_by_key(now, |i| i.timeout) {
             Ok(index) => index,
             Err(index) => index,
         };
 
-        while index < self.items.len() && now <= &self.items[index].timeout {
+        while index < items.len() && &items[index].timeout <= now {
             index += 1;
         }
 
-        let mut tail = self.items.split_off(index);
+        let mut tail = items.split_off(index);
 
-        swap(&mut tail, &mut self.items);
+        swap(&mut tail, &mut *items);
 
         tail
     }
+
+    fn items(&self) -> MutexGuard<'_, Vec<Item>> {
+        self.items.lock().expect("Timeouts lock is poisoned!")
+    }
 }
 
 impl State {
@@ -97,8 +131,56 @@ impl State {
 }
 
 impl Inner {
-    fn progress_timeouts(&mut self) {
-        let now = Utc::now();
+    /// Set the retention policies and requeue all resources of the state.
+    pub fn set_retention(&mut self, retention: Retention) {
+        self.timeouts.retention = retention;
+
+        self.rebuild_timeouts();
+    }
+
+    /// Add all resources of the state to the timeout queue.
+    ///
+    /// Draft tasks are skipped, because they are not queued when they are
+    /// created either.
+    pub(super) fn rebuild_timeouts(&mut self) {
+        let Self {
+            ref tasks,
+            ref audit_events,
+            ref medication_dispenses,
+            ref communications,
+            ref timeouts,
+            ..
+        } = self;
+
+        timeouts.items().clear();
+
+        for task_meta in tasks.iter() {
+            if task_meta.task.status != Status::Draft {
+                timeouts.insert(&task_meta.task);
+            }
+        }
+
+        for audit_event in audit_events.to_vec() {
+            timeouts.insert(&audit_event);
+        }
+
+        for medication_dispense in medication_dispenses.iter() {
+            timeouts.insert(medication_dispense);
+        }
+
+        for communication in communications.iter() {
+            timeouts.insert(communication);
+        }
+    }
+
+    /// Remove all resources from the state that are expired.
+    ///
+    /// Audit events are not removed together with the task they belong to,
+    /// they are kept until their own retention period is expired. The
+    /// retention of audit events is validated against the legal minimum
+    /// when it is loaded (see `Retention::validate`).
+    pub fn progress_timeouts(&mut self) {
+        let now = self.clock.now();
         let items = self.timeouts.split_of_timeouts(&now);
         let ids = items.into_iter().map(|i| i.id);
 
@@ -110,7 +192,7 @@ impl Inner {
                         None => continue,
                     };
 
-                    if task_meta.task.timeout() > now {
+                    if !is_expired(self.timeouts.timeout(&task_meta.task), &now) {
                         continue;
                     }
 
@@ -122,7 +204,7 @@ impl Inner {
                         None => continue,
                     };
 
-                    if audit_event.timeout() > now {
+                    if !is_expired(self.timeouts.timeout(&audit_event), &now) {
                         continue;
                     }
 
@@ -134,12 +216,27 @@ impl Inner {
                         None => continue,
                     };
 
-                    if md.timeout() > now {
+                    if !is_expired(self.timeouts.timeout(md), &now) {
                         continue;
                     }
 
                     self.medication_dispense_delete_by_id(&id);
                 }
+                ResourceId::Communication(id) => {
+                    let communication = match self.communications.get_by_id(&id) {
+                        Some(communication) => communication,
+                        None => continue,
+                    };
+
+                    if !is_expired(self.timeouts.timeout(communication), &now) {
+                        continue;
+                    }
+
+                    if let Some(communication) = self.communications.remove_by_id(&id) {
+                        self.communications
+                            .release_binary(&communication, &mut self.binaries);
+                    }
+                }
             }
         }
     }
@@ -156,10 +253,10 @@ impl TimeoutResource for MedicationDispense {
         ResourceId::MedicationDispense(id)
     }
 
-    fn timeout(&self) -> DateTime<Utc> {
+    fn timeout(&self, retention: &Retention, _now: DateTime<Utc>) -> Option<DateTime<Utc>> {
         let date: DateTime<Utc> = self.when_handed_over.clone().into();
 
-        date + Duration::days(100)
+        Some(date + *retention.medication_dispenses.retention)
     }
 }
 
@@ -170,8 +267,27 @@ impl TimeoutResource for AuditEvent {
         ResourceId::AuditEvent(id)
     }
 
-    fn timeout(&self) -> DateTime<Utc> {
-        *self.recorded + Duration::days(3 * 365)
+    fn timeout(&self, retention: &Retention, _now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        Some(*self.recorded + *retention.audit_events.retention)
+    }
+}
+
+impl TimeoutResource for Communication {
+    fn id(&self) -> ResourceId {
+        let id = self
+            .id()
+            .as_ref()
+            .expect("Communication without Id!")
+            .clone();
+
+        ResourceId::Communication(id)
+    }
+
+    fn timeout(&self, retention: &Retention, _now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let retention = retention.communications.retention?;
+        let sent = self.sent().clone().map(DateTime::<Utc>::from)?;
+
+        Some(sent + *retention)
     }
 }
 
@@ -182,28 +298,88 @@ impl TimeoutResource for Task {
         ResourceId::Task(id)
     }
 
-    fn timeout(&self) -> DateTime<Utc> {
-        match self.status {
-            Status::Draft => Utc::now() + Duration::days(1),
+    fn timeout(&self, retention: &Retention, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let retention = &retention.tasks;
+        let authored_on = self.authored_on.clone().map(DateTime::<Utc>::from);
+        let last_modified = self.last_modified.clone().map(DateTime::<Utc>::from);
+        let last_modified = last_modified.or(authored_on).unwrap_or(now);
+
+        let timeout = match self.status {
+            Status::Draft => authored_on.unwrap_or(now) + *retention.draft,
             Status::Ready => {
                 let date = self.extension.expiry_date.as_ref().unwrap();
                 let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").unwrap();
-                let date = date.and_hms(0, 0, 0) + Duration::days(10);
+                let date = date.and_hms(0, 0, 0) + *retention.ready;
 
                 DateTime::from_utc(date, Utc)
             }
-            Status::Cancelled => Utc::now() + Duration::days(10),
-            Status::InProgress => Utc::now() + Duration::days(100),
-            Status::Completed => Utc::now() + Duration::days(100),
+            Status::Cancelled => last_modified + *retention.cancelled,
+            Status::InProgress => last_modified + *retention.in_progress,
+            Status::Completed => last_modified + *retention.completed,
             _ => unreachable!("Invalid Task Status"),
-        }
+        };
+
+        Some(timeout)
     }
 }
 
+fn is_expired(timeout: Option<DateTime<Utc>>, now: &DateTime<Utc>) -> bool {
+    matches!(timeout, Some(timeout) if &timeout <= now)
+}
+
 async fn timeout_task(state: State) {
     loop {
-        state.lock().await.progress_timeouts();
+        let interval = {
+            let mut state = state.lock().await;
+            state.progress_timeouts();
+
+            state
+                .timeouts
+                .retention()
+                .purge_interval
+                .to_std()
+                .unwrap_or_else(|_| TokioDuration::from_secs(60))
+        };
+
+        delay_for(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryFrom;
+
+    use chrono::Duration;
+
+    #[test]
+    fn split_of_timeouts() {
+        let now = Utc::now();
+
+        let timeouts = Timeouts::default();
+        for offset in &[-2, -1, 0, 1, 2] {
+            timeouts.items().push(Item {
+                id: ResourceId::Task(Id::try_from("task").unwrap()),
+                timeout: now + Duration::days(*offset),
+            });
+        }
 
-        delay_for(TokioDuration::from_secs(60)).await;
+        let expired = timeouts.split_of_timeouts(&now);
+        let expired = expired.iter().map(|i| i.timeout).collect::<Vec<_>>();
+        let pending = timeouts
+            .items()
+            .iter()
+            .map(|i| i.timeout)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            expired,
+            vec![now - Duration::days(2), now - Duration::days(1), now]
+        );
+        assert_eq!(
+            pending,
+            vec![now + Duration::days(1), now + Duration::days(2)]
+        );
     }
 }
@@ -21,6 +21,7 @@ mod create_access_token;
 mod misc;
 mod pkcs7_sign;
 mod pkcs7_verify;
+mod state;
 mod vau_decrypt;
 mod vau_encrypt;
 mod x509;
@@ -32,6 +33,7 @@ use aes_encrypt::{execute as aes_encrypt, Opts as AesEncryptOpts};
 use create_access_token::{execute as create_access_token, Opts as CreateAccessTokenOpts};
 use pkcs7_sign::{execute as pkcs7_sign, Opts as Pkcs7SignOpts};
 use pkcs7_verify::{execute as pkcs7_verify, Opts as Pkcs7VerifyOpts};
+use state::{execute as state, Opts as StateOpts};
 use vau_decrypt::{execute as vau_decrypt, Opts as VauDecryptOpts};
 use vau_encrypt::{execute as vau_encrypt, Opts as VauEncryptOpts};
 use x509::{execute as x509, Opts as X509Opts};
@@ -45,6 +47,7 @@ fn main() {
         Command::CreateAccessToken(opts) => create_access_token(opts),
         Command::Pkcs7Sign(opts) => pkcs7_sign(opts),
         Command::Pkcs7Verify(opts) => pkcs7_verify(opts),
+        Command::State(opts) => state(opts),
         Command::VauDecrypt(opts) => vau_decrypt(opts),
         Command::VauEncrypt(opts) => vau_encrypt(opts),
         Command::X509(opts) => x509(opts),
@@ -58,6 +61,7 @@ enum Command {
     CreateAccessToken(CreateAccessTokenOpts),
     Pkcs7Sign(Pkcs7SignOpts),
     Pkcs7Verify(Pkcs7VerifyOpts),
+    State(StateOpts),
     VauDecrypt(VauDecryptOpts),
     VauEncrypt(VauEncryptOpts),
     X509(X509Opts),
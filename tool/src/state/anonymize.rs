@@ -0,0 +1,264 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde_json::{json, to_vec, Value};
+use structopt::StructOpt;
+
+use crate::misc::write_output;
+
+use super::{items, read_state};
+
+#[derive(StructOpt)]
+/// Tool to anonymize the state file of the FD service.
+///
+/// Replaces the KVNRs and the names of the patients with generated values, so that the state
+/// could be shared for debugging purposes. The same KVNR is always replaced by the same value, so
+/// the references between the resources are kept. The signatures of the modified receipts are no
+/// longer valid after the state was anonymized.
+///
+/// The signed e-prescriptions can not be modified without breaking their signature, so they are
+/// removed from the state unless '--keep-e-prescriptions' is passed.
+///
+/// The binaries hold the attachments of communications. They are free text that can not be
+/// anonymized reliably, so they are always removed from the state. The references of the
+/// attachments to them are kept.
+pub struct Opts {
+    /// File path of the state to anonymize.
+    ///
+    /// Path to the file that contains the state. If this parameter is not passed, the state is
+    /// read from stdin.
+    #[structopt(short, long)]
+    input: Option<PathBuf>,
+
+    /// File path to store the anonymized state at.
+    ///
+    /// Path to the file the anonymized state is stored at. If this parameter is not passed, the
+    /// anonymized state is written to stdout.
+    #[structopt(short, long)]
+    output: Option<PathBuf>,
+
+    /// Keep the signed e-prescriptions in the state.
+    #[structopt(long)]
+    keep_e_prescriptions: bool,
+}
+
+pub fn execute(opts: Opts) {
+    let (mut state, _) = read_state(&opts.input);
+
+    let count = anonymize(&mut state, opts.keep_e_prescriptions);
+
+    eprintln!("Replaced {} KVNR(s)", count);
+
+    let output = to_vec(&state).expect("Unable to serialize state");
+
+    write_output(&opts.output, &output);
+}
+
+/// Anonymize the passed state and return the number of replaced KVNRs.
+fn anonymize(state: &mut Value, keep_e_prescriptions: bool) -> usize {
+    let kvnrs = collect_kvnrs(state);
+
+    if !keep_e_prescriptions {
+        state["e_prescriptions"] = json!([]);
+    }
+
+    state["binaries"] = json!([]);
+
+    replace_kvnrs(state, &kvnrs);
+    replace_names(state);
+
+    kvnrs.len()
+}
+
+/// Collect all KVNRs of the state and assign a generated KVNR to each of them.
+fn collect_kvnrs(state: &Value) -> HashMap<String, String> {
+    let tasks = items(state, "tasks").filter_map(|task| task.pointer("/task/for_"));
+    let patients = items(state, "patient_receipts")
+        .filter_map(|receipt| receipt.pointer("/entry/patient/1/identifier"))
+        .filter_map(Value::as_object)
+        .flat_map(|identifier| identifier.values())
+        .filter_map(|identifier| identifier.get("value"));
+    let medication_dispenses = items(state, "medication_dispenses")
+        .filter_map(|medication_dispense| medication_dispense.get("subject"));
+    let charge_items =
+        items(state, "charge_items").filter_map(|charge_item| charge_item.get("subject"));
+    let audit_events = items(state, "audit_events")
+        .filter_map(|audit_event| audit_event.pointer("/agent/who/Kvnr"));
+    let communications = items(state, "communications")
+        .filter_map(Value::as_object)
+        .flatten()
+        .flat_map(|(type_, communication)| {
+            let fields: &[&str] = match type_.as_str() {
+                "InfoReq" | "DispenseReq" => &["sender"],
+                "Reply" => &["recipient"],
+                "Representative" => &["sender", "recipient"],
+                _ => &[],
+            };
+
+            fields
+                .iter()
+                .filter_map(move |field| communication.get(*field))
+        });
+
+    let mut kvnrs = HashMap::new();
+    for kvnr in tasks
+        .chain(patients)
+        .chain(medication_dispenses)
+        .chain(charge_items)
+        .chain(audit_events)
+        .chain(communications)
+        .filter_map(Value::as_str)
+    {
+        if !kvnrs.contains_key(kvnr) {
+            let anonymized = format!("X{:09}", kvnrs.len() + 1);
+
+            kvnrs.insert(kvnr.to_owned(), anonymized);
+        }
+    }
+
+    kvnrs
+}
+
+/// Replace each string of the state that matches a KVNR with the generated one.
+fn replace_kvnrs(value: &mut Value, kvnrs: &HashMap<String, String>) {
+    match value {
+        Value::String(s) => {
+            if let Some(kvnr) = kvnrs.get(s.as_str()) {
+                *s = kvnr.clone();
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                replace_kvnrs(value, kvnrs);
+            }
+        }
+        Value::Object(values) => {
+            for value in values.values_mut() {
+                replace_kvnrs(value, kvnrs);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Replace the names of the patients and of the insured persons that are part of an audit event.
+fn replace_names(state: &mut Value) {
+    for (index, receipt) in items_mut(state, "patient_receipts").enumerate() {
+        if let Some(name) = receipt.pointer_mut("/entry/patient/1/name") {
+            let given = "Max".to_owned();
+            let family = format!("Mustermann{}", index + 1);
+
+            *name = json!({
+                "given": given,
+                "family": {
+                    "value": format!("{} {}", given, family),
+                    "prefix": null,
+                    "family": family,
+                    "extension": null
+                },
+                "prefix": null
+            });
+        }
+    }
+
+    for audit_event in items_mut(state, "audit_events") {
+        let is_kvnr = audit_event.pointer("/agent/who/Kvnr").is_some();
+
+        if let Some(name) = audit_event.pointer_mut("/agent/name").filter(|_| is_kvnr) {
+            *name = json!("Max Mustermann");
+        }
+    }
+}
+
+fn items_mut<'a>(state: &'a mut Value, key: &str) -> impl Iterator<Item = &'a mut Value> {
+    state
+        .get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::to_string;
+
+    #[test]
+    fn anonymize_state() {
+        let (mut state, _) = read_state(&Some("./examples/state.json".into()));
+
+        assert_eq!(anonymize(&mut state, false), 4);
+
+        let content = to_string(&state).unwrap();
+        for original in &[
+            "X111111111",
+            "X222222222",
+            "X333333333",
+            "X444444444",
+            "Erika",
+            "Musterfrau",
+        ] {
+            assert!(!content.contains(original), "{}", original);
+        }
+
+        // The same KVNR is replaced by the same value
+        assert_eq!(state.pointer("/tasks/0/task/for_").unwrap(), "X000000001");
+        assert_eq!(state.pointer("/tasks/1/task/for_").unwrap(), "X000000002");
+        assert_eq!(
+            state.pointer("/medication_dispenses/0/subject").unwrap(),
+            "X000000001"
+        );
+        assert_eq!(
+            state.pointer("/patient_receipts/0/entry/patient/1/identifier/Gkv/value"),
+            Some(&json!("X000000001"))
+        );
+        assert_eq!(
+            state.pointer("/audit_events/0/agent/name").unwrap(),
+            "Max Mustermann"
+        );
+
+        // KVNRs that are only part of a communication are replaced as well
+        assert_eq!(
+            state
+                .pointer("/communications/2/Representative/sender")
+                .unwrap(),
+            "X000000001"
+        );
+        assert_eq!(
+            state
+                .pointer("/communications/2/Representative/recipient")
+                .unwrap(),
+            "X000000004"
+        );
+
+        assert_eq!(state["e_prescriptions"], json!([]));
+        assert_eq!(state["binaries"], json!([]));
+    }
+
+    #[test]
+    fn anonymize_state_keep_e_prescriptions() {
+        let (mut state, _) = read_state(&Some("./examples/state.json".into()));
+
+        anonymize(&mut state, true);
+
+        assert_eq!(items(&state, "e_prescriptions").count(), 1);
+    }
+}
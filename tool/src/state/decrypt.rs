@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::fs::{read, read_to_string};
+use std::path::PathBuf;
+
+use miscellaneous::envelope::{derive_key, Envelope, LABEL_STATE};
+use openssl::ec::EcKey;
+use serde_json::from_slice;
+use structopt::StructOpt;
+use vau::hex_decode;
+
+use crate::misc::{read_input, write_output};
+
+#[derive(StructOpt)]
+/// Tool to decrypt the state file or the journal of the FD service.
+///
+/// The FD service is able to encrypt its state file and journal using AES-256-GCM. This tool is
+/// used to decrypt them for debugging purposes. Lines of the journal that are not encrypted are
+/// written to the output unchanged.
+pub struct Opts {
+    /// File path of the state key.
+    ///
+    /// Path to the file that contains the hex encoded key that was passed to the FD service using
+    /// the '--state-key' parameter.
+    #[structopt(short, long)]
+    key: Option<PathBuf>,
+
+    /// File path of the private encryption key of the FD service.
+    ///
+    /// Path to the file that contains the private key in PEM format that was passed to the FD
+    /// service using the '--enc-key' parameter. This is used if the state was encrypted with
+    /// the '--encrypt-state' parameter.
+    #[structopt(short, long)]
+    enc_key: Option<PathBuf>,
+
+    /// File path of the encrypted state or journal.
+    ///
+    /// Path to the file that contains the encrypted state or journal. If this parameter is not
+    /// passed, the encrypted state is read from stdin.
+    #[structopt(short, long)]
+    input: Option<PathBuf>,
+
+    /// File path to store the decrypted state at.
+    ///
+    /// Path to the file the decrypted state is stored at. If this parameter is not passed, the
+    /// decrypted state is written to stdout.
+    #[structopt(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn execute(opts: Opts) {
+    /* read input */
+    let input = read_input(&opts.input);
+
+    /* read key */
+    let key = match (opts.key, opts.enc_key) {
+        (Some(key), _) => {
+            let key = read_to_string(key).expect("Unable to read key");
+
+            hex_decode(key.trim()).expect("Invalid key")
+        }
+        (None, Some(enc_key)) => {
+            let enc_key = read(enc_key).expect("Unable to read encryption key");
+            let enc_key = EcKey::private_key_from_pem(&enc_key).expect("Unable to load key");
+
+            derive_key(LABEL_STATE, &enc_key.private_key().to_vec()).expect("Unable to derive key")
+        }
+        (None, None) => panic!("Either '--key' or '--enc-key' must be passed!"),
+    };
+
+    /* decrypt */
+    let output = match from_slice::<Envelope>(&input) {
+        Ok(envelope) => envelope.open(&key).expect("Unable to decrypt state"),
+        Err(_) => {
+            let mut output = Vec::new();
+
+            for line in input.split(|c| *c == b'\n').filter(|l| !l.is_empty()) {
+                match from_slice::<Envelope>(line) {
+                    Ok(envelope) => {
+                        let line = envelope.open(&key).expect("Unable to decrypt journal");
+
+                        output.extend_from_slice(&line);
+                    }
+                    Err(_) => output.extend_from_slice(line),
+                }
+
+                output.push(b'\n');
+            }
+
+            output
+        }
+    };
+
+    /* write output */
+    write_output(&opts.output, &output);
+}
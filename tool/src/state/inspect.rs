@@ -0,0 +1,146 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use miscellaneous::migration::CURRENT_VERSION;
+use serde_json::Value;
+use structopt::StructOpt;
+
+use super::{items, read_state};
+
+#[derive(StructOpt)]
+/// Tool to inspect the state file of the FD service.
+///
+/// Prints the number of stored resources. Tasks are grouped by their status and communications
+/// by their type.
+pub struct Opts {
+    /// File path of the state to inspect.
+    ///
+    /// Path to the file that contains the state. If this parameter is not passed, the state is
+    /// read from stdin.
+    #[structopt(short, long)]
+    input: Option<PathBuf>,
+}
+
+pub fn execute(opts: Opts) {
+    let (state, version) = read_state(&opts.input);
+
+    println!("Version: {} (stored as {})", CURRENT_VERSION, version);
+
+    print_counts(&state, "Tasks", "tasks", task_status);
+    print_counts(&state, "E-Prescriptions", "e_prescriptions", no_group);
+    print_counts(&state, "Patient Receipts", "patient_receipts", no_group);
+    print_counts(&state, "Erx Receipts", "erx_receipts", no_group);
+    print_counts(
+        &state,
+        "Communications",
+        "communications",
+        communication_type,
+    );
+    print_counts(
+        &state,
+        "Medication Dispenses",
+        "medication_dispenses",
+        no_group,
+    );
+    print_counts(&state, "Charge Items", "charge_items", no_group);
+    print_counts(&state, "Audit Events", "audit_events", no_group);
+}
+
+fn print_counts<F>(state: &Value, name: &str, key: &str, group: F)
+where
+    F: Fn(&Value) -> Option<&str>,
+{
+    let (total, groups) = count(state, key, group);
+
+    println!("{}: {}", name, total);
+
+    for (group, count) in groups {
+        println!("    {}: {}", group, count);
+    }
+}
+
+/// Count the items that are stored in the state with the passed key.
+///
+/// Returns the total number of items and the number of items per group.
+fn count<F>(state: &Value, key: &str, group: F) -> (usize, BTreeMap<String, usize>)
+where
+    F: Fn(&Value) -> Option<&str>,
+{
+    let mut total = 0;
+    let mut groups = BTreeMap::<String, usize>::new();
+
+    for item in items(state, key) {
+        total += 1;
+
+        if let Some(group) = group(item) {
+            *groups.entry(group.to_owned()).or_default() += 1;
+        }
+    }
+
+    (total, groups)
+}
+
+fn task_status(task: &Value) -> Option<&str> {
+    task.pointer("/task/status").and_then(Value::as_str)
+}
+
+fn communication_type(communication: &Value) -> Option<&str> {
+    communication
+        .as_object()
+        .and_then(|communication| communication.keys().next())
+        .map(String::as_str)
+}
+
+fn no_group(_: &Value) -> Option<&str> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_resources() {
+        let (state, version) = read_state(&Some("./examples/state.json".into()));
+        assert_eq!(version, 2);
+
+        let (total, groups) = count(&state, "tasks", task_status);
+        assert_eq!(total, 2);
+        assert_eq!(
+            groups.into_iter().collect::<Vec<_>>(),
+            vec![("Completed".into(), 1), ("Ready".into(), 1)]
+        );
+
+        let (total, groups) = count(&state, "communications", communication_type);
+        assert_eq!(total, 3);
+        assert_eq!(
+            groups.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("DispenseReq".into(), 1),
+                ("InfoReq".into(), 1),
+                ("Representative".into(), 1)
+            ]
+        );
+
+        let (total, groups) = count(&state, "charge_items", no_group);
+        assert_eq!(total, 0);
+        assert!(groups.is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::path::PathBuf;
+
+use miscellaneous::migration::CURRENT_VERSION;
+use serde_json::to_vec;
+use structopt::StructOpt;
+
+use crate::misc::write_output;
+
+use super::read_state;
+
+#[derive(StructOpt)]
+/// Tool to migrate the state file of the FD service to the current version.
+///
+/// The FD service migrates older states automatically when they are loaded. This tool can be
+/// used to migrate a state file without starting the service.
+pub struct Opts {
+    /// File path of the state to migrate.
+    ///
+    /// Path to the file that contains the state. If this parameter is not passed, the state is
+    /// read from stdin.
+    #[structopt(short, long)]
+    input: Option<PathBuf>,
+
+    /// File path to store the migrated state at.
+    ///
+    /// Path to the file the migrated state is stored at. If this parameter is not passed, the
+    /// migrated state is written to stdout.
+    #[structopt(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn execute(opts: Opts) {
+    let (state, version) = read_state(&opts.input);
+
+    eprintln!(
+        "Migrated state from version {} to version {}",
+        version, CURRENT_VERSION
+    );
+
+    let output = to_vec(&state).expect("Unable to serialize state");
+
+    write_output(&opts.output, &output);
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+mod anonymize;
+mod decrypt;
+mod inspect;
+mod migrate;
+mod validate;
+
+use std::path::PathBuf;
+
+use miscellaneous::migration::{is_encrypted, migrate as migrate_state};
+use serde_json::{from_slice, Value};
+use structopt::StructOpt;
+
+use crate::misc::read_input;
+
+use anonymize::{execute as anonymize, Opts as AnonymizeOpts};
+use decrypt::{execute as decrypt, Opts as DecryptOpts};
+use inspect::{execute as inspect, Opts as InspectOpts};
+use migrate::{execute as migrate, Opts as MigrateOpts};
+use validate::{execute as validate, Opts as ValidateOpts};
+
+#[derive(StructOpt)]
+/// Tools to work with the state file of the FD service.
+pub enum Opts {
+    Anonymize(AnonymizeOpts),
+    Decrypt(DecryptOpts),
+    Inspect(InspectOpts),
+    Migrate(MigrateOpts),
+    Validate(ValidateOpts),
+}
+
+pub fn execute(opts: Opts) {
+    match opts {
+        Opts::Anonymize(opts) => anonymize(opts),
+        Opts::Decrypt(opts) => decrypt(opts),
+        Opts::Inspect(opts) => inspect(opts),
+        Opts::Migrate(opts) => migrate(opts),
+        Opts::Validate(opts) => validate(opts),
+    }
+}
+
+/// Read the state from the passed input and migrate it to the current version.
+///
+/// Returns the state and the version it had before it was migrated.
+fn read_state(input: &Option<PathBuf>) -> (Value, usize) {
+    let input = read_input(input);
+    let mut state: Value = from_slice(&input).expect("Unable to parse state");
+
+    if is_encrypted(&state) {
+        panic!("State is encrypted, use 'state decrypt' to decrypt it first!");
+    }
+
+    let version = migrate_state(&mut state).expect("Unable to migrate state");
+
+    (state, version)
+}
+
+/// Get the items that are stored in the passed state with the passed key.
+fn items<'a>(state: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    state
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) 2021 gematik GmbH
+ * 
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ * 
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ * 
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ */
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::exit;
+
+use serde_json::Value;
+use structopt::StructOpt;
+
+use super::{items, read_state};
+
+#[derive(StructOpt)]
+/// Tool to validate the state file of the FD service.
+///
+/// Checks that all resources have a unique ID and that the references between the resources
+/// can be resolved, e.g. that the e-prescription referenced by the input of a task is part of
+/// the state. All detected problems are written to stdout.
+pub struct Opts {
+    /// File path of the state to validate.
+    ///
+    /// Path to the file that contains the state. If this parameter is not passed, the state is
+    /// read from stdin.
+    #[structopt(short, long)]
+    input: Option<PathBuf>,
+}
+
+pub fn execute(opts: Opts) {
+    let (state, _) = read_state(&opts.input);
+
+    let problems = find_problems(&state);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    if problems.is_empty() {
+        println!("State is valid");
+    } else {
+        println!("Found {} problem(s)", problems.len());
+
+        exit(1);
+    }
+}
+
+/// Check the IDs of the resources and the references between them.
+fn find_problems(state: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let tasks = collect_ids(state, "tasks", "/task/id", &mut problems);
+    let e_prescriptions = collect_ids(state, "e_prescriptions", "/0", &mut problems);
+    let patient_receipts = collect_ids(state, "patient_receipts", "/id", &mut problems);
+    let erx_receipts = collect_ids(state, "erx_receipts", "/id", &mut problems);
+    let prescription_ids = items(state, "tasks")
+        .filter_map(|task| get_str(task, "/task/identifier/prescription_id"))
+        .collect::<HashSet<_>>();
+
+    collect_ids(state, "medication_dispenses", "/id", &mut problems);
+    collect_ids(state, "audit_events", "/id", &mut problems);
+
+    for task in items(state, "tasks") {
+        let id = get_str(task, "/task/id").unwrap_or_default();
+
+        if let Some(e_prescription) = get_str(task, "/task/input/e_prescription") {
+            if !e_prescriptions.contains(e_prescription) {
+                problems.push(format!(
+                    "Task {}: Unknown e-prescription: {}",
+                    id, e_prescription
+                ));
+            }
+        }
+
+        if let Some(patient_receipt) = get_str(task, "/task/input/patient_receipt") {
+            if !patient_receipts.contains(patient_receipt) {
+                problems.push(format!(
+                    "Task {}: Unknown patient receipt: {}",
+                    id, patient_receipt
+                ));
+            }
+        }
+
+        if let Some(receipt) = get_str(task, "/task/output/receipt") {
+            if !erx_receipts.contains(receipt) {
+                problems.push(format!("Task {}: Unknown erx receipt: {}", id, receipt));
+            }
+        }
+    }
+
+    for communication in items(state, "communications").filter_map(communication_inner) {
+        let id = get_str(communication, "/id").unwrap_or_default();
+        let task = get_str(communication, "/based_on")
+            .and_then(|based_on| based_on.strip_prefix("Task/"))
+            .and_then(|task| task.split(|c: char| c == '/' || c == '?').next());
+
+        match task {
+            Some(task) if tasks.contains(task) => (),
+            Some(task) => problems.push(format!("Communication {}: Unknown task: {}", id, task)),
+            None => problems.push(format!("Communication {}: Missing task reference", id)),
+        }
+    }
+
+    for medication_dispense in items(state, "medication_dispenses") {
+        let id = get_str(medication_dispense, "/id").unwrap_or_default();
+
+        if let Some(prescription_id) = get_str(medication_dispense, "/prescription_id") {
+            if !prescription_ids.contains(prescription_id) {
+                problems.push(format!(
+                    "MedicationDispense {}: Unknown prescription ID: {}",
+                    id, prescription_id
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+fn collect_ids<'a>(
+    state: &'a Value,
+    key: &str,
+    pointer: &str,
+    problems: &mut Vec<String>,
+) -> HashSet<&'a str> {
+    let mut ids = HashSet::new();
+
+    for item in items(state, key) {
+        match get_str(item, pointer) {
+            Some(id) if !ids.insert(id) => problems.push(format!("{}: Duplicate ID: {}", key, id)),
+            Some(_) => (),
+            None => problems.push(format!("{}: Item without ID", key)),
+        }
+    }
+
+    ids
+}
+
+fn communication_inner(communication: &Value) -> Option<&Value> {
+    communication
+        .as_object()
+        .and_then(|communication| communication.values().next())
+}
+
+fn get_str<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+    value.pointer(pointer).and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_problems_of_state() {
+        let (state, _) = read_state(&Some("./examples/state.json".into()));
+
+        assert_eq!(
+            find_problems(&state),
+            vec![
+                "medication_dispenses: Duplicate ID: medication-dispense-1",
+                "Task task-2: Unknown e-prescription: e-prescription-2",
+                "Communication communication-2: Unknown task: task-3",
+                "MedicationDispense medication-dispense-1: Unknown prescription ID: \
+                 160.000.000.000.003.03",
+            ]
+        );
+    }
+}
@@ -33,6 +33,7 @@ mod tests {
     use super::*;
 
     use libxml::*;
+    use openssl::nid::Nid;
 
     #[test]
     fn test_simple_verify() {
@@ -46,4 +47,24 @@ mod tests {
         assert!(verified_nodes.contains(node_root, None));
         assert!(!verified_nodes.contains(node_signature, None));
     }
+
+    #[test]
+    fn test_simple_signer_cert() {
+        let doc = Doc::from_file("./examples/simple.xml").unwrap();
+        let node_root = doc.root().unwrap();
+        let cert = node_root.signer_cert().unwrap();
+
+        let serial = cert.serial_number().to_bn().unwrap().to_hex_str().unwrap();
+        assert_eq!("AFA28BB933ADDAAF", &*serial);
+
+        let common_name = cert
+            .subject_name()
+            .entries_by_nid(Nid::COMMONNAME)
+            .next()
+            .unwrap()
+            .data()
+            .as_utf8()
+            .unwrap();
+        assert_eq!("Aleksey Sanin", &*common_name);
+    }
 }
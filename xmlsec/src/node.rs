@@ -17,7 +17,7 @@
 
 use libxml::{ElementType, NodeRef};
 use openssl::{
-    pkey::{HasPublic, PKey, PKeyRef, Public},
+    pkey::{HasPublic, PKeyRef},
     x509::X509,
 };
 
@@ -29,6 +29,7 @@ use super::{
 
 pub trait Node {
     fn verify<'a>(&'a self) -> Result<Box<dyn NodeSetLike + 'a>, Error>;
+    fn signer_cert(&self) -> Result<X509, Error>;
 }
 
 macro_rules! read_xml {
@@ -129,7 +130,7 @@ impl Node for NodeRef {
         let _node_objects = read_xml!(next_vec iter, NODE_OBJECT, NAMESPACE_HREF);
         read_xml!(end iter);
 
-        let key = process_key_info(node_key_info)?;
+        let key = process_key_info(node_key_info)?.public_key()?;
         let signature = process_signature_value(node_signature_value)?;
         let (canonicalization_method, signature_method, node_references) =
             process_signed_info(node_signed_info)?;
@@ -144,6 +145,20 @@ impl Node for NodeRef {
 
         progress_references(node_references)
     }
+
+    fn signer_cert(&self) -> Result<X509, Error> {
+        let node_signature = self
+            .find_element(NODE_SIGNATURE, NAMESPACE_HREF)
+            .ok_or(Error::SignatureNodeNotFound)?;
+        let node_key_info = node_signature
+            .child_elements()
+            .find(|n| node_matches(n, NODE_KEY_INFO, NAMESPACE_HREF))
+            .ok_or_else(|| {
+                Error::InvalidSignatureNode(format!("Unable to find '{}'", NODE_KEY_INFO))
+            })?;
+
+        process_key_info(node_key_info)
+    }
 }
 
 trait NodeEx {
@@ -180,8 +195,8 @@ pub fn node_matches(node: &NodeRef, name: &str, ns_href: &str) -> bool {
 }
 
 #[allow(clippy::single_match)]
-fn process_key_info(node: &NodeRef) -> Result<PKey<Public>, Error> {
-    let mut key = None;
+fn process_key_info(node: &NodeRef) -> Result<X509, Error> {
+    let mut cert = None;
     let mut node = node.first_child().and_then(NodeRef::next_element);
     while let Some(n) = node {
         node = n.next_sibling().and_then(NodeRef::next_element);
@@ -199,25 +214,24 @@ fn process_key_info(node: &NodeRef) -> Result<PKey<Public>, Error> {
                 let node_cert = read_xml!(next iter, NODE_X509_CERTIFICATE, NAMESPACE_HREF);
                 read_xml!(end iter);
 
-                let cert = node_cert.content()?.ok_or_else(|| {
+                let content = node_cert.content()?.ok_or_else(|| {
                     Error::InvalidSignatureNode(format!(
                         "Node '{}' is missing the certificate content",
                         NODE_X509_CERTIFICATE
                     ))
                 })?;
-                let cert = format!(
+                let pem = format!(
                     "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----",
-                    cert.trim()
+                    content.trim()
                 );
-                let cert = X509::from_pem(cert.as_bytes())?;
 
-                key = Some(cert.public_key()?);
+                cert = Some(X509::from_pem(pem.as_bytes())?);
             }
             _ => (),
         }
     }
 
-    key.ok_or_else(|| Error::InvalidSignatureNode("Unable to find key".into()))
+    cert.ok_or_else(|| Error::InvalidSignatureNode("Unable to find key".into()))
 }
 
 fn process_signature_value(node: &NodeRef) -> Result<Data<'static>, Error> {